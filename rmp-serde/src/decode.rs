@@ -91,7 +91,13 @@ impl Display for Error {
             Error::InvalidMarkerRead(ref err) => write!(fmt, "IO error while reading marker: {}", err),
             Error::InvalidDataRead(ref err) => write!(fmt, "IO error while reading data: {}", err),
             Error::TypeMismatch(ref actual_marker) => {
-                write!(fmt, "wrong msgpack marker {:?}", actual_marker)
+                write!(fmt, "wrong msgpack marker {:?}", actual_marker)?;
+                // Any value may be of the wrong type, so only the reserved byte, which is never a
+                // value, hints at a misaligned stream here.
+                match actual_marker.hint() {
+                    Some(hint) if *actual_marker == Marker::Reserved => write!(fmt, " ({})", hint),
+                    _ => Ok(()),
+                }
             }
             Error::OutOfRange => fmt.write_str("numeric cast found out of range"),
             Error::LengthMismatch(expected_length) => write!(
@@ -130,6 +136,8 @@ impl From<ValueReadError> for Error {
             ValueReadError::TypeMismatch(marker) => Error::TypeMismatch(marker),
             ValueReadError::InvalidMarkerRead(err) => Error::InvalidMarkerRead(err),
             ValueReadError::InvalidDataRead(err) => Error::InvalidDataRead(err),
+            err @ ValueReadError::UnexpectedExtSize { .. } |
+            err @ ValueReadError::UnexpectedExtType { .. } => Error::Uncategorized(err.to_string()),
         }
     }
 }
@@ -231,6 +239,59 @@ impl<R: Read, C> Deserializer<ReadReader<R>, C> {
     pub fn into_inner(self) -> R {
         self.rd.rd
     }
+
+    /// Runs `f` with a deserializer constrained to the next `len` bytes, for frames whose body
+    /// length is declared upfront.
+    ///
+    /// The sub-deserializer reads through [`Read::take`], so a malformed body that tries to read
+    /// past the frame fails with an unexpected end of input rather than reading into the next
+    /// frame. Once `f` succeeds, the bytes of the frame it left unread are skipped, so this
+    /// deserializer resumes at the frame boundary. A marker already peeked by this deserializer
+    /// counts as the first byte of the frame, unless the frame is empty, in which case it stays
+    /// with this deserializer.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `f` as is, with the reader somewhere within the frame then, or
+    /// [`Error::InvalidDataRead`] if the input ends before the end of the frame.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmp_serde::Deserializer;
+    /// use serde::Deserialize;
+    ///
+    /// // A 3-byte frame holding 1 and some padding, then 2.
+    /// let buf = [0x01, 0xc0, 0xc0, 0x02];
+    /// let mut de = Deserializer::new(&buf[..]);
+    ///
+    /// let first = de.with_limit(3, |de| u8::deserialize(de)).unwrap();
+    /// let second = u8::deserialize(&mut de).unwrap();
+    /// assert_eq!((1, 2), (first, second));
+    /// ```
+    pub fn with_limit<T, F>(&mut self, len: u64, f: F) -> Result<T, Error>
+        where C: SerializerConfig,
+              F: FnOnce(&mut Deserializer<ReadReader<io::Take<&mut R>>, C>) -> Result<T, Error>
+    {
+        let marker = if len > 0 { self.marker.take() } else { None };
+        let limit = if marker.is_some() { len.saturating_sub(1) } else { len };
+        let mut de = Deserializer {
+            rd: ReadReader::new(Read::take(&mut self.rd.rd, limit)),
+            config: self.config,
+            marker,
+            depth: self.depth,
+        };
+
+        let val = f(&mut de)?;
+
+        let rest = &mut de.rd.rd;
+        let remaining = rest.limit();
+        if io::copy(rest, &mut io::sink()).map_err(Error::InvalidDataRead)? < remaining {
+            return Err(Error::InvalidDataRead(ErrorKind::UnexpectedEof.into()));
+        }
+
+        Ok(val)
+    }
 }
 
 impl<R: Read, C: SerializerConfig> Deserializer<R, C> {
@@ -29,6 +29,32 @@ fn fail_nil_from_reserved() {
     }
 }
 
+#[test]
+fn fail_reserved_display_hint() {
+    let buf = [0xc1];
+    let mut de = Deserializer::new(&buf[..]);
+
+    let err = <u8 as Deserialize>::deserialize(&mut de).unwrap_err();
+    assert_eq!(
+        "wrong msgpack marker Reserved (byte 0xc1 is reserved; the stream may be misaligned or truncated)",
+        err.to_string()
+    );
+}
+
+#[test]
+fn fail_zero_display_no_hint() {
+    // A legitimate 0 where a u128, which is encoded as a bin8, is expected isn't a sign of
+    // misalignment.
+    let buf = [0x00];
+    let mut de = Deserializer::new(&buf[..]);
+
+    let err = <u128 as Deserialize>::deserialize(&mut de).unwrap_err();
+    assert_eq!("wrong msgpack marker FixPos(0)", err.to_string());
+
+    assert_eq!("wrong msgpack marker FixNeg(-1)", Error::TypeMismatch(Marker::FixNeg(-1)).to_string());
+    assert_eq!("wrong msgpack marker FixPos(42)", Error::TypeMismatch(Marker::FixPos(42)).to_string());
+}
+
 #[test]
 fn pass_bool() {
     let buf = [0xc3, 0xc2];
@@ -569,3 +595,53 @@ fn fail_depth_limit() {
         other => panic!("unexpected result: {:?}", other),
     }
 }
+
+#[test]
+fn fail_with_limit_over_read() {
+    // A 3-byte frame whose body declares a 4-element array, then the next frame.
+    let buf = [0x94, 0x01, 0x02, 0x91, 0x03];
+    let mut cur = Cursor::new(&buf[..]);
+    let mut de = Deserializer::new(&mut cur);
+
+    match de.with_limit(3, |de| Vec::<u8>::deserialize(de)) {
+        Err(Error::InvalidMarkerRead(err)) => assert_eq!(std::io::ErrorKind::UnexpectedEof, err.kind()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    // Nothing past the frame has been read.
+    assert_eq!(3, cur.position());
+}
+
+#[test]
+fn pass_with_limit_resumes_at_frame_boundary() {
+    // A 4-byte frame holding [1] and trailing bytes, then the next frame.
+    let buf = [0x91, 0x01, 0xc0, 0xc0, 0x91, 0x02];
+    let mut de = Deserializer::new(Cursor::new(&buf[..]));
+
+    assert_eq!(vec![1], de.with_limit(4, |de| Vec::<u8>::deserialize(de)).unwrap());
+    assert_eq!(4, de.position());
+    assert_eq!(vec![2], de.with_limit(2, |de| Vec::<u8>::deserialize(de)).unwrap());
+}
+
+#[test]
+fn fail_with_limit_truncated_frame() {
+    let buf = [0x01];
+    let mut de = Deserializer::new(&buf[..]);
+
+    match de.with_limit(3, |de| u8::deserialize(de)) {
+        Err(Error::InvalidDataRead(err)) => assert_eq!(std::io::ErrorKind::UnexpectedEof, err.kind()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn pass_with_limit_empty_frame() {
+    let buf = [0x01];
+    let mut de = Deserializer::new(&buf[..]);
+
+    match de.with_limit(0, |de| u8::deserialize(de)) {
+        Err(Error::InvalidMarkerRead(err)) => assert_eq!(std::io::ErrorKind::UnexpectedEof, err.kind()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    de.with_limit(0, |_| Ok(())).unwrap();
+    assert_eq!(1, u8::deserialize(&mut de).unwrap());
+}
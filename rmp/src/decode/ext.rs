@@ -1,5 +1,6 @@
-use crate::Marker;
-use super::{read_marker, RmpRead, ValueReadError};
+use crate::{Marker, I128_EXT_TYPE, U128_EXT_TYPE};
+use super::bytes::{Bytes, BytesReadError};
+use super::{read_marker, RmpRead, RmpReadErr, ValueReadError};
 
 /// Attempts to read exactly 3 bytes from the given reader and interpret them as a fixext1 type
 /// with data attached.
@@ -14,6 +15,8 @@ use super::{read_marker, RmpRead, ValueReadError};
 /// This function will return `ValueReadError` on any I/O error while reading either the marker or
 /// the data.
 ///
+/// It returns `ValueReadError::UnexpectedExtSize` if the marker is a fixext of another size.
+///
 /// # Note
 ///
 /// This function will silently retry on every EINTR received from the underlying `Read` until
@@ -25,7 +28,7 @@ pub fn read_fixext1<R: RmpRead>(rd: &mut R) -> Result<(i8, u8), ValueReadError<R
             let data = rd.read_data_u8()?;
             Ok((ty, data))
         }
-        marker => Err(ValueReadError::TypeMismatch(marker)),
+        marker => Err(unexpected_fixext(marker, 1)),
     }
 }
 
@@ -42,13 +45,15 @@ pub fn read_fixext1<R: RmpRead>(rd: &mut R) -> Result<(i8, u8), ValueReadError<R
 ///
 /// This function will return `ValueReadError` on any I/O error while reading either the marker or
 /// the data.
+///
+/// It returns `ValueReadError::UnexpectedExtSize` if the marker is a fixext of another size.
 pub fn read_fixext2<R: RmpRead>(rd: &mut R) -> Result<(i8, [u8; 2]), ValueReadError<R::Error>> {
     match read_marker(rd)? {
         Marker::FixExt2 => {
             let mut buf = [0; 2];
             read_fixext_data(rd, &mut buf).map(|ty| (ty, buf))
         }
-        marker => Err(ValueReadError::TypeMismatch(marker)),
+        marker => Err(unexpected_fixext(marker, 2)),
     }
 }
 
@@ -65,13 +70,15 @@ pub fn read_fixext2<R: RmpRead>(rd: &mut R) -> Result<(i8, [u8; 2]), ValueReadEr
 ///
 /// This function will return `ValueReadError` on any I/O error while reading either the marker or
 /// the data.
+///
+/// It returns `ValueReadError::UnexpectedExtSize` if the marker is a fixext of another size.
 pub fn read_fixext4<R: RmpRead>(rd: &mut R) -> Result<(i8, [u8; 4]), ValueReadError<R::Error>> {
     match read_marker(rd)? {
         Marker::FixExt4 => {
             let mut buf = [0; 4];
             read_fixext_data(rd, &mut buf).map(|ty| (ty, buf))
         }
-        marker => Err(ValueReadError::TypeMismatch(marker)),
+        marker => Err(unexpected_fixext(marker, 4)),
     }
 }
 
@@ -88,13 +95,15 @@ pub fn read_fixext4<R: RmpRead>(rd: &mut R) -> Result<(i8, [u8; 4]), ValueReadEr
 ///
 /// This function will return `ValueReadError` on any I/O error while reading either the marker or
 /// the data.
+///
+/// It returns `ValueReadError::UnexpectedExtSize` if the marker is a fixext of another size.
 pub fn read_fixext8<R: RmpRead>(rd: &mut R) -> Result<(i8, [u8; 8]), ValueReadError<R::Error>> {
     match read_marker(rd)? {
         Marker::FixExt8 => {
             let mut buf = [0; 8];
             read_fixext_data(rd, &mut buf).map(|ty| (ty, buf))
         }
-        marker => Err(ValueReadError::TypeMismatch(marker)),
+        marker => Err(unexpected_fixext(marker, 8)),
     }
 }
 
@@ -111,13 +120,132 @@ pub fn read_fixext8<R: RmpRead>(rd: &mut R) -> Result<(i8, [u8; 8]), ValueReadEr
 ///
 /// This function will return `ValueReadError` on any I/O error while reading either the marker or
 /// the data.
+///
+/// It returns `ValueReadError::UnexpectedExtSize` if the marker is a fixext of another size.
 pub fn read_fixext16<R: RmpRead>(rd: &mut R) -> Result<(i8, [u8; 16]), ValueReadError<R::Error>> {
     match read_marker(rd)? {
         Marker::FixExt16 => {
             let mut buf = [0; 16];
             read_fixext_data(rd, &mut buf).map(|ty| (ty, buf))
         }
-        marker => Err(ValueReadError::TypeMismatch(marker)),
+        marker => Err(unexpected_fixext(marker, 16)),
+    }
+}
+
+/// Attempts to read a fixext of any size from the given slice, returning its type and its payload
+/// borrowed from the slice.
+///
+/// Unlike [`read_fixext4`], [`read_fixext8`] and [`read_fixext16`], this doesn't copy the payload.
+/// The fixext takes the first `2 + data.len()` bytes of the slice; the rest isn't read.
+///
+/// # Errors
+///
+/// This function will return `ValueReadError::InvalidMarkerRead` or
+/// `ValueReadError::InvalidDataRead` if the slice ends before the fixext does, and
+/// `ValueReadError::TypeMismatch` if the marker isn't a fixext.
+///
+/// # Examples
+///
+/// ```
+/// let buf = [0xd6, 0x01, 0x0a, 0x0b, 0x0c, 0x0d, 0xc0];
+///
+/// let (ty, data) = rmp::decode::read_fixext_ref(&buf).unwrap();
+///
+/// assert_eq!(1, ty);
+/// assert_eq!(&buf[2..6], data);
+/// ```
+pub fn read_fixext_ref(rd: &[u8]) -> Result<(i8, &[u8]), ValueReadError<BytesReadError>> {
+    let mut cur = Bytes::new(rd);
+    let marker = read_marker(&mut cur)?;
+    let len = fixext_len(marker).ok_or(ValueReadError::TypeMismatch(marker))? as usize;
+    let ty = cur.read_data_i8()?;
+
+    let data = cur.remaining_slice();
+    if data.len() < len {
+        return Err(ValueReadError::InvalidDataRead(BytesReadError::InsufficientBytes {
+            expected: len,
+            actual: data.len(),
+            position: cur.position(),
+        }));
+    }
+
+    Ok((ty, &data[..len]))
+}
+
+/// Reads a fixext16 of the given type, as written for 128-bit integers.
+fn read_int128_ext<R: RmpRead>(rd: &mut R, expected: i8) -> Result<[u8; 16], ValueReadError<R::Error>> {
+    match read_fixext16(rd)? {
+        (ty, buf) if ty == expected => Ok(buf),
+        (found, _) => Err(ValueReadError::UnexpectedExtType { expected, found }),
+    }
+}
+
+/// Attempts to read exactly 18 bytes from the given reader and interpret them as an `i128`, as
+/// written by [`write_i128_ext`](crate::encode::write_i128_ext).
+///
+/// # Errors
+///
+/// This function will return `ValueReadError` on any I/O error while reading either the marker or
+/// the data.
+///
+/// It returns `ValueReadError::UnexpectedExtSize` if the marker is a fixext of another size, and
+/// `ValueReadError::UnexpectedExtType` if the ext type isn't [`I128_EXT_TYPE`].
+///
+/// # Examples
+/// ```
+/// let mut buf = Vec::new();
+/// rmp::encode::write_i128_ext(&mut buf, i128::MIN).unwrap();
+///
+/// assert_eq!(i128::MIN, rmp::decode::read_i128_ext(&mut &buf[..]).unwrap());
+/// ```
+pub fn read_i128_ext<R: RmpRead>(rd: &mut R) -> Result<i128, ValueReadError<R::Error>> {
+    read_int128_ext(rd, I128_EXT_TYPE).map(i128::from_be_bytes)
+}
+
+/// Attempts to read exactly 18 bytes from the given reader and interpret them as a `u128`, as
+/// written by [`write_u128_ext`](crate::encode::write_u128_ext).
+///
+/// # Errors
+///
+/// This function will return `ValueReadError` on any I/O error while reading either the marker or
+/// the data.
+///
+/// It returns `ValueReadError::UnexpectedExtSize` if the marker is a fixext of another size, and
+/// `ValueReadError::UnexpectedExtType` if the ext type isn't [`U128_EXT_TYPE`].
+pub fn read_u128_ext<R: RmpRead>(rd: &mut R) -> Result<u128, ValueReadError<R::Error>> {
+    read_int128_ext(rd, U128_EXT_TYPE).map(u128::from_be_bytes)
+}
+
+/// Returns the size of the payload carried by a fixext marker.
+fn fixext_len(marker: Marker) -> Option<u32> {
+    match marker {
+        Marker::FixExt1 => Some(1),
+        Marker::FixExt2 => Some(2),
+        Marker::FixExt4 => Some(4),
+        Marker::FixExt8 => Some(8),
+        Marker::FixExt16 => Some(16),
+        _ => None,
+    }
+}
+
+/// Returns the fixext marker for the given payload size.
+pub(crate) fn fixext_marker(len: u32) -> Marker {
+    match len {
+        1 => Marker::FixExt1,
+        2 => Marker::FixExt2,
+        4 => Marker::FixExt4,
+        8 => Marker::FixExt8,
+        16 => Marker::FixExt16,
+        _ => Marker::Reserved,
+    }
+}
+
+/// Builds the error returned by a reader handling fixexts of `expected` bytes that got `marker`.
+#[cold]
+fn unexpected_fixext<E: RmpReadErr>(marker: Marker, expected: u32) -> ValueReadError<E> {
+    match fixext_len(marker) {
+        Some(found) => ValueReadError::UnexpectedExtSize { expected, found },
+        None => ValueReadError::TypeMismatch(marker),
     }
 }
 
@@ -17,12 +17,18 @@ mod uint;
 
 pub use self::dec::{read_f32, read_f64};
 pub use self::ext::{
-    read_ext_meta, read_fixext1, read_fixext16, read_fixext2, read_fixext4, read_fixext8, ExtMeta,
+    read_ext_meta, read_fixext1, read_fixext16, read_fixext2, read_fixext4, read_fixext8, read_fixext_ref,
+    read_i128_ext, read_u128_ext, ExtMeta,
 };
 pub use self::sint::{read_i16, read_i32, read_i64, read_i8, read_nfix};
 #[allow(deprecated)]
 // While we re-export deprecated items, we don't want to trigger warnings while compiling this crate
-pub use self::str::{read_str, read_str_from_slice, read_str_len, read_str_ref, DecodeStringError};
+pub use self::str::{
+    read_char, read_str, read_str_from_slice, read_str_len, read_str_ref, read_str_with_len,
+    DecodeCharError, DecodeStringError,
+};
+#[cfg(feature = "std")]
+pub use self::str::read_str_retryable;
 pub use self::uint::{read_pfix, read_u16, read_u32, read_u64, read_u8};
 
 #[cfg(feature = "std")]
@@ -32,6 +38,7 @@ use core::fmt::{self, Display, Debug, Formatter};
 use num_traits::cast::FromPrimitive;
 
 use crate::Marker;
+use alloc::vec::Vec;
 
 pub mod bytes;
 pub use bytes::Bytes;
@@ -187,6 +194,21 @@ impl<E: RmpReadErr> From<E> for MarkerWriteError<E> {
 #[allow(deprecated)] // Needed for backwards compat
 pub struct MarkerReadError<E: RmpReadErr = Error>(pub E);
 
+#[cfg(feature = "std")]
+impl<E: RmpReadErr> error::Error for MarkerReadError<E> {
+    #[cold]
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.0)
+    }
+}
+
+impl<E: RmpReadErr> Display for MarkerReadError<E> {
+    #[cold]
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "failed to read MessagePack marker: {}", self.0)
+    }
+}
+
 /// An error which can occur when attempting to read a MessagePack value from the reader.
 #[derive(Debug)]
 #[allow(deprecated)] // Needed for backwards compat
@@ -197,6 +219,20 @@ pub enum ValueReadError<E: RmpReadErr = Error> {
     InvalidDataRead(E),
     /// The type decoded isn't match with the expected one.
     TypeMismatch(Marker),
+    /// A fixext was found, but its size differs from the one the reader handles.
+    UnexpectedExtSize {
+        /// The size the reader handles.
+        expected: u32,
+        /// The size of the fixext actually found.
+        found: u32,
+    },
+    /// An ext was found, but its type differs from the one the reader handles.
+    UnexpectedExtType {
+        /// The type the reader handles.
+        expected: i8,
+        /// The type of the ext actually found.
+        found: i8,
+    },
 }
 
 #[cfg(feature = "std")]
@@ -206,7 +242,9 @@ impl error::Error for ValueReadError {
         match *self {
             ValueReadError::InvalidMarkerRead(ref err) |
             ValueReadError::InvalidDataRead(ref err) => Some(err),
-            ValueReadError::TypeMismatch(..) => None,
+            ValueReadError::TypeMismatch(..) |
+            ValueReadError::UnexpectedExtSize { .. } |
+            ValueReadError::UnexpectedExtType { .. } => None,
         }
     }
 }
@@ -221,6 +259,12 @@ impl Display for ValueReadError {
             ValueReadError::TypeMismatch(..) => {
                 "the type decoded isn't match with the expected one"
             }
+            ValueReadError::UnexpectedExtSize { expected, found } => {
+                return write!(f, "expected a fixext of {} bytes, found {} bytes", expected, found);
+            }
+            ValueReadError::UnexpectedExtType { expected, found } => {
+                return write!(f, "expected an ext of type {}, found type {}", expected, found);
+            }
         })
     }
 }
@@ -350,6 +394,11 @@ impl<E: RmpReadErr> From<ValueReadError<E>> for NumValueReadError<E> {
             ValueReadError::InvalidMarkerRead(err) => NumValueReadError::InvalidMarkerRead(err),
             ValueReadError::InvalidDataRead(err) => NumValueReadError::InvalidDataRead(err),
             ValueReadError::TypeMismatch(err) => NumValueReadError::TypeMismatch(err),
+            ValueReadError::UnexpectedExtSize { found, .. } => {
+                NumValueReadError::TypeMismatch(self::ext::fixext_marker(found))
+            }
+            // Only the 128-bit integer readers check the type, and they read fixext16.
+            ValueReadError::UnexpectedExtType { .. } => NumValueReadError::TypeMismatch(Marker::FixExt16),
         }
     }
 }
@@ -362,6 +411,10 @@ impl<E: RmpReadErr> From<ValueReadError<E>> for NumValueReadError<E> {
 /// Unlike `read_*`, this function weakens type restrictions, allowing you to safely decode packed
 /// values even if you aren't sure about the actual integral type.
 ///
+/// Every integer marker is accepted regardless of its signedness, so a positive fixnum or an
+/// unsigned integer can be read as a signed `T` and a non-negative signed integer as an unsigned
+/// one, as long as the value itself fits.
+///
 /// # Errors
 ///
 /// This function will return `NumValueReadError` on any I/O error while reading either the marker
@@ -402,6 +455,35 @@ pub fn read_int<T: FromPrimitive, R: RmpRead>(rd: &mut R) -> Result<T, NumValueR
     val.ok_or(NumValueReadError::OutOfRange)
 }
 
+/// Attempts to read an array of integers, decoding every element with [`read_int`].
+///
+/// This is the counterpart of [`write_int_array`](crate::encode::write_int_array).
+///
+/// # Errors
+///
+/// Returns `NumValueReadError::TypeMismatch` if the value is not an array or one of its elements is
+/// not an integer, and `NumValueReadError::OutOfRange` if an element doesn't fit in `T`.
+///
+/// # Examples
+///
+/// ```
+/// let buf = [0x93, 0x01, 0xff, 0xcd, 0x01, 0x2c];
+///
+/// assert_eq!(vec![1i64, -1, 300], rmp::decode::read_int_array(&mut &buf[..]).unwrap());
+/// ```
+pub fn read_int_array<T: FromPrimitive, R: RmpRead>(rd: &mut R) -> Result<Vec<T>, NumValueReadError<R::Error>> {
+    let len = read_array_len(rd)?;
+
+    // Note: Do not trust the length prefix for preallocation.
+    // See https://github.com/3Hren/msgpack-rust/issues/151
+    let mut vec = Vec::with_capacity(core::cmp::min(len as usize, 1024));
+    for _ in 0..len {
+        vec.push(read_int(rd)?);
+    }
+
+    Ok(vec)
+}
+
 /// Attempts to read up to 5 bytes from the given reader and to decode them as a big-endian u32
 /// array size.
 ///
@@ -419,8 +501,8 @@ where
     R: RmpRead,
 {
     match read_marker(rd)? {
-        Marker::FixArray(size) => Ok(size as u32),
-        Marker::Array16 => Ok(rd.read_data_u16()? as u32),
+        Marker::FixArray(size) => Ok(u32::from(size)),
+        Marker::Array16 => Ok(u32::from(rd.read_data_u16()?)),
         Marker::Array32 => Ok(rd.read_data_u32()?),
         marker => Err(ValueReadError::TypeMismatch(marker)),
     }
@@ -444,8 +526,8 @@ pub fn read_map_len<R: RmpRead>(rd: &mut R) -> Result<u32, ValueReadError<R::Err
 
 pub fn marker_to_len<R: RmpRead>(rd: &mut R, marker: Marker) -> Result<u32, ValueReadError<R::Error>> {
     match marker {
-        Marker::FixMap(size) => Ok(size as u32),
-        Marker::Map16 => Ok(rd.read_data_u16()? as u32),
+        Marker::FixMap(size) => Ok(u32::from(size)),
+        Marker::Map16 => Ok(u32::from(rd.read_data_u16()?)),
         Marker::Map32 => Ok(rd.read_data_u32()?),
         marker => Err(ValueReadError::TypeMismatch(marker)),
     }
@@ -45,6 +45,63 @@ impl<'a, E: RmpReadErr> From<ValueReadError<E>> for DecodeStringError<'a, E> {
             ValueReadError::InvalidMarkerRead(err) => DecodeStringError::InvalidMarkerRead(err),
             ValueReadError::InvalidDataRead(err) => DecodeStringError::InvalidDataRead(err),
             ValueReadError::TypeMismatch(marker) => DecodeStringError::TypeMismatch(marker),
+            ValueReadError::UnexpectedExtSize { found, .. } => {
+                DecodeStringError::TypeMismatch(super::ext::fixext_marker(found))
+            }
+            ValueReadError::UnexpectedExtType { .. } => DecodeStringError::TypeMismatch(Marker::FixExt16),
+        }
+    }
+}
+
+/// An error which can occur when attempting to read a single character from the reader.
+#[derive(Debug)]
+#[allow(deprecated)] // Only for compatibility
+pub enum DecodeCharError<E: RmpReadErr = super::Error> {
+    InvalidMarkerRead(E),
+    InvalidDataRead(E),
+    TypeMismatch(Marker),
+    InvalidUtf8(Utf8Error),
+    /// The string is not exactly one Unicode scalar value. Contains the string's byte length.
+    NotSingleChar(u32),
+}
+
+#[cfg(feature = "std")]
+impl<E: RmpReadErr> error::Error for DecodeCharError<E> {
+    #[cold]
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            DecodeCharError::InvalidMarkerRead(ref err) |
+            DecodeCharError::InvalidDataRead(ref err) => Some(err),
+            DecodeCharError::TypeMismatch(..) |
+            DecodeCharError::NotSingleChar(..) => None,
+            DecodeCharError::InvalidUtf8(ref err) => Some(err),
+        }
+    }
+}
+
+impl<E: RmpReadErr> Display for DecodeCharError<E> {
+    #[cold]
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match *self {
+            DecodeCharError::NotSingleChar(len) => {
+                write!(f, "expected a single character, got a string of {} bytes", len)
+            }
+            _ => f.write_str("error while decoding character"),
+        }
+    }
+}
+
+impl<E: RmpReadErr> From<ValueReadError<E>> for DecodeCharError<E> {
+    #[cold]
+    fn from(err: ValueReadError<E>) -> DecodeCharError<E> {
+        match err {
+            ValueReadError::InvalidMarkerRead(err) => DecodeCharError::InvalidMarkerRead(err),
+            ValueReadError::InvalidDataRead(err) => DecodeCharError::InvalidDataRead(err),
+            ValueReadError::TypeMismatch(marker) => DecodeCharError::TypeMismatch(marker),
+            ValueReadError::UnexpectedExtSize { found, .. } => {
+                DecodeCharError::TypeMismatch(super::ext::fixext_marker(found))
+            }
+            ValueReadError::UnexpectedExtType { .. } => DecodeCharError::TypeMismatch(Marker::FixExt16),
         }
     }
 }
@@ -62,11 +119,25 @@ impl<'a, E: RmpReadErr> From<ValueReadError<E>> for DecodeStringError<'a, E> {
 ///
 /// It also returns `ValueReadError::TypeMismatch` if the actual type is not equal with the
 /// expected one, indicating you with the actual type.
+///
+/// # Note
+///
+/// A `Str32` length may not fit in `usize` on 16-bit targets, so avoid converting it with
+/// `as usize` before checking it against the space actually available.
 #[inline]
 pub fn read_str_len<R: RmpRead>(rd: &mut R) -> Result<u32, ValueReadError<R::Error>> {
     Ok(read_str_len_with_nread(rd)?.0)
 }
 
+/// Checks whether `len` bytes fit into `available` ones.
+///
+/// The comparison is done in `u64`, so that a `u32` length can't be truncated on targets where
+/// `usize` is narrower than 32 bits.
+#[inline]
+fn fits_in(available: usize, len: u32) -> bool {
+    available as u64 >= u64::from(len)
+}
+
 fn read_str_len_with_nread<R>(rd: &mut R) -> Result<(u32, usize), ValueReadError<R::Error>>
     where R: RmpRead
 {
@@ -85,6 +156,9 @@ fn read_str_len_with_nread<R>(rd: &mut R) -> Result<(u32, usize), ValueReadError
 /// string.
 /// According to the spec, the string's data must to be encoded using utf-8.
 ///
+/// On success the reader is positioned exactly after the string's payload, so the next value can
+/// be read from it right away. Nothing beyond the payload is read speculatively.
+///
 /// # Errors
 ///
 /// Returns `Err` in the following cases:
@@ -114,13 +188,120 @@ where
     R: RmpRead,
 {
     let len = read_str_len(rd)?;
-    let ulen = len as usize;
 
-    if buf.len() < ulen {
+    if !fits_in(buf.len(), len) {
         return Err(DecodeStringError::BufferSizeTooSmall(len));
     }
 
-    read_str_data(rd, len, &mut buf[0..ulen])
+    read_str_data(rd, len, &mut buf[0..len as usize])
+}
+
+/// Same as [`read_str`], but also returns the number of payload bytes read, which is the byte length
+/// of the string rather than its number of characters.
+///
+/// The header isn't counted, so the total number of bytes consumed from the reader is larger.
+///
+/// # Errors
+///
+/// Returns the same errors as [`read_str`].
+///
+/// # Examples
+/// ```
+/// use rmp::decode::read_str_with_len;
+///
+/// // "€1"
+/// let buf = [0xa4, 0xe2, 0x82, 0xac, 0x31];
+/// let mut out = [0u8; 16];
+///
+/// assert_eq!(("€1", 4), read_str_with_len(&mut &buf[..], &mut out).unwrap());
+/// ```
+pub fn read_str_with_len<'r, R>(rd: &mut R, buf: &'r mut [u8]) -> Result<(&'r str, usize), DecodeStringError<'r, R::Error>>
+where
+    R: RmpRead,
+{
+    let s = read_str(rd, buf)?;
+    Ok((s, s.len()))
+}
+
+/// Same as [`read_str`], but leaves the reader where it was if the buffer is too small, so that
+/// the read can be retried with a larger one.
+///
+/// On `DecodeStringError::BufferSizeTooSmall` the reader is seeked back before the string's marker,
+/// which [`read_str`] has no way to do. On success the reader is positioned exactly after the
+/// string's payload; on any other error its position is unspecified.
+///
+/// # Errors
+///
+/// Returns the same errors as [`read_str`], and `DecodeStringError::InvalidDataRead` if seeking
+/// back fails.
+///
+/// # Examples
+/// ```
+/// use std::io::Cursor;
+///
+/// use rmp::decode::{read_str_retryable, DecodeStringError};
+///
+/// let mut cur = Cursor::new([0xa3, 0x72, 0x6d, 0x70]);
+///
+/// let mut out = vec![0; 2];
+/// let len = match read_str_retryable(&mut cur, &mut out) {
+///     Err(DecodeStringError::BufferSizeTooSmall(len)) => len,
+///     other => panic!("unexpected result: {:?}", other),
+/// };
+/// assert_eq!(0, cur.position());
+///
+/// let mut out = vec![0; len as usize];
+/// assert_eq!("rmp", read_str_retryable(&mut cur, &mut out).unwrap());
+/// ```
+#[cfg(feature = "std")]
+pub fn read_str_retryable<'r, R>(rd: &mut R, buf: &'r mut [u8]) -> Result<&'r str, DecodeStringError<'r, std::io::Error>>
+where
+    R: std::io::Read + std::io::Seek,
+{
+    let (len, nread) = read_str_len_with_nread(rd)?;
+
+    if !fits_in(buf.len(), len) {
+        rd.seek(std::io::SeekFrom::Current(-(nread as i64))).map_err(DecodeStringError::InvalidDataRead)?;
+        return Err(DecodeStringError::BufferSizeTooSmall(len));
+    }
+
+    read_str_data(rd, len, &mut buf[0..len as usize])
+}
+
+/// Attempts to read a string consisting of exactly one Unicode scalar value and to decode it as
+/// a `char`.
+///
+/// Such a string occupies at most 4 bytes of payload, so no external buffer is required.
+///
+/// # Errors
+///
+/// Returns `DecodeCharError::NotSingleChar` with the string's byte length if it is empty or
+/// contains more than one character. Strings longer than 4 bytes are rejected without reading
+/// their payload.
+///
+/// # Examples
+/// ```
+/// use rmp::decode::read_char;
+///
+/// let buf = [0xa3, 0xe2, 0x82, 0xac];
+///
+/// assert_eq!('€', read_char(&mut &buf[..]).unwrap());
+/// ```
+pub fn read_char<R: RmpRead>(rd: &mut R) -> Result<char, DecodeCharError<R::Error>> {
+    let len = read_str_len(rd)?;
+    if len == 0 || len > 4 {
+        return Err(DecodeCharError::NotSingleChar(len));
+    }
+
+    let mut buf = [0u8; 4];
+    let buf = &mut buf[..len as usize];
+    rd.read_exact_buf(buf).map_err(DecodeCharError::InvalidDataRead)?;
+
+    let mut chars = from_utf8(buf).map_err(DecodeCharError::InvalidUtf8)?.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) => Ok(ch),
+        _ => Err(DecodeCharError::NotSingleChar(len)),
+    }
 }
 
 pub fn read_str_data<'r, R>(rd: &mut R,
@@ -178,10 +359,9 @@ pub fn read_str_from_slice<T: ?Sized + AsRef<[u8]>>(
 ) -> Result<(&str, &[u8]), DecodeStringError<'_, super::bytes::BytesReadError>> {
     let buf = buf.as_ref();
     let (len, nread) = read_str_len_with_nread(&mut super::Bytes::new(buf))?;
-    let ulen = len as usize;
 
-    if buf[nread..].len() >= ulen {
-        let (head, tail) = buf.split_at(nread + ulen);
+    if fits_in(buf[nread..].len(), len) {
+        let (head, tail) = buf.split_at(nread + len as usize);
         match from_utf8(&head[nread..]) {
             Ok(val) => Ok((val, tail)),
             Err(err) => Err(DecodeStringError::InvalidUtf8(buf, err)),
@@ -8,6 +8,9 @@ use crate::Marker;
 /// This function is useful when you want to get full control for writing the data itself, for
 /// example, when using non-blocking socket.
 ///
+/// Only the header is written. The caller is responsible for writing exactly `len` bytes
+/// afterwards.
+///
 /// # Errors
 ///
 /// This function will return `ValueWriteError` on any I/O error occurred while writing either the
@@ -40,3 +43,36 @@ pub fn write_bin<W: RmpWrite>(wr: &mut W, data: &[u8]) -> Result<(), ValueWriteE
     wr.write_bytes(data)
         .map_err(ValueWriteError::InvalidDataWrite)
 }
+
+/// Encodes and attempts to write a binary of `len` bytes whose payload is copied from `src`, without
+/// buffering it.
+///
+/// Exactly `len` bytes are read from `src`; anything after them is left in it.
+///
+/// # Errors
+///
+/// This function will return `ValueWriteError` on any I/O error occurred while writing the
+/// marker or copying the data, including `ValueWriteError::InvalidDataWrite` with the
+/// `UnexpectedEof` kind if `src` yields fewer than `len` bytes. The header and the bytes copied so
+/// far have been written then.
+///
+/// # Examples
+///
+/// ```
+/// let mut src = std::io::Cursor::new([1, 2, 3, 4]);
+/// let mut buf = Vec::new();
+///
+/// rmp::encode::write_bin_from_reader(&mut buf, 3, &mut src).unwrap();
+///
+/// assert_eq!(vec![0xc4, 0x03, 0x01, 0x02, 0x03], buf);
+/// assert_eq!(3, src.position());
+/// ```
+#[cfg(feature = "std")]
+pub fn write_bin_from_reader<W, R>(wr: &mut W, len: u32, src: &mut R) -> Result<(), ValueWriteError<std::io::Error>>
+where
+    W: std::io::Write,
+    R: std::io::Read,
+{
+    write_bin_len(wr, len)?;
+    super::copy_payload(wr, len, src)
+}
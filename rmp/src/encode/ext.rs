@@ -1 +1,61 @@
+use super::{write_ext_meta, RmpWrite, ValueWriteError};
+use crate::{I128_EXT_TYPE, U128_EXT_TYPE};
 
+/// Encodes and attempts to write an extension with the given type and payload into the given
+/// write.
+///
+/// The most compact header for the payload's size is used: a fixext if there is one of exactly
+/// that size, and an ext8, ext16 or ext32 otherwise.
+///
+/// # Errors
+///
+/// This function will return `ValueWriteError` on any I/O error occurred while writing either the
+/// header or the payload.
+///
+/// # Examples
+///
+/// ```
+/// let mut buf = Vec::new();
+///
+/// rmp::encode::write_ext(&mut buf, 42, &[1, 2, 3]).unwrap();
+///
+/// assert_eq!(vec![0xc7, 0x03, 0x2a, 0x01, 0x02, 0x03], buf);
+/// ```
+pub fn write_ext<W: RmpWrite>(wr: &mut W, typeid: i8, data: &[u8]) -> Result<(), ValueWriteError<W::Error>> {
+    write_ext_meta(wr, data.len() as u32, typeid)?;
+    wr.write_bytes(data)
+        .map_err(ValueWriteError::InvalidDataWrite)
+}
+
+/// Encodes and attempts to write an `i128` as a fixext16 of type [`I128_EXT_TYPE`], with the
+/// integer in big-endian as payload.
+///
+/// # Errors
+///
+/// This function will return `ValueWriteError` on any I/O error occurred while writing either the
+/// header or the payload.
+///
+/// # Examples
+///
+/// ```
+/// let mut buf = Vec::new();
+///
+/// rmp::encode::write_i128_ext(&mut buf, -1).unwrap();
+///
+/// assert_eq!([0xd8, 0x64], buf[..2]);
+/// assert_eq!([0xff; 16], buf[2..]);
+/// ```
+pub fn write_i128_ext<W: RmpWrite>(wr: &mut W, val: i128) -> Result<(), ValueWriteError<W::Error>> {
+    write_ext(wr, I128_EXT_TYPE, &val.to_be_bytes())
+}
+
+/// Encodes and attempts to write a `u128` as a fixext16 of type [`U128_EXT_TYPE`], with the
+/// integer in big-endian as payload.
+///
+/// # Errors
+///
+/// This function will return `ValueWriteError` on any I/O error occurred while writing either the
+/// header or the payload.
+pub fn write_u128_ext<W: RmpWrite>(wr: &mut W, val: u128) -> Result<(), ValueWriteError<W::Error>> {
+    write_ext(wr, U128_EXT_TYPE, &val.to_be_bytes())
+}
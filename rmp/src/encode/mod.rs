@@ -10,10 +10,16 @@ mod uint;
 mod vec;
 
 pub use self::bin::{write_bin, write_bin_len};
+#[cfg(feature = "std")]
+pub use self::bin::write_bin_from_reader;
 pub use self::dec::{write_f32, write_f64};
+pub use self::ext::{write_ext, write_i128_ext, write_u128_ext};
 pub use self::sint::{write_i16, write_i32, write_i64, write_i8, write_nfix, write_sint};
 pub use self::str::{write_str, write_str_len};
+#[cfg(feature = "std")]
+pub use self::str::write_str_from_reader;
 pub use self::uint::{write_pfix, write_u16, write_u32, write_u64, write_u8, write_uint};
+pub use self::vec::{write_bool_array, write_int_array};
 
 #[cfg(feature = "std")]
 use std::error;
@@ -194,6 +200,25 @@ impl<T: std::io::Write> RmpWrite for T {
     }
 }
 
+/// Copies exactly `len` bytes from `src` to `wr`, as the payload of a value whose header has
+/// already been written.
+#[cfg(feature = "std")]
+fn copy_payload<W, R>(wr: &mut W, len: u32, src: &mut R) -> Result<(), ValueWriteError<std::io::Error>>
+where
+    W: std::io::Write,
+    R: std::io::Read,
+{
+    let copied = std::io::copy(&mut std::io::Read::take(src, u64::from(len)), wr).map_err(ValueWriteError::InvalidDataWrite)?;
+    if copied < u64::from(len) {
+        return Err(ValueWriteError::InvalidDataWrite(std::io::Error::new(
+            std::io::ErrorKind::UnexpectedEof,
+            "source ended before the declared length",
+        )));
+    }
+
+    Ok(())
+}
+
 /// An error that can occur when attempting to write multi-byte MessagePack value.
 #[derive(Debug)]
 #[allow(deprecated)] // TODO: Needed for compatibility
@@ -254,6 +279,9 @@ impl<E: RmpWriteErr> Display for ValueWriteError<E> {
 /// Encodes and attempts to write the most efficient array length implementation to the given write,
 /// returning the marker used.
 ///
+/// Only the header is written. The caller is responsible for writing exactly `len` values
+/// afterwards.
+///
 /// # Errors
 ///
 /// This function will return `ValueWriteError` on any I/O error occurred while writing either the
@@ -278,6 +306,9 @@ pub fn write_array_len<W: RmpWrite>(wr: &mut W, len: u32) -> Result<Marker, Valu
 /// Encodes and attempts to write the most efficient map length implementation to the given write,
 /// returning the marker used.
 ///
+/// Only the header is written. The caller is responsible for writing exactly `len` key-value
+/// pairs afterwards, each key followed by its value.
+///
 /// # Errors
 ///
 /// This function will return `ValueWriteError` on any I/O error occurred while writing either the
@@ -302,6 +333,9 @@ pub fn write_map_len<W: RmpWrite>(wr: &mut W, len: u32) -> Result<Marker, ValueW
 /// Encodes and attempts to write the most efficient ext metadata implementation to the given
 /// write, returning the marker used.
 ///
+/// Only the marker, length and type are written. The caller is responsible for writing exactly
+/// `len` bytes of data afterwards. Use [`write_ext`] to write both at once.
+///
 /// # Errors
 ///
 /// This function will return `ValueWriteError` on any I/O error occurred while writing either the
@@ -5,6 +5,9 @@ use crate::Marker;
 /// Encodes and attempts to write the most efficient string length implementation to the given
 /// write, returning the marker used.
 ///
+/// Only the header is written. The caller is responsible for writing exactly `len` bytes of UTF-8
+/// data afterwards, for example directly to the underlying `Write`.
+///
 /// # Errors
 ///
 /// This function will return `ValueWriteError` on any I/O error occurred while writing either the
@@ -40,3 +43,37 @@ pub fn write_str<W: RmpWrite>(wr: &mut W, data: &str) -> Result<(), ValueWriteEr
     write_str_len(wr, data.len() as u32)?;
     wr.write_bytes(data.as_bytes()).map_err(ValueWriteError::InvalidDataWrite)
 }
+
+/// Encodes and attempts to write a string of `len` bytes whose payload is copied from `src`,
+/// without buffering it.
+///
+/// Exactly `len` bytes are read from `src`; anything after them is left in it. The payload isn't
+/// checked to be valid UTF-8.
+///
+/// # Errors
+///
+/// This function will return `ValueWriteError` on any I/O error occurred while writing the
+/// marker or copying the data, including `ValueWriteError::InvalidDataWrite` with the
+/// `UnexpectedEof` kind if `src` yields fewer than `len` bytes. The header and the bytes copied so
+/// far have been written then.
+///
+/// # Examples
+///
+/// ```
+/// let mut src = std::io::Cursor::new("le message");
+/// let mut buf = Vec::new();
+///
+/// rmp::encode::write_str_from_reader(&mut buf, 2, &mut src).unwrap();
+///
+/// assert_eq!(vec![0xa2, 0x6c, 0x65], buf);
+/// assert_eq!(2, src.position());
+/// ```
+#[cfg(feature = "std")]
+pub fn write_str_from_reader<W, R>(wr: &mut W, len: u32, src: &mut R) -> Result<(), ValueWriteError<std::io::Error>>
+where
+    W: std::io::Write,
+    R: std::io::Read,
+{
+    write_str_len(wr, len)?;
+    super::copy_payload(wr, len, src)
+}
@@ -1 +1,52 @@
+use super::{write_array_len, write_bool, write_sint, RmpWrite, ValueWriteError};
 
+/// Encodes and attempts to write a slice of integers as an array into the given write.
+///
+/// The array header is followed by every element encoded with [`write_sint`], so each one takes
+/// the most compact representation available.
+///
+/// # Errors
+///
+/// This function will return `ValueWriteError` on any I/O error occurred while writing either the
+/// array header or one of the elements.
+///
+/// # Examples
+///
+/// ```
+/// let mut buf = Vec::new();
+///
+/// rmp::encode::write_int_array(&mut buf, &[1, -1, 300]).unwrap();
+///
+/// assert_eq!(vec![0x93, 0x01, 0xff, 0xcd, 0x01, 0x2c], buf);
+/// ```
+pub fn write_int_array<W: RmpWrite>(wr: &mut W, data: &[i64]) -> Result<(), ValueWriteError<W::Error>> {
+    write_array_len(wr, data.len() as u32)?;
+    for &val in data {
+        write_sint(wr, val)?;
+    }
+    Ok(())
+}
+
+/// Encodes and attempts to write a slice of booleans as an array into the given write.
+///
+/// # Errors
+///
+/// This function will return `ValueWriteError` on any I/O error occurred while writing either the
+/// array header or one of the elements.
+///
+/// # Examples
+///
+/// ```
+/// let mut buf = Vec::new();
+///
+/// rmp::encode::write_bool_array(&mut buf, &[true, false]).unwrap();
+///
+/// assert_eq!(vec![0x92, 0xc3, 0xc2], buf);
+/// ```
+pub fn write_bool_array<W: RmpWrite>(wr: &mut W, data: &[bool]) -> Result<(), ValueWriteError<W::Error>> {
+    write_array_len(wr, data.len() as u32)?;
+    for &val in data {
+        write_bool(wr, val).map_err(ValueWriteError::InvalidDataWrite)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,71 @@
+//! Fixed-width big-endian lengths, for protocols that frame MessagePack bodies with a length prefix
+//! that isn't itself MessagePack.
+//!
+//! # Examples
+//!
+//! ```
+//! use rmp::framing::{read_u24_be, write_u24_be};
+//!
+//! let mut buf = Vec::new();
+//! write_u24_be(&mut buf, 0x123456).unwrap();
+//!
+//! assert_eq!([0x12, 0x34, 0x56], buf[..]);
+//! assert_eq!(0x123456, read_u24_be(&mut &buf[..]).unwrap());
+//! ```
+
+use crate::decode::{RmpRead, ValueReadError};
+use crate::encode::{RmpWrite, ValueWriteError};
+
+/// The largest value [`write_u24_be`] accepts.
+pub const U24_MAX: u32 = 0xff_ffff;
+
+/// Attempts to read a 2-byte big-endian unsigned integer from the given reader.
+///
+/// # Errors
+///
+/// This function will return `ValueReadError::InvalidDataRead` on any I/O error while reading,
+/// including an unexpected end of the input.
+#[inline]
+pub fn read_u16_be<R: RmpRead>(rd: &mut R) -> Result<u16, ValueReadError<R::Error>> {
+    rd.read_data_u16()
+}
+
+/// Attempts to read a 3-byte big-endian unsigned integer from the given reader.
+///
+/// # Errors
+///
+/// This function will return `ValueReadError::InvalidDataRead` on any I/O error while reading,
+/// including an unexpected end of the input.
+pub fn read_u24_be<R: RmpRead>(rd: &mut R) -> Result<u32, ValueReadError<R::Error>> {
+    let mut buf = [0; 4];
+    rd.read_exact_buf(&mut buf[1..]).map_err(ValueReadError::InvalidDataRead)?;
+
+    Ok(u32::from_be_bytes(buf))
+}
+
+/// Attempts to write the given value as a 2-byte big-endian unsigned integer.
+///
+/// # Errors
+///
+/// This function will return `ValueWriteError::InvalidDataWrite` on any I/O error while writing.
+#[inline]
+pub fn write_u16_be<W: RmpWrite>(wr: &mut W, val: u16) -> Result<(), ValueWriteError<W::Error>> {
+    wr.write_bytes(&val.to_be_bytes()).map_err(ValueWriteError::InvalidDataWrite)
+}
+
+/// Attempts to write the given value as a 3-byte big-endian unsigned integer.
+///
+/// The function is **strict** with the input arguments - it is the user's responsibility to check
+/// that the value fits in 24 bits, otherwise it will panic.
+///
+/// # Errors
+///
+/// This function will return `ValueWriteError::InvalidDataWrite` on any I/O error while writing.
+///
+/// # Panics
+///
+/// Panics if `val` is greater than [`U24_MAX`].
+pub fn write_u24_be<W: RmpWrite>(wr: &mut W, val: u32) -> Result<(), ValueWriteError<W::Error>> {
+    assert!(val <= U24_MAX);
+    wr.write_bytes(&val.to_be_bytes()[1..]).map_err(ValueWriteError::InvalidDataWrite)
+}
@@ -153,10 +153,24 @@ extern crate alloc;
 
 pub mod decode;
 pub mod encode;
+pub mod framing;
 mod marker;
 mod errors;
 
-pub use crate::marker::Marker;
+pub use crate::marker::{Marker, MarkerCategory};
 
 /// Version of the MessagePack [spec](http://github.com/msgpack/msgpack/blob/master/spec.md).
 pub const MSGPACK_VERSION: u32 = 5;
+
+/// The ext type carrying an `i128`, as written by [`encode::write_i128_ext`].
+///
+/// MessagePack has no 128-bit integers, so this crate carries them as a fixext16 of this
+/// application type, whose payload is the integer in big-endian two's complement. Other
+/// implementations exchanging such integers with this crate must use the same type and layout.
+pub const I128_EXT_TYPE: i8 = 100;
+
+/// The ext type carrying a `u128`, as written by [`encode::write_u128_ext`].
+///
+/// The layout is the same as for [`I128_EXT_TYPE`], with the payload being the unsigned integer in
+/// big-endian.
+pub const U128_EXT_TYPE: i8 = 101;
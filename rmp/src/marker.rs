@@ -89,6 +89,28 @@ impl Marker {
         }
     }
 
+    /// Construct a msgpack marker from a single byte, returning `None` for the byte `0xc1`, which
+    /// the MessagePack spec marks as never used.
+    ///
+    /// Unlike [`Marker::from_u8`], this doesn't map that byte to [`Marker::Reserved`], so invalid
+    /// input can't be mistaken for a marker.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmp::Marker;
+    ///
+    /// assert_eq!(Some(Marker::Null), Marker::try_from_u8(0xc0));
+    /// assert_eq!(None, Marker::try_from_u8(0xc1));
+    /// ```
+    #[inline]
+    pub fn try_from_u8(n: u8) -> Option<Marker> {
+        match Marker::from_u8(n) {
+            Marker::Reserved => None,
+            marker => Some(marker),
+        }
+    }
+
     /// Converts a marker object into a single-byte representation.
     pub fn to_u8(&self) -> u8 {
         match *self {
@@ -142,6 +164,180 @@ impl Marker {
             Marker::Reserved      => 0xc1,
         }
     }
+
+    /// Returns a guess at why this marker may have been found where another value was expected,
+    /// for error messages, or `None` if nothing stands out about its byte.
+    ///
+    /// Bytes 0x00 and 0xff are also the integers 0 and -1, the most common integers, so their hints
+    /// are only one possibility among others, and are best given where no integer could be.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmp::Marker;
+    ///
+    /// assert_eq!(
+    ///     Some("byte 0xc1 is reserved; the stream may be misaligned or truncated"),
+    ///     Marker::Reserved.hint()
+    /// );
+    /// assert_eq!(None, Marker::Str8.hint());
+    /// ```
+    pub fn hint(&self) -> Option<&'static str> {
+        match self.to_u8() {
+            0xc1 => Some("byte 0xc1 is reserved; the stream may be misaligned or truncated"),
+            0x00 => Some(
+                "byte 0x00 is the integer 0, but also often the high byte of a length or number; if no \
+                 integer was expected, the stream may be misaligned, e.g. by a length read with the wrong width"
+            ),
+            0xff => Some(
+                "byte 0xff is the integer -1, but also often padding or erased memory; if no integer was \
+                 expected, the stream may be truncated"
+            ),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the format family this marker belongs to, as in the MessagePack
+    /// specification, e.g. `"str8"` or `"positive fixint"`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmp::Marker;
+    ///
+    /// assert_eq!("array16", Marker::Array16.name());
+    /// assert_eq!("fixstr", Marker::FixStr(3).name());
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Marker::FixPos(..)    => "positive fixint",
+            Marker::FixNeg(..)    => "negative fixint",
+
+            Marker::Null          => "nil",
+
+            Marker::True          => "true",
+            Marker::False         => "false",
+
+            Marker::U8            => "uint8",
+            Marker::U16           => "uint16",
+            Marker::U32           => "uint32",
+            Marker::U64           => "uint64",
+
+            Marker::I8            => "int8",
+            Marker::I16           => "int16",
+            Marker::I32           => "int32",
+            Marker::I64           => "int64",
+
+            Marker::F32           => "float32",
+            Marker::F64           => "float64",
+
+            Marker::FixStr(..)    => "fixstr",
+            Marker::Str8          => "str8",
+            Marker::Str16         => "str16",
+            Marker::Str32         => "str32",
+
+            Marker::Bin8          => "bin8",
+            Marker::Bin16         => "bin16",
+            Marker::Bin32         => "bin32",
+
+            Marker::FixArray(..)  => "fixarray",
+            Marker::Array16       => "array16",
+            Marker::Array32       => "array32",
+
+            Marker::FixMap(..)    => "fixmap",
+            Marker::Map16         => "map16",
+            Marker::Map32         => "map32",
+
+            Marker::FixExt1       => "fixext1",
+            Marker::FixExt2       => "fixext2",
+            Marker::FixExt4       => "fixext4",
+            Marker::FixExt8       => "fixext8",
+            Marker::FixExt16      => "fixext16",
+            Marker::Ext8          => "ext8",
+            Marker::Ext16         => "ext16",
+            Marker::Ext32         => "ext32",
+
+            Marker::Reserved      => "never used",
+        }
+    }
+
+    /// Returns the category of values this marker encodes, merging the formats that differ only
+    /// in their width, such as all integers or all strings.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmp::{Marker, MarkerCategory};
+    ///
+    /// assert_eq!(MarkerCategory::Integer, Marker::FixNeg(-1).category());
+    /// assert_eq!(MarkerCategory::Float, Marker::F32.category());
+    /// ```
+    pub fn category(&self) -> MarkerCategory {
+        match *self {
+            Marker::Null => MarkerCategory::Nil,
+            Marker::True |
+            Marker::False => MarkerCategory::Boolean,
+            Marker::FixPos(..) |
+            Marker::FixNeg(..) |
+            Marker::U8 |
+            Marker::U16 |
+            Marker::U32 |
+            Marker::U64 |
+            Marker::I8 |
+            Marker::I16 |
+            Marker::I32 |
+            Marker::I64 => MarkerCategory::Integer,
+            Marker::F32 |
+            Marker::F64 => MarkerCategory::Float,
+            Marker::FixStr(..) |
+            Marker::Str8 |
+            Marker::Str16 |
+            Marker::Str32 => MarkerCategory::String,
+            Marker::Bin8 |
+            Marker::Bin16 |
+            Marker::Bin32 => MarkerCategory::Binary,
+            Marker::FixArray(..) |
+            Marker::Array16 |
+            Marker::Array32 => MarkerCategory::Array,
+            Marker::FixMap(..) |
+            Marker::Map16 |
+            Marker::Map32 => MarkerCategory::Map,
+            Marker::FixExt1 |
+            Marker::FixExt2 |
+            Marker::FixExt4 |
+            Marker::FixExt8 |
+            Marker::FixExt16 |
+            Marker::Ext8 |
+            Marker::Ext16 |
+            Marker::Ext32 => MarkerCategory::Ext,
+            Marker::Reserved => MarkerCategory::Reserved,
+        }
+    }
+}
+
+/// The category of values a [`Marker`] encodes, see [`Marker::category`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MarkerCategory {
+    /// Nil.
+    Nil,
+    /// `true` or `false`.
+    Boolean,
+    /// A signed or unsigned integer of any width, including the fixints.
+    Integer,
+    /// A 32 or 64-bit float.
+    Float,
+    /// A string.
+    String,
+    /// A byte array.
+    Binary,
+    /// An array.
+    Array,
+    /// A map.
+    Map,
+    /// An extension, fixed-size or not.
+    Ext,
+    /// The reserved marker byte `0xc1`.
+    Reserved,
 }
 
 impl From<u8> for Marker {
@@ -33,6 +33,16 @@ fn from_array16_min_read_size() {
     assert_eq!(3, cur.position());
 }
 
+#[test]
+fn from_array16_zero_read_size() {
+    // Not the most compact encoding, but legal.
+    let buf: &[u8] = &[0xdc, 0x00, 0x00];
+    let mut cur = Cursor::new(buf);
+
+    assert_eq!(0, read_array_len(&mut cur).unwrap());
+    assert_eq!(3, cur.position());
+}
+
 #[test]
 fn from_array16_max_read_size() {
     let buf: &[u8] = &[0xdc, 0xff, 0xff];
@@ -1,6 +1,7 @@
 use super::Cursor;
 
 use crate::msgpack::decode::*;
+use crate::msgpack::Marker;
 
 #[test]
 fn from_fixext1_read_fixext1() {
@@ -55,6 +56,30 @@ fn from_fixext16_read_fixext16() {
     assert_eq!(18, cur.position());
 }
 
+#[test]
+fn from_fixext8_read_fixext4() {
+    let buf = [0xd7, 0x01, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08];
+    let mut cur = Cursor::new(&buf[..]);
+
+    match read_fixext4(&mut cur) {
+        Err(ValueReadError::UnexpectedExtSize { expected: 4, found: 8 }) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    assert_eq!(1, cur.position());
+}
+
+#[test]
+fn from_null_read_fixext4() {
+    let buf: &[u8] = &[0xc0];
+    let mut cur = Cursor::new(buf);
+
+    match read_fixext4(&mut cur) {
+        Err(ValueReadError::TypeMismatch(Marker::Null)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    assert_eq!(1, cur.position());
+}
+
 #[test]
 fn from_fixext1_read_ext_meta() {
     let buf: &[u8] = &[0xd4, 0x01];
@@ -126,3 +151,192 @@ fn from_ext32_read_ext_meta() {
     assert_eq!(ExtMeta { typeid: 1, size: 4294967295 }, read_ext_meta(&mut cur).unwrap());
     assert_eq!(6, cur.position());
 }
+
+/// Encodes a fixext of type 1 whose payload is `1, 2, ..., len`, followed by `trailing` nils.
+fn fixext_buf(marker: u8, len: u8, trailing: usize) -> Vec<u8> {
+    let mut buf = vec![marker, 0x01];
+    buf.extend(1..=len);
+    buf.resize(buf.len() + trailing, 0xc0);
+    buf
+}
+
+#[test]
+fn from_fixext_exact_read_fixext() {
+    let buf = fixext_buf(0xd4, 1, 0);
+    let mut cur = Cursor::new(&buf[..]);
+    assert_eq!((1, 1), read_fixext1(&mut cur).unwrap());
+    assert_eq!(3, cur.position());
+
+    let buf = fixext_buf(0xd5, 2, 0);
+    let mut cur = Cursor::new(&buf[..]);
+    assert_eq!((1, [1, 2]), read_fixext2(&mut cur).unwrap());
+    assert_eq!(4, cur.position());
+
+    let buf = fixext_buf(0xd6, 4, 0);
+    let mut cur = Cursor::new(&buf[..]);
+    assert_eq!((1, [1, 2, 3, 4]), read_fixext4(&mut cur).unwrap());
+    assert_eq!(6, cur.position());
+
+    let buf = fixext_buf(0xd7, 8, 0);
+    let mut cur = Cursor::new(&buf[..]);
+    assert_eq!((1, [1, 2, 3, 4, 5, 6, 7, 8]), read_fixext8(&mut cur).unwrap());
+    assert_eq!(10, cur.position());
+
+    let buf = fixext_buf(0xd8, 16, 0);
+    let mut cur = Cursor::new(&buf[..]);
+    assert_eq!((1, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]),
+               read_fixext16(&mut cur).unwrap());
+    assert_eq!(18, cur.position());
+}
+
+#[test]
+fn from_fixext_oversized_read_fixext_leaves_trailing_bytes() {
+    let buf = fixext_buf(0xd4, 1, 2);
+    let mut cur = Cursor::new(&buf[..]);
+    assert_eq!((1, 1), read_fixext1(&mut cur).unwrap());
+    assert_eq!(3, cur.position());
+    assert_eq!(Marker::Null, read_marker(&mut cur).unwrap());
+
+    let buf = fixext_buf(0xd5, 2, 2);
+    let mut cur = Cursor::new(&buf[..]);
+    assert_eq!((1, [1, 2]), read_fixext2(&mut cur).unwrap());
+    assert_eq!(4, cur.position());
+    assert_eq!(Marker::Null, read_marker(&mut cur).unwrap());
+
+    let buf = fixext_buf(0xd6, 4, 2);
+    let mut cur = Cursor::new(&buf[..]);
+    assert_eq!((1, [1, 2, 3, 4]), read_fixext4(&mut cur).unwrap());
+    assert_eq!(6, cur.position());
+    assert_eq!(Marker::Null, read_marker(&mut cur).unwrap());
+
+    let buf = fixext_buf(0xd7, 8, 2);
+    let mut cur = Cursor::new(&buf[..]);
+    assert_eq!((1, [1, 2, 3, 4, 5, 6, 7, 8]), read_fixext8(&mut cur).unwrap());
+    assert_eq!(10, cur.position());
+    assert_eq!(Marker::Null, read_marker(&mut cur).unwrap());
+
+    let buf = fixext_buf(0xd8, 16, 2);
+    let mut cur = Cursor::new(&buf[..]);
+    assert_eq!((1, [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16]),
+               read_fixext16(&mut cur).unwrap());
+    assert_eq!(18, cur.position());
+    assert_eq!(Marker::Null, read_marker(&mut cur).unwrap());
+}
+
+#[test]
+fn from_fixext_undersized_read_fixext_errors() {
+    // Each payload lacks its last byte.
+    let buf = fixext_buf(0xd4, 0, 0);
+    let mut cur = Cursor::new(&buf[..]);
+    assert!(matches!(read_fixext1(&mut cur), Err(ValueReadError::InvalidDataRead(..))));
+
+    let buf = fixext_buf(0xd5, 1, 0);
+    let mut cur = Cursor::new(&buf[..]);
+    assert!(matches!(read_fixext2(&mut cur), Err(ValueReadError::InvalidDataRead(..))));
+
+    let buf = fixext_buf(0xd6, 3, 0);
+    let mut cur = Cursor::new(&buf[..]);
+    assert!(matches!(read_fixext4(&mut cur), Err(ValueReadError::InvalidDataRead(..))));
+
+    let buf = fixext_buf(0xd7, 7, 0);
+    let mut cur = Cursor::new(&buf[..]);
+    assert!(matches!(read_fixext8(&mut cur), Err(ValueReadError::InvalidDataRead(..))));
+
+    let buf = fixext_buf(0xd8, 15, 0);
+    let mut cur = Cursor::new(&buf[..]);
+    assert!(matches!(read_fixext16(&mut cur), Err(ValueReadError::InvalidDataRead(..))));
+}
+
+fn int128_round_trip(val: i128) -> i128 {
+    let mut buf = Vec::new();
+    crate::msgpack::encode::write_i128_ext(&mut buf, val).unwrap();
+    assert_eq!(18, buf.len());
+
+    let mut cur = Cursor::new(&buf[..]);
+    let res = read_i128_ext(&mut cur).unwrap();
+    assert_eq!(18, cur.position());
+    res
+}
+
+#[test]
+fn from_i128_ext_round_trip() {
+    assert_eq!(i128::MIN, int128_round_trip(i128::MIN));
+    assert_eq!(i128::MAX, int128_round_trip(i128::MAX));
+    assert_eq!(-170141183460469231731687303715884105, int128_round_trip(-170141183460469231731687303715884105));
+    assert_eq!(0, int128_round_trip(0));
+}
+
+#[test]
+fn from_u128_ext_round_trip() {
+    let mut buf = Vec::new();
+    crate::msgpack::encode::write_u128_ext(&mut buf, u128::MAX).unwrap();
+
+    let mut cur = Cursor::new(&buf[..]);
+    assert_eq!(u128::MAX, read_u128_ext(&mut cur).unwrap());
+}
+
+#[test]
+fn from_i128_ext_big_endian() {
+    let buf: &[u8] = &[0xd8, 0x64, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x02];
+    let mut cur = Cursor::new(buf);
+
+    assert_eq!(0x0102, read_i128_ext(&mut cur).unwrap());
+}
+
+#[test]
+fn from_other_ext_type_read_i128_ext() {
+    let buf: &[u8] = &[0xd8, 0x65, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    let mut cur = Cursor::new(buf);
+
+    match read_i128_ext(&mut cur) {
+        Err(ValueReadError::UnexpectedExtType { expected: 100, found: 101 }) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let buf = fixext_buf(0xd7, 8, 0);
+    let mut cur = Cursor::new(&buf[..]);
+    assert!(matches!(read_i128_ext(&mut cur), Err(ValueReadError::UnexpectedExtSize { expected: 16, found: 8 })));
+}
+
+#[test]
+fn from_fixext4_read_fixext_ref() {
+    let buf: &[u8] = &[0xd6, 0x01, 0x00, 0x00, 0x00, 0x01, 0xc0];
+
+    let (ty, data) = read_fixext_ref(buf).unwrap();
+
+    assert_eq!(1, ty);
+    assert_eq!([0x00, 0x00, 0x00, 0x01], data);
+    // The payload is borrowed from the input.
+    assert!(core::ptr::eq(&buf[2], &data[0]));
+}
+
+#[test]
+fn from_fixext16_read_fixext_ref() {
+    let buf = fixext_buf(0xd8, 16, 2);
+
+    let (ty, data) = read_fixext_ref(&buf).unwrap();
+
+    assert_eq!(1, ty);
+    assert_eq!(&buf[2..18], data);
+    assert_eq!(buf[2..].as_ptr(), data.as_ptr());
+}
+
+#[test]
+fn from_truncated_fixext8_read_fixext_ref() {
+    let buf: &[u8] = &[0xd7, 0x01, 0x00, 0x00];
+
+    match read_fixext_ref(buf) {
+        Err(ValueReadError::InvalidDataRead(bytes::BytesReadError::InsufficientBytes { expected: 8, actual: 2, .. })) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_ext8_read_fixext_ref() {
+    let buf: &[u8] = &[0xc7, 0x01, 0x01, 0x00];
+
+    match read_fixext_ref(buf) {
+        Err(ValueReadError::TypeMismatch(Marker::Ext8)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
@@ -0,0 +1,108 @@
+use crate::msgpack::decode::*;
+
+#[test]
+fn from_empty_bytes_read_marker_display() {
+    let err = read_marker(&mut Bytes::new(&[])).unwrap_err();
+
+    assert_eq!(
+        "failed to read MessagePack marker: Expected at least bytes 1, but only got 0 (pos 0)",
+        err.to_string()
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn from_empty_reader_read_marker_display() {
+    use std::error::Error;
+
+    let buf: &[u8] = &[];
+    let err = read_marker(&mut &buf[..]).unwrap_err();
+
+    assert_eq!(std::io::ErrorKind::UnexpectedEof, err.0.kind());
+    assert_eq!(format!("failed to read MessagePack marker: {}", err.0), err.to_string());
+    assert_eq!(err.0.to_string(), err.source().unwrap().to_string());
+}
+
+#[test]
+fn marker_name_covers_every_family() {
+    use crate::msgpack::Marker;
+
+    let names = [
+        (0x00, "positive fixint"),
+        (0xff, "negative fixint"),
+        (0xc0, "nil"),
+        (0xc1, "never used"),
+        (0xc2, "false"),
+        (0xc3, "true"),
+        (0xcc, "uint8"),
+        (0xcf, "uint64"),
+        (0xd0, "int8"),
+        (0xd3, "int64"),
+        (0xca, "float32"),
+        (0xcb, "float64"),
+        (0xa5, "fixstr"),
+        (0xd9, "str8"),
+        (0xdb, "str32"),
+        (0xc4, "bin8"),
+        (0xc6, "bin32"),
+        (0x93, "fixarray"),
+        (0xdc, "array16"),
+        (0xdd, "array32"),
+        (0x81, "fixmap"),
+        (0xde, "map16"),
+        (0xdf, "map32"),
+        (0xd4, "fixext1"),
+        (0xd8, "fixext16"),
+        (0xc7, "ext8"),
+        (0xc9, "ext32"),
+    ];
+
+    for (byte, name) in names {
+        assert_eq!(name, Marker::from_u8(byte).name(), "marker 0x{:02x}", byte);
+    }
+}
+
+#[test]
+fn marker_try_from_u8() {
+    use crate::msgpack::Marker;
+
+    assert_eq!(Some(Marker::FixPos(0x2a)), Marker::try_from_u8(0x2a));
+    assert_eq!(Some(Marker::FixNeg(-1)), Marker::try_from_u8(0xff));
+    assert_eq!(Some(Marker::FixStr(3)), Marker::try_from_u8(0xa3));
+    assert_eq!(Some(Marker::Null), Marker::try_from_u8(0xc0));
+    assert_eq!(Some(Marker::False), Marker::try_from_u8(0xc2));
+    assert_eq!(Some(Marker::Map32), Marker::try_from_u8(0xdf));
+    assert_eq!(None, Marker::try_from_u8(0xc1));
+
+    for byte in (0..=0xff).filter(|&byte| byte != 0xc1) {
+        assert_eq!(Some(Marker::from_u8(byte)), Marker::try_from_u8(byte));
+    }
+}
+
+#[test]
+fn marker_hint() {
+    use crate::msgpack::Marker;
+
+    assert_eq!(
+        Some("byte 0xc1 is reserved; the stream may be misaligned or truncated"),
+        Marker::from_u8(0xc1).hint()
+    );
+    assert!(Marker::from_u8(0x00).hint().unwrap().starts_with("byte 0x00 is the integer 0, but also often"));
+    assert!(Marker::from_u8(0xff).hint().unwrap().starts_with("byte 0xff is the integer -1, but also often padding"));
+    assert_eq!(None, Marker::from_u8(0x2a).hint());
+    assert_eq!(None, Marker::Array16.hint());
+}
+
+#[test]
+fn marker_category() {
+    use crate::msgpack::{Marker, MarkerCategory};
+
+    assert_eq!(MarkerCategory::Integer, Marker::from_u8(0x2a).category());
+    assert_eq!(MarkerCategory::Integer, Marker::from_u8(0xd3).category());
+    assert_eq!(MarkerCategory::Float, Marker::from_u8(0xca).category());
+    assert_eq!(MarkerCategory::Float, Marker::from_u8(0xcb).category());
+    assert_eq!(MarkerCategory::String, Marker::from_u8(0xa3).category());
+    assert_eq!(MarkerCategory::Binary, Marker::from_u8(0xc4).category());
+    assert_eq!(MarkerCategory::Ext, Marker::from_u8(0xd4).category());
+    assert_eq!(MarkerCategory::Reserved, Marker::from_u8(0xc1).category());
+}
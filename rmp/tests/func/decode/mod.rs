@@ -4,6 +4,7 @@ mod bool;
 mod ext;
 mod float;
 mod map;
+mod marker;
 mod null;
 mod sint;
 mod string;
@@ -278,3 +278,44 @@ fn from_i64_max_read_int() {
     assert_eq!(9223372036854775807i64, read_int(&mut cur).unwrap());
     assert_eq!(9, cur.position());
 }
+
+#[test]
+fn from_pfix_read_int_as_i64() {
+    let buf: &[u8] = &[0x2a];
+    let mut cur = Cursor::new(buf);
+
+    assert_eq!(42i64, read_int(&mut cur).unwrap());
+    assert_eq!(1, cur.position());
+}
+
+#[test]
+fn from_u16_read_int_as_i64() {
+    let buf: &[u8] = &[0xcd, 0xff, 0xff];
+    let mut cur = Cursor::new(buf);
+
+    assert_eq!(65535i64, read_int(&mut cur).unwrap());
+    assert_eq!(3, cur.position());
+}
+
+#[test]
+fn from_u64_above_i64_max_read_int_as_i64() {
+    let buf: &[u8] = &[0xcf, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    let mut cur = Cursor::new(buf);
+
+    match read_int::<i64, _>(&mut cur) {
+        Err(NumValueReadError::OutOfRange) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(9, cur.position());
+}
+
+#[test]
+fn from_nfix_read_int_as_u64() {
+    let buf: &[u8] = &[0xff];
+    let mut cur = Cursor::new(buf);
+
+    match read_int::<u64, _>(&mut cur) {
+        Err(NumValueReadError::OutOfRange) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
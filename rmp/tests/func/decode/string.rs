@@ -183,6 +183,36 @@ fn from_str_strfix_invalid_utf8() {
     assert_eq!(3, cur.position());
 }
 
+#[test]
+fn from_str_str16() {
+    let buf: &[u8] = &[0xda, 0x00, 0x0a, 0x6c, 0x65, 0x20, 0x6d, 0x65, 0x73, 0x73, 0x61, 0x67, 0x65];
+    let mut cur = Cursor::new(buf);
+
+    let out: &mut [u8] = &mut [0u8; 16];
+
+    assert_eq!("le message", read_str(&mut cur, out).unwrap());
+    assert_eq!(13, cur.position());
+}
+
+#[test]
+fn from_str_str16_invalid_utf8() {
+    // Invalid 2 Octet Sequence.
+    let buf: &[u8] = &[0xda, 0x00, 0x02, 0xc3, 0x28];
+    let mut cur = Cursor::new(buf);
+
+    let out: &mut [u8] = &mut [0u8; 16];
+
+    match read_str(&mut cur, out) {
+        Err(DecodeStringError::InvalidUtf8(raw, err)) => {
+            assert_eq!(&[0xc3, 0x28], raw);
+            assert_eq!(0, err.valid_up_to());
+        }
+        other => panic!("unexpected result: {:?}", other)
+    }
+
+    assert_eq!(5, cur.position());
+}
+
 #[test]
 fn from_str_strfix_buffer_too_small() {
     let buf: &[u8] = &[0xaa, 0x6c, 0x65, 0x20, 0x6d, 0x65, 0x73, 0x73, 0x61, 0x67, 0x65];
@@ -197,6 +227,108 @@ fn from_str_strfix_buffer_too_small() {
     assert_eq!(1, cur.position());
 }
 
+#[test]
+fn from_str_strfix_then_read_int() {
+    // "key", 300
+    let buf: &[u8] = &[0xa3, 0x6b, 0x65, 0x79, 0xcd, 0x01, 0x2c];
+    let mut cur = Cursor::new(buf);
+
+    let out: &mut [u8] = &mut [0u8; 16];
+
+    assert_eq!("key", read_str(&mut cur, out).unwrap());
+    assert_eq!(4, cur.position());
+    assert_eq!(300u16, read_int(&mut cur).unwrap());
+    assert_eq!(7, cur.position());
+}
+
+#[test]
+fn from_str_str8_then_read_str() {
+    // "first", "second"
+    let buf: &[u8] = &[
+        0xd9, 0x05, 0x66, 0x69, 0x72, 0x73, 0x74, 0xa6, 0x73, 0x65, 0x63, 0x6f, 0x6e, 0x64,
+    ];
+    let mut cur = Cursor::new(buf);
+
+    let mut out = [0u8; 16];
+
+    assert_eq!("first", read_str(&mut cur, &mut out).unwrap());
+    assert_eq!(7, cur.position());
+    assert_eq!("second", read_str(&mut cur, &mut out).unwrap());
+    assert_eq!(14, cur.position());
+}
+
+#[test]
+fn from_fixstr_multibyte_read_str_with_len() {
+    // "Grüße, €" takes 8 characters but 12 bytes.
+    let buf: &[u8] = &[0xac, 0x47, 0x72, 0xc3, 0xbc, 0xc3, 0x9f, 0x65, 0x2c, 0x20, 0xe2, 0x82, 0xac, 0xc0];
+    let mut cur = Cursor::new(buf);
+
+    let out: &mut [u8] = &mut [0u8; 16];
+    let (s, len) = read_str_with_len(&mut cur, out).unwrap();
+
+    assert_eq!("Grüße, €", s);
+    assert_eq!(8, s.chars().count());
+    assert_eq!(12, len);
+    assert_eq!(13, cur.position());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn from_str8_buffer_too_small_read_str_retryable() {
+    let buf: &[u8] = &[0xc0, 0xd9, 0x03, 0x72, 0x6d, 0x70, 0xc0];
+    let mut cur = Cursor::new(buf);
+    cur.set_position(1);
+
+    let out: &mut [u8] = &mut [0u8; 2];
+    match read_str_retryable(&mut cur, out) {
+        Err(DecodeStringError::BufferSizeTooSmall(3)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    assert_eq!(1, cur.position());
+
+    let out: &mut [u8] = &mut [0u8; 3];
+    assert_eq!("rmp", read_str_retryable(&mut cur, out).unwrap());
+    assert_eq!(6, cur.position());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn from_str32_max_buffer_too_small_read_str_retryable() {
+    let buf: &[u8] = &[0xdb, 0xff, 0xff, 0xff, 0xff, 0x61, 0x62];
+    let mut cur = Cursor::new(buf);
+
+    let out: &mut [u8] = &mut [0u8; 16];
+    match read_str_retryable(&mut cur, out) {
+        Err(DecodeStringError::BufferSizeTooSmall(4294967295)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    assert_eq!(0, cur.position());
+}
+
+#[test]
+fn from_str32_max_buffer_too_small() {
+    let buf: &[u8] = &[0xdb, 0xff, 0xff, 0xff, 0xff, 0x61, 0x62];
+    let mut cur = Cursor::new(buf);
+
+    let out: &mut [u8] = &mut [0u8; 16];
+
+    match read_str(&mut cur, out) {
+        Err(DecodeStringError::BufferSizeTooSmall(4294967295)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    assert_eq!(5, cur.position());
+}
+
+#[test]
+fn from_str32_max_decode_from_slice_too_small() {
+    let buf: &[u8] = &[0xdb, 0xff, 0xff, 0xff, 0xff, 0x61, 0x62];
+
+    match read_str_from_slice(buf) {
+        Err(DecodeStringError::BufferSizeTooSmall(4294967295)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+}
+
 #[test]
 fn from_str_strfix_decode_from_slice() {
     // Wrap an incomplete buffer into the Cursor to see how many bytes were consumed.
@@ -240,3 +372,77 @@ fn example_process_sequence_of_strings() {
 
     assert_eq!(vec!["Unpacking", "multiple", "strings"], chunks);
 }
+
+#[test]
+fn from_fixstr_ascii_read_char() {
+    let buf: &[u8] = &[0xa1, 0x61];
+    let mut cur = Cursor::new(buf);
+
+    assert_eq!('a', read_char(&mut cur).unwrap());
+    assert_eq!(2, cur.position());
+}
+
+#[test]
+fn from_fixstr_multibyte_read_char() {
+    let buf: &[u8] = &[0xa3, 0xe2, 0x82, 0xac];
+    let mut cur = Cursor::new(buf);
+
+    assert_eq!('€', read_char(&mut cur).unwrap());
+    assert_eq!(4, cur.position());
+}
+
+#[test]
+fn from_str8_multibyte_read_char() {
+    let buf: &[u8] = &[0xd9, 0x04, 0xf0, 0x9f, 0x92, 0x96];
+    let mut cur = Cursor::new(buf);
+
+    assert_eq!('💖', read_char(&mut cur).unwrap());
+    assert_eq!(6, cur.position());
+}
+
+#[test]
+fn from_fixstr_two_chars_read_char() {
+    let buf: &[u8] = &[0xa2, 0x61, 0x62];
+    let mut cur = Cursor::new(buf);
+
+    match read_char(&mut cur) {
+        Err(DecodeCharError::NotSingleChar(2)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    assert_eq!(3, cur.position());
+}
+
+#[test]
+fn from_fixstr_empty_read_char() {
+    let buf: &[u8] = &[0xa0];
+    let mut cur = Cursor::new(buf);
+
+    match read_char(&mut cur) {
+        Err(DecodeCharError::NotSingleChar(0)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    assert_eq!(1, cur.position());
+}
+
+#[test]
+fn from_fixstr_too_long_read_char() {
+    let buf: &[u8] = &[0xa5, 0x61, 0x62, 0x63, 0x64, 0x65];
+    let mut cur = Cursor::new(buf);
+
+    match read_char(&mut cur) {
+        Err(DecodeCharError::NotSingleChar(5)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    assert_eq!(1, cur.position());
+}
+
+#[test]
+fn from_fixstr_invalid_utf8_read_char() {
+    let buf: &[u8] = &[0xa2, 0xc3, 0x28];
+    let mut cur = Cursor::new(buf);
+
+    match read_char(&mut cur) {
+        Err(DecodeCharError::InvalidUtf8(..)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+}
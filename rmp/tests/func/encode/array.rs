@@ -27,3 +27,41 @@ fn pass_pack_len_u32() {
 
     assert_eq!([0xdd, 0xff, 0xff, 0xff, 0xff], buf);
 }
+
+#[test]
+fn pass_pack_bool_array() {
+    let mut buf = Vec::new();
+
+    write_bool_array(&mut buf, &[true, false, true]).unwrap();
+
+    assert_eq!(vec![0x93, 0xc3, 0xc2, 0xc3], buf);
+}
+
+#[test]
+fn pass_pack_int_array_round_trip() {
+    let data: Vec<i64> = (0..1000).map(|i| (i - 500) * i * 1_000_003).collect();
+    let mut buf = Vec::new();
+
+    write_int_array(&mut buf, &data).unwrap();
+
+    assert_eq!([0xdc, 0x03, 0xe8], buf[..3]);
+    let decoded: Vec<i64> = crate::msgpack::decode::read_int_array(&mut &buf[..]).unwrap();
+    assert_eq!(data, decoded);
+}
+
+#[test]
+fn pass_pack_len_then_manual_values() {
+    let mut buf = Vec::new();
+
+    assert_eq!(Marker::Array16, write_array_len(&mut buf, 20).unwrap());
+    for _ in 0..20 {
+        write_nil(&mut buf).unwrap();
+    }
+
+    let mut rd = &buf[..];
+    assert_eq!(20, crate::msgpack::decode::read_array_len(&mut rd).unwrap());
+    for _ in 0..20 {
+        crate::msgpack::decode::read_nil(&mut rd).unwrap();
+    }
+    assert!(rd.is_empty());
+}
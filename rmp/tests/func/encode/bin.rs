@@ -27,3 +27,52 @@ fn pass_pack_len_u32() {
 
     assert_eq!([0xc6, 0xff, 0xff, 0xff, 0xff], buf);
 }
+
+#[test]
+fn pass_pack_len_then_manual_payload() {
+    let mut buf = Vec::new();
+
+    assert_eq!(Marker::Bin8, write_bin_len(&mut buf, 3).unwrap());
+    buf.extend_from_slice(&[1, 2, 3]);
+
+    let mut rd = &buf[..];
+    assert_eq!(3, crate::msgpack::decode::read_bin_len(&mut rd).unwrap());
+    assert_eq!([1, 2, 3], rd);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn pass_pack_from_reader_exact() {
+    let mut src = std::io::Cursor::new([1, 2, 3]);
+    let mut buf = Vec::new();
+
+    write_bin_from_reader(&mut buf, 3, &mut src).unwrap();
+
+    assert_eq!([0xc4, 0x03, 0x01, 0x02, 0x03], buf[..]);
+    assert_eq!(3, src.position());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn pass_pack_from_reader_leaves_rest() {
+    let mut src = std::io::Cursor::new([1, 2, 3, 4, 5]);
+    let mut buf = Vec::new();
+
+    write_bin_from_reader(&mut buf, 3, &mut src).unwrap();
+
+    assert_eq!([0xc4, 0x03, 0x01, 0x02, 0x03], buf[..]);
+    assert_eq!(3, src.position());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn fail_pack_from_reader_short() {
+    let mut src = std::io::Cursor::new([1, 2]);
+    let mut buf = Vec::new();
+
+    match write_bin_from_reader(&mut buf, 3, &mut src) {
+        Err(ValueWriteError::InvalidDataWrite(err)) => assert_eq!(std::io::ErrorKind::UnexpectedEof, err.kind()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!([0xc4, 0x03, 0x01, 0x02], buf[..]);
+}
@@ -17,3 +17,16 @@ fn pass_pack_false() {
 
     assert_eq!([0xc2], buf);
 }
+
+#[test]
+#[cfg(feature = "std")]
+fn pass_pack_into_cursor() {
+    let mut cur = std::io::Cursor::new(Vec::new());
+
+    write_bool(&mut cur, true).unwrap();
+    assert_eq!(1, cur.position());
+    write_bool(&mut cur, false).unwrap();
+    assert_eq!(2, cur.position());
+
+    assert_eq!(vec![0xc3, 0xc2], cur.into_inner());
+}
@@ -79,3 +79,74 @@ fn pass_pack_meta_32() {
 
     assert_eq!([0xc9, 0xff, 0xff, 0xff, 0xff, 0x10], buf);
 }
+
+fn read_ext(buf: &[u8]) -> (i8, &[u8]) {
+    let mut rd = buf;
+    let meta = crate::msgpack::decode::read_ext_meta(&mut rd).unwrap();
+    assert_eq!(meta.size as usize, rd.len());
+    (meta.typeid, rd)
+}
+
+#[test]
+fn pass_pack_ext_fix4() {
+    let mut buf = Vec::new();
+
+    write_ext(&mut buf, 16, &[1, 2, 3, 4]).unwrap();
+
+    assert_eq!(vec![0xd6, 0x10, 0x01, 0x02, 0x03, 0x04], buf);
+    assert_eq!((16, &[1, 2, 3, 4][..]), read_ext(&buf));
+}
+
+#[test]
+fn pass_pack_ext_8() {
+    let mut buf = Vec::new();
+
+    write_ext(&mut buf, 16, &[1, 2, 3]).unwrap();
+
+    assert_eq!(vec![0xc7, 0x03, 0x10, 0x01, 0x02, 0x03], buf);
+    assert_eq!((16, &[1, 2, 3][..]), read_ext(&buf));
+}
+
+#[test]
+fn pass_pack_ext_16() {
+    let data = [0x42; 300];
+    let mut buf = Vec::new();
+
+    write_ext(&mut buf, -1, &data).unwrap();
+
+    assert_eq!([0xc8, 0x01, 0x2c, 0xff], buf[..4]);
+    assert_eq!((-1, &data[..]), read_ext(&buf));
+}
+
+#[test]
+fn pass_pack_meta_then_manual_payload() {
+    let mut buf = Vec::new();
+
+    assert_eq!(Marker::Ext8, write_ext_meta(&mut buf, 5, 16).unwrap());
+    buf.extend_from_slice(&[1, 2, 3, 4, 5]);
+
+    assert_eq!((16, &[1, 2, 3, 4, 5][..]), read_ext(&buf));
+}
+
+#[test]
+fn pass_pack_i128_ext() {
+    let mut buf = Vec::new();
+
+    write_i128_ext(&mut buf, i128::MIN).unwrap();
+
+    let mut expected = vec![0xd8, crate::msgpack::I128_EXT_TYPE as u8, 0x80];
+    expected.resize(18, 0x00);
+    assert_eq!(expected, buf);
+}
+
+#[test]
+fn pass_pack_u128_ext() {
+    let mut buf = Vec::new();
+
+    write_u128_ext(&mut buf, 1).unwrap();
+
+    let mut expected = vec![0xd8, crate::msgpack::U128_EXT_TYPE as u8];
+    expected.resize(17, 0x00);
+    expected.push(0x01);
+    assert_eq!(expected, buf);
+}
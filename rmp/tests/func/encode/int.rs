@@ -167,6 +167,29 @@ fn pass_pack_uint_u64() {
     assert_eq!([0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff], buf);
 }
 
+#[test]
+fn pass_pack_uint_boundaries() {
+    let cases: [(u64, Marker, &[u8]); 8] = [
+        (0, Marker::FixPos(0), &[0x00]),
+        (0x7f, Marker::FixPos(0x7f), &[0x7f]),
+        (0x80, Marker::U8, &[0xcc, 0x80]),
+        (0xff, Marker::U8, &[0xcc, 0xff]),
+        (0x100, Marker::U16, &[0xcd, 0x01, 0x00]),
+        (0xffff, Marker::U16, &[0xcd, 0xff, 0xff]),
+        (0x10000, Marker::U32, &[0xce, 0x00, 0x01, 0x00, 0x00]),
+        (u64::MAX, Marker::U64, &[0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]),
+    ];
+
+    for (val, marker, expected) in cases {
+        let mut buf = [0x00; 9];
+        let mut wr = &mut buf[..];
+
+        assert_eq!(marker, write_uint(&mut wr, val).ok().unwrap(), "value {:#x}", val);
+        let len = 9 - wr.len();
+        assert_eq!(expected, &buf[..len], "value {:#x}", val);
+    }
+}
+
 #[test]
 fn pass_pack_sint_fix() {
     let mut buf = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
@@ -238,3 +261,28 @@ fn pass_pack_sint_i64_max() {
 
     assert_eq!([0xcf, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff], buf);
 }
+
+#[test]
+fn pass_pack_sint_boundaries_round_trip() {
+    use crate::msgpack::decode::read_int;
+
+    let cases: [(i64, Marker, &[u8]); 7] = [
+        (0, Marker::FixPos(0), &[0x00]),
+        (-1, Marker::FixNeg(-1), &[0xff]),
+        (-32, Marker::FixNeg(-32), &[0xe0]),
+        (-33, Marker::I8, &[0xd0, 0xdf]),
+        (-128, Marker::I8, &[0xd0, 0x80]),
+        (-129, Marker::I16, &[0xd1, 0xff, 0x7f]),
+        (i64::MIN, Marker::I64, &[0xd3, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ];
+
+    for (val, marker, expected) in cases {
+        let mut buf = [0x00; 9];
+        let mut wr = &mut buf[..];
+
+        assert_eq!(marker, write_sint(&mut wr, val).ok().unwrap(), "value {}", val);
+        let len = 9 - wr.len();
+        assert_eq!(expected, &buf[..len], "value {}", val);
+        assert_eq!(val, read_int::<i64, _>(&mut &buf[..len]).unwrap(), "value {}", val);
+    }
+}
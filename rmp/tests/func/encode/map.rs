@@ -27,3 +27,22 @@ fn pass_pack_len_u32() {
 
     assert_eq!([0xdf, 0xff, 0xff, 0xff, 0xff], buf);
 }
+
+#[test]
+fn pass_pack_len_then_manual_pairs() {
+    let mut buf = Vec::new();
+
+    assert_eq!(Marker::FixMap(2), write_map_len(&mut buf, 2).unwrap());
+    for (key, val) in [(1, true), (2, false)] {
+        write_uint(&mut buf, key).unwrap();
+        write_bool(&mut buf, val).unwrap();
+    }
+
+    let mut rd = &buf[..];
+    assert_eq!(2, crate::msgpack::decode::read_map_len(&mut rd).unwrap());
+    for (key, val) in [(1, true), (2, false)] {
+        assert_eq!(key, crate::msgpack::decode::read_int::<u64, _>(&mut rd).unwrap());
+        assert_eq!(val, crate::msgpack::decode::read_bool(&mut rd).unwrap());
+    }
+    assert!(rd.is_empty());
+}
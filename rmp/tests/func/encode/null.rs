@@ -14,3 +14,14 @@ fn fail_pack_too_small_buffer() {
     let mut buf = [];
     write_nil(&mut &mut buf[..]).err().unwrap();
 }
+
+#[test]
+#[cfg(feature = "std")]
+fn pass_pack_into_cursor() {
+    let mut cur = std::io::Cursor::new(Vec::new());
+
+    write_nil(&mut cur).unwrap();
+
+    assert_eq!(1, cur.position());
+    assert_eq!(vec![0xc0], cur.into_inner());
+}
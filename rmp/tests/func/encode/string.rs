@@ -36,3 +36,53 @@ fn pass_pack_len_u32() {
 
     assert_eq!([0xdb, 0xff, 0xff, 0xff, 0xff], buf);
 }
+
+#[test]
+fn pass_pack_len_then_manual_payload() {
+    let mut buf = Vec::new();
+
+    assert_eq!(Marker::Str8, write_str_len(&mut buf, 40).unwrap());
+    buf.extend_from_slice(&[b'x'; 40]);
+
+    let mut out = [0u8; 64];
+    let mut rd = &buf[..];
+    assert_eq!("x".repeat(40), crate::msgpack::decode::read_str(&mut rd, &mut out).unwrap());
+    assert!(rd.is_empty());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn pass_pack_from_reader_exact() {
+    let mut src = std::io::Cursor::new("le message");
+    let mut buf = Vec::new();
+
+    write_str_from_reader(&mut buf, 10, &mut src).unwrap();
+
+    assert_eq!(b"\xaale message", &buf[..]);
+    assert_eq!(10, src.position());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn pass_pack_from_reader_leaves_rest() {
+    let mut src = std::io::Cursor::new("le message");
+    let mut buf = Vec::new();
+
+    write_str_from_reader(&mut buf, 2, &mut src).unwrap();
+
+    assert_eq!(b"\xa2le", &buf[..]);
+    assert_eq!(2, src.position());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn fail_pack_from_reader_short() {
+    let mut src = std::io::Cursor::new("le");
+    let mut buf = Vec::new();
+
+    match write_str_from_reader(&mut buf, 10, &mut src) {
+        Err(ValueWriteError::InvalidDataWrite(err)) => assert_eq!(std::io::ErrorKind::UnexpectedEof, err.kind()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(b"\xaale", &buf[..]);
+}
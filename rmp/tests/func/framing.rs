@@ -0,0 +1,49 @@
+use msgpack::decode::{Bytes, ValueReadError};
+use msgpack::encode::ByteBuf;
+use crate::msgpack::framing::*;
+
+#[test]
+fn pass_u24_round_trip() {
+    for val in [0, 1, 0xff, 0x100, 0x12_3456, U24_MAX] {
+        let mut buf = ByteBuf::new();
+        write_u24_be(&mut buf, val).unwrap();
+
+        assert_eq!(3, buf.as_slice().len());
+        assert_eq!(val, read_u24_be(&mut Bytes::new(buf.as_slice())).unwrap());
+    }
+}
+
+#[test]
+fn pass_u24_max() {
+    let mut buf = ByteBuf::new();
+    write_u24_be(&mut buf, 0xff_ffff).unwrap();
+
+    assert_eq!([0xff, 0xff, 0xff], buf.as_slice());
+}
+
+#[test]
+#[should_panic]
+fn fail_u24_too_large() {
+    let mut buf = ByteBuf::new();
+    let _ = write_u24_be(&mut buf, 0x100_0000);
+}
+
+#[test]
+fn fail_u24_truncated() {
+    let buf = [0x12, 0x34];
+    let mut rd = Bytes::new(&buf);
+
+    match read_u24_be(&mut rd) {
+        Err(ValueReadError::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn pass_u16_round_trip() {
+    let mut buf = ByteBuf::new();
+    write_u16_be(&mut buf, 0xbeef).unwrap();
+
+    assert_eq!([0xbe, 0xef], buf.as_slice());
+    assert_eq!(0xbeef, read_u16_be(&mut Bytes::new(buf.as_slice())).unwrap());
+}
@@ -1,3 +1,4 @@
 mod decode;
 mod encode;
+mod framing;
 mod mirror;
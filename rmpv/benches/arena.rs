@@ -0,0 +1,55 @@
+#![feature(test)]
+
+extern crate test;
+
+use bumpalo::Bump;
+use test::Bencher;
+
+use rmp::encode;
+use rmpv::decode::{read_value, read_value_in};
+
+const MESSAGES: u32 = 10_000;
+
+/// Encodes `MESSAGES` requests like `{"id": 42, "method": "get", "params": ["users", b"\x01\x02"]}`,
+/// one after another.
+fn messages() -> Vec<u8> {
+    let mut buf = Vec::new();
+    for id in 0..MESSAGES {
+        encode::write_map_len(&mut buf, 3).unwrap();
+        encode::write_str(&mut buf, "id").unwrap();
+        encode::write_uint(&mut buf, u64::from(id)).unwrap();
+        encode::write_str(&mut buf, "method").unwrap();
+        encode::write_str(&mut buf, "get").unwrap();
+        encode::write_str(&mut buf, "params").unwrap();
+        encode::write_array_len(&mut buf, 2).unwrap();
+        encode::write_str(&mut buf, "users").unwrap();
+        encode::write_bin(&mut buf, &[1, 2]).unwrap();
+    }
+    buf
+}
+
+#[bench]
+fn from_messages_read_value(b: &mut Bencher) {
+    let buf = messages();
+
+    b.iter(|| {
+        let mut rd = &buf[..];
+        for _ in 0..MESSAGES {
+            test::black_box(read_value(&mut rd).unwrap());
+        }
+    });
+}
+
+#[bench]
+fn from_messages_read_value_in_reused_arena(b: &mut Bencher) {
+    let buf = messages();
+    let mut arena = Bump::new();
+
+    b.iter(|| {
+        let mut rd = &buf[..];
+        for _ in 0..MESSAGES {
+            test::black_box(read_value_in(&mut rd, &arena).unwrap());
+            arena.reset();
+        }
+    });
+}
@@ -0,0 +1,131 @@
+#![feature(test)]
+
+extern crate test;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use test::Bencher;
+
+use rmp::encode;
+use rmpv::decode::{
+    read_string_map, read_string_map_interned, read_value, read_value_with_strings, DecodeConfig, KeyInterner,
+};
+
+/// Counts allocations, to compare how many each way of decoding the records performs. The counts
+/// are printed when running with `--nocapture`.
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const RECORDS: u32 = 10_000;
+
+/// Encodes `RECORDS` maps like `{"id": 42, "name": "rmp", "active": true}`, one after another.
+fn records() -> Vec<u8> {
+    let mut buf = Vec::new();
+    for id in 0..RECORDS {
+        encode::write_map_len(&mut buf, 3).unwrap();
+        encode::write_str(&mut buf, "id").unwrap();
+        encode::write_uint(&mut buf, u64::from(id)).unwrap();
+        encode::write_str(&mut buf, "name").unwrap();
+        encode::write_str(&mut buf, "rmp").unwrap();
+        encode::write_str(&mut buf, "active").unwrap();
+        encode::write_bool(&mut buf, id % 2 == 0).unwrap();
+    }
+    buf
+}
+
+/// Runs `f` once and returns the number of allocations it performed.
+fn count_allocations<F: FnOnce()>(f: F) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn decode_plain(buf: &[u8]) {
+    let mut rd = buf;
+    for _ in 0..RECORDS {
+        test::black_box(read_string_map(&mut rd).unwrap());
+    }
+}
+
+fn decode_interned(buf: &[u8]) {
+    let mut rd = buf;
+    let mut keys = KeyInterner::new();
+    for _ in 0..RECORDS {
+        test::black_box(read_string_map_interned(&mut rd, &mut keys).unwrap());
+    }
+}
+
+#[bench]
+fn from_records_read_string_map(b: &mut Bencher) {
+    let buf = records();
+    eprintln!("read_string_map: {} allocations", count_allocations(|| decode_plain(&buf)));
+
+    b.iter(|| decode_plain(&buf));
+}
+
+#[bench]
+fn from_records_read_string_map_interned(b: &mut Bencher) {
+    let buf = records();
+    eprintln!("read_string_map_interned: {} allocations", count_allocations(|| decode_interned(&buf)));
+
+    b.iter(|| decode_interned(&buf));
+}
+
+const CATEGORIES: [&str; 4] = ["pending", "active", "suspended", "closed"];
+
+/// Encodes an array of `RECORDS` records like `["rmp", "active"]`, whose second field takes one of
+/// a few `CATEGORIES`.
+fn categorized_records() -> Vec<u8> {
+    let mut buf = Vec::new();
+    encode::write_array_len(&mut buf, RECORDS).unwrap();
+    for id in 0..RECORDS {
+        encode::write_array_len(&mut buf, 2).unwrap();
+        encode::write_str(&mut buf, "rmp").unwrap();
+        encode::write_str(&mut buf, CATEGORIES[id as usize % CATEGORIES.len()]).unwrap();
+    }
+    buf
+}
+
+fn decode_categories_plain(buf: &[u8]) {
+    test::black_box(read_value(&mut &buf[..]).unwrap());
+}
+
+fn decode_categories_deduplicated(buf: &[u8], strings: &HashSet<Arc<str>>) {
+    test::black_box(read_value_with_strings(&mut &buf[..], &DecodeConfig::default(), strings).unwrap());
+}
+
+#[bench]
+fn from_categorized_records_read_value(b: &mut Bencher) {
+    let buf = categorized_records();
+    eprintln!("read_value: {} allocations", count_allocations(|| decode_categories_plain(&buf)));
+
+    b.iter(|| decode_categories_plain(&buf));
+}
+
+#[bench]
+fn from_categorized_records_read_value_with_strings(b: &mut Bencher) {
+    let buf = categorized_records();
+    let strings = CATEGORIES.iter().map(|&s| Arc::from(s)).collect();
+    eprintln!("read_value_with_strings: {} allocations",
+        count_allocations(|| decode_categories_deduplicated(&buf, &strings)));
+
+    b.iter(|| decode_categories_deduplicated(&buf, &strings));
+}
@@ -28,6 +28,9 @@ const COMPLEX: [u8; 137] = [
     0x52,
 ];
 
+/// Offset of the `{0: ["read", ...], ...}` map inside [`COMPLEX`].
+const COMPLEX_MAP_OFFSET: usize = 20;
+
 #[bench]
 fn from_string_read_value(b: &mut Bencher) {
     // Lorem ipsum dolor sit amet.
@@ -58,6 +61,38 @@ fn from_string_read_value_ref(b: &mut Bencher) {
     });
 }
 
+#[bench]
+fn from_complex_read_array_len(b: &mut Bencher) {
+    b.iter(|| {
+        let res = rmp::decode::read_array_len(&mut &COMPLEX[..]).unwrap();
+        test::black_box(res);
+    });
+}
+
+#[bench]
+fn from_complex_read_map_len(b: &mut Bencher) {
+    let buf = &COMPLEX[COMPLEX_MAP_OFFSET..];
+
+    b.iter(|| {
+        let res = rmp::decode::read_map_len(&mut &buf[..]).unwrap();
+        test::black_box(res);
+    });
+}
+
+#[bench]
+fn from_complex_read_value_with_alloc_limit(b: &mut Bencher) {
+    let config = DecodeConfig {
+        max_total_alloc: Some(64 * 1024),
+        ..DecodeConfig::default()
+    };
+
+    b.iter(|| {
+        let res = read_value_with_config(&mut &COMPLEX[..], &config).unwrap();
+        test::black_box(res);
+    });
+    b.bytes = COMPLEX.len() as u64;
+}
+
 #[bench]
 fn from_complex_read_value(b: &mut Bencher) {
     b.iter(|| {
@@ -0,0 +1,152 @@
+//! Incremental construction of array and map [`Value`]s, checked against their declared length.
+//!
+//! # Examples
+//!
+//! ```
+//! use rmpv::Value;
+//!
+//! let mut builder = Value::map_builder(2);
+//! builder.key("id").unwrap().value(42).unwrap();
+//! builder.key("name").unwrap().value("John").unwrap();
+//!
+//! let val = builder.finish().unwrap();
+//! assert_eq!(Value::from(42), val["id"]);
+//! ```
+
+use std::error;
+use std::fmt::{self, Display, Formatter};
+
+use crate::Value;
+
+/// An error returned when a container is built inconsistently.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BuildError {
+    /// The number of elements or entries differs from the declared one.
+    LengthMismatch {
+        /// The length declared when creating the builder.
+        declared: usize,
+        /// The number of elements or entries actually appended.
+        actual: usize,
+    },
+    /// A key was appended while the previous one is still waiting for its value, or the map was
+    /// finished then.
+    DanglingKey,
+    /// A value was appended without a key.
+    MissingKey,
+}
+
+impl Display for BuildError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        match *self {
+            BuildError::LengthMismatch { declared, actual } => {
+                write!(fmt, "declared length {} but got {} items", declared, actual)
+            }
+            BuildError::DanglingKey => fmt.write_str("map key without a value"),
+            BuildError::MissingKey => fmt.write_str("map value without a key"),
+        }
+    }
+}
+
+impl error::Error for BuildError {}
+
+/// A builder of [`Value::Array`], created by [`Value::array_builder`].
+#[derive(Clone, Debug)]
+pub struct ArrayBuilder {
+    len: usize,
+    vec: Vec<Value>,
+}
+
+impl ArrayBuilder {
+    pub(crate) fn new(len: usize) -> Self {
+        Self { len, vec: Vec::with_capacity(len) }
+    }
+
+    /// Appends an element.
+    #[inline]
+    pub fn push<V: Into<Value>>(&mut self, val: V) -> &mut Self {
+        self.vec.push(val.into());
+        self
+    }
+
+    /// Returns the built array.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BuildError::LengthMismatch`] if the number of elements appended differs from the
+    /// declared length.
+    pub fn finish(self) -> Result<Value, BuildError> {
+        if self.vec.len() != self.len {
+            return Err(BuildError::LengthMismatch { declared: self.len, actual: self.vec.len() });
+        }
+
+        Ok(Value::Array(self.vec))
+    }
+}
+
+/// A builder of [`Value::Map`], created by [`Value::map_builder`].
+///
+/// Entries are appended by a [`key`](MapBuilder::key) followed by its [`value`](MapBuilder::value).
+#[derive(Clone, Debug)]
+pub struct MapBuilder {
+    len: usize,
+    map: Vec<(Value, Value)>,
+    key: Option<Value>,
+}
+
+impl MapBuilder {
+    pub(crate) fn new(len: usize) -> Self {
+        Self { len, map: Vec::with_capacity(len), key: None }
+    }
+
+    /// Appends the key of the next entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BuildError::DanglingKey`] if the previous key hasn't got its value yet.
+    pub fn key<K: Into<Value>>(&mut self, key: K) -> Result<&mut Self, BuildError> {
+        if self.key.is_some() {
+            return Err(BuildError::DanglingKey);
+        }
+
+        self.key = Some(key.into());
+        Ok(self)
+    }
+
+    /// Appends the value of the entry whose key has just been appended.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BuildError::MissingKey`] if there is no such key.
+    pub fn value<V: Into<Value>>(&mut self, val: V) -> Result<&mut Self, BuildError> {
+        let key = self.key.take().ok_or(BuildError::MissingKey)?;
+        self.map.push((key, val.into()));
+        Ok(self)
+    }
+
+    /// Appends a whole entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BuildError::DanglingKey`] if the previous key hasn't got its value yet.
+    pub fn entry<K: Into<Value>, V: Into<Value>>(&mut self, key: K, val: V) -> Result<&mut Self, BuildError> {
+        self.key(key)?.value(val)
+    }
+
+    /// Returns the built map.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BuildError::DanglingKey`] if the last key hasn't got its value, and
+    /// [`BuildError::LengthMismatch`] if the number of entries appended differs from the declared
+    /// length.
+    pub fn finish(self) -> Result<Value, BuildError> {
+        if self.key.is_some() {
+            return Err(BuildError::DanglingKey);
+        }
+        if self.map.len() != self.len {
+            return Err(BuildError::LengthMismatch { declared: self.len, actual: self.map.len() });
+        }
+
+        Ok(Value::Map(self.map))
+    }
+}
@@ -0,0 +1,124 @@
+use std::io::Read;
+
+use rmp::decode::{read_marker, RmpRead};
+use rmp::Marker;
+
+use super::{read_value, Error};
+use crate::Value;
+
+/// The markers a value and all the values it contains were encoded with, mirroring its structure.
+///
+/// Returned by [`read_value_annotated`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Annotation {
+    /// The marker of a value that isn't a container, such as an integer, a string or an extension.
+    Scalar(Marker),
+    /// The marker of an array and the annotations of its elements.
+    Array(Marker, Vec<Annotation>),
+    /// The marker of a map and the annotations of its keys and values.
+    Map(Marker, Vec<(Annotation, Annotation)>),
+}
+
+impl Annotation {
+    /// Returns the marker of the annotated value itself.
+    #[inline]
+    #[must_use]
+    pub fn marker(&self) -> Marker {
+        match *self {
+            Annotation::Scalar(marker) |
+            Annotation::Array(marker, ..) |
+            Annotation::Map(marker, ..) => marker,
+        }
+    }
+}
+
+/// Attempts to read a [`Value`], along with the marker every value in it was encoded with.
+///
+/// The markers tell apart the encodings a `Value` doesn't keep, such as a small integer sent as a
+/// `U32` instead of a fixint, or a short string sent as a `Str16`, for transcoders that preserve
+/// them and linters that report them.
+///
+/// The [`Annotation`] tree is built next to the value and has one node per value, so it roughly
+/// doubles the memory the decoded tree takes.
+///
+/// # Errors
+///
+/// Returns the same errors as [`read_value`], including [`Error::DepthLimitExceeded`] past
+/// [`MAX_DEPTH`](super::MAX_DEPTH) levels of nesting.
+///
+/// # Examples
+///
+/// ```
+/// use rmp::Marker;
+/// use rmpv::decode::{read_value_annotated, Annotation};
+/// use rmpv::Value;
+///
+/// let buf = [0x91, 0xcd, 0x00, 0x01];
+///
+/// let (val, annotation) = read_value_annotated(&mut &buf[..]).unwrap();
+///
+/// assert_eq!(Value::Array(vec![Value::from(1)]), val);
+/// assert_eq!(Annotation::Array(Marker::FixArray(1), vec![Annotation::Scalar(Marker::U16)]), annotation);
+/// ```
+pub fn read_value_annotated<R: Read>(rd: &mut R) -> Result<(Value, Annotation), Error> {
+    read_annotated(rd, super::MAX_DEPTH)
+}
+
+fn read_annotated<R: Read>(rd: &mut R, depth: usize) -> Result<(Value, Annotation), Error> {
+    let depth = super::decrement_depth(depth)?;
+    let marker = read_marker(rd)?;
+
+    match marker {
+        Marker::FixArray(len) => read_array(rd, marker, len.into(), depth),
+        Marker::Array16 => {
+            let len = rd.read_data_u16()?;
+            read_array(rd, marker, len.into(), depth)
+        }
+        Marker::Array32 => {
+            let len = rd.read_data_u32()?;
+            read_array(rd, marker, len, depth)
+        }
+        Marker::FixMap(len) => read_map(rd, marker, len.into(), depth),
+        Marker::Map16 => {
+            let len = rd.read_data_u16()?;
+            read_map(rd, marker, len.into(), depth)
+        }
+        Marker::Map32 => {
+            let len = rd.read_data_u32()?;
+            read_map(rd, marker, len, depth)
+        }
+        marker => {
+            // Scalars are decoded as usual, from their marker on.
+            let buf = [marker.to_u8()];
+            let val = read_value(&mut (&buf[..]).chain(rd))?;
+            Ok((val, Annotation::Scalar(marker)))
+        }
+    }
+}
+
+fn read_array<R: Read>(rd: &mut R, marker: Marker, len: u32, depth: usize) -> Result<(Value, Annotation), Error> {
+    let mut vals = super::safe_with_capacity(len as usize, super::CONTAINER_PREALLOC_MAX);
+    let mut annotations = super::safe_with_capacity(len as usize, super::CONTAINER_PREALLOC_MAX);
+
+    for _ in 0..len {
+        let (val, annotation) = read_annotated(rd, depth)?;
+        vals.push(val);
+        annotations.push(annotation);
+    }
+
+    Ok((Value::Array(vals), Annotation::Array(marker, annotations)))
+}
+
+fn read_map<R: Read>(rd: &mut R, marker: Marker, len: u32, depth: usize) -> Result<(Value, Annotation), Error> {
+    let mut entries = super::safe_with_capacity(len as usize, super::CONTAINER_PREALLOC_MAX);
+    let mut annotations = super::safe_with_capacity(len as usize, super::CONTAINER_PREALLOC_MAX);
+
+    for _ in 0..len {
+        let (key, key_annotation) = read_annotated(rd, depth)?;
+        let (val, val_annotation) = read_annotated(rd, depth)?;
+        entries.push((key, val));
+        annotations.push((key_annotation, val_annotation));
+    }
+
+    Ok((Value::Map(entries), Annotation::Map(marker, annotations)))
+}
@@ -0,0 +1,250 @@
+use std::cmp;
+use std::io::Read;
+use std::str;
+
+use bumpalo::collections::Vec as BumpVec;
+use bumpalo::Bump;
+use rmp::decode::{read_marker, RmpRead};
+use rmp::Marker;
+
+use super::Error;
+use crate::{Integer, Utf8StringRef, Value};
+
+/// A MessagePack value whose strings, binaries and containers are allocated in a [`Bump`] arena.
+///
+/// Returned by [`read_value_in`]. It borrows the arena, so the whole tree is freed at once when
+/// the arena is reset or dropped, rather than node by node.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArenaValue<'a> {
+    /// Nil represents nil.
+    Nil,
+    /// Boolean represents true or false.
+    Boolean(bool),
+    /// Integer represents an integer.
+    Integer(Integer),
+    /// A 32-bit floating point number.
+    F32(f32),
+    /// A 64-bit floating point number.
+    F64(f64),
+    /// String extending Raw type represents a UTF-8 string.
+    String(Utf8StringRef<'a>),
+    /// Binary extending Raw type represents a byte array.
+    Binary(&'a [u8]),
+    /// Array represents a sequence of objects.
+    Array(&'a [ArenaValue<'a>]),
+    /// Map represents key-value pairs of objects.
+    Map(&'a [(ArenaValue<'a>, ArenaValue<'a>)]),
+    /// Extended implements Extension interface: represents a tuple of type information and a byte
+    /// array where type information is an integer whose meaning is defined by applications.
+    Ext(i8, &'a [u8]),
+}
+
+impl<'a> ArenaValue<'a> {
+    /// Converts the arena value into an owned [`Value`], copying its data out of the arena.
+    #[must_use]
+    pub fn to_owned(&self) -> Value {
+        match *self {
+            ArenaValue::Nil => Value::Nil,
+            ArenaValue::Boolean(val) => Value::Boolean(val),
+            ArenaValue::Integer(val) => Value::Integer(val),
+            ArenaValue::F32(val) => Value::F32(val),
+            ArenaValue::F64(val) => Value::F64(val),
+            ArenaValue::String(val) => Value::String(val.into()),
+            ArenaValue::Binary(val) => Value::Binary(val.to_vec()),
+            ArenaValue::Array(val) => Value::Array(val.iter().map(|v| v.to_owned()).collect()),
+            ArenaValue::Map(val) => Value::Map(val.iter().map(|(k, v)| (k.to_owned(), v.to_owned())).collect()),
+            ArenaValue::Ext(ty, buf) => Value::Ext(ty, buf.to_vec()),
+        }
+    }
+}
+
+/// Attempts to read a value from the given reader, allocating its strings, binaries and
+/// containers in `arena`.
+///
+/// This suits request-scoped decoding, where the whole tree is dropped together: allocating in a
+/// bump arena is a pointer increment, and freeing amounts to resetting the arena, which can then
+/// be reused for the next message without returning its memory to the allocator.
+///
+/// Declared lengths aren't trusted, as with [`read_value`](super::read_value): data is allocated
+/// as it arrives.
+///
+/// # Errors
+///
+/// Returns the same errors as [`read_value`](super::read_value), including
+/// [`Error::DepthLimitExceeded`] past [`MAX_DEPTH`](super::MAX_DEPTH) levels of nesting. What has
+/// been allocated before the error stays in the arena until it is reset.
+///
+/// # Examples
+///
+/// ```
+/// use bumpalo::Bump;
+/// use rmpv::decode::{read_value_in, ArenaValue};
+/// use rmpv::Value;
+///
+/// let mut arena = Bump::new();
+///
+/// for buf in [&[0x91, 0xa2, 0x6f, 0x6b][..], &[0x92, 0x01, 0x02][..]] {
+///     let val = read_value_in(&mut &buf[..], &arena).unwrap();
+///     assert!(matches!(val, ArenaValue::Array(..)));
+///     arena.reset();
+/// }
+///
+/// let val = read_value_in(&mut &[0xa2, 0x6f, 0x6b][..], &arena).unwrap();
+/// assert_eq!(Value::from("ok"), val.to_owned());
+/// ```
+pub fn read_value_in<'a, R: Read>(rd: &mut R, arena: &'a Bump) -> Result<ArenaValue<'a>, Error> {
+    read_arena_value(rd, arena, super::MAX_DEPTH)
+}
+
+fn read_arena_value<'a, R: Read>(rd: &mut R, arena: &'a Bump, depth: usize) -> Result<ArenaValue<'a>, Error> {
+    let depth = super::decrement_depth(depth)?;
+
+    match read_marker(rd)? {
+        Marker::FixArray(len) => read_array(rd, len.into(), arena, depth),
+        Marker::Array16 => {
+            let len = rd.read_data_u16()?;
+            read_array(rd, len.into(), arena, depth)
+        }
+        Marker::Array32 => {
+            let len = rd.read_data_u32()?;
+            read_array(rd, len, arena, depth)
+        }
+        Marker::FixMap(len) => read_map(rd, len.into(), arena, depth),
+        Marker::Map16 => {
+            let len = rd.read_data_u16()?;
+            read_map(rd, len.into(), arena, depth)
+        }
+        Marker::Map32 => {
+            let len = rd.read_data_u32()?;
+            read_map(rd, len, arena, depth)
+        }
+        marker => read_leaf(rd, marker, arena),
+    }
+}
+
+/// Reads a value that isn't a container, whose marker has been read.
+///
+/// It is kept out of [`read_arena_value`] so that the frames of the recursion stay small.
+#[inline(never)]
+fn read_leaf<'a, R: Read>(rd: &mut R, marker: Marker, arena: &'a Bump) -> Result<ArenaValue<'a>, Error> {
+    let val = match marker {
+        Marker::Null => ArenaValue::Nil,
+        Marker::True => ArenaValue::Boolean(true),
+        Marker::False => ArenaValue::Boolean(false),
+        Marker::FixPos(val) => ArenaValue::Integer(Integer::from(val)),
+        Marker::FixNeg(val) => ArenaValue::Integer(Integer::from(val)),
+        Marker::U8 => ArenaValue::Integer(Integer::from(rd.read_data_u8()?)),
+        Marker::U16 => ArenaValue::Integer(Integer::from(rd.read_data_u16()?)),
+        Marker::U32 => ArenaValue::Integer(Integer::from(rd.read_data_u32()?)),
+        Marker::U64 => ArenaValue::Integer(Integer::from(rd.read_data_u64()?)),
+        Marker::I8 => ArenaValue::Integer(Integer::from(rd.read_data_i8()?)),
+        Marker::I16 => ArenaValue::Integer(Integer::from(rd.read_data_i16()?)),
+        Marker::I32 => ArenaValue::Integer(Integer::from(rd.read_data_i32()?)),
+        Marker::I64 => ArenaValue::Integer(Integer::from(rd.read_data_i64()?)),
+        Marker::F32 => ArenaValue::F32(rd.read_data_f32()?),
+        Marker::F64 => ArenaValue::F64(rd.read_data_f64()?),
+        Marker::FixStr(len) => read_str(rd, len.into(), arena)?,
+        Marker::Str8 => {
+            let len = rd.read_data_u8()?;
+            read_str(rd, len.into(), arena)?
+        }
+        Marker::Str16 => {
+            let len = rd.read_data_u16()?;
+            read_str(rd, len.into(), arena)?
+        }
+        Marker::Str32 => {
+            let len = rd.read_data_u32()?;
+            read_str(rd, len, arena)?
+        }
+        Marker::Bin8 => {
+            let len = rd.read_data_u8()?;
+            ArenaValue::Binary(read_bytes(rd, len.into(), arena)?)
+        }
+        Marker::Bin16 => {
+            let len = rd.read_data_u16()?;
+            ArenaValue::Binary(read_bytes(rd, len.into(), arena)?)
+        }
+        Marker::Bin32 => {
+            let len = rd.read_data_u32()?;
+            ArenaValue::Binary(read_bytes(rd, len, arena)?)
+        }
+        Marker::FixExt1 => read_ext(rd, 1, arena)?,
+        Marker::FixExt2 => read_ext(rd, 2, arena)?,
+        Marker::FixExt4 => read_ext(rd, 4, arena)?,
+        Marker::FixExt8 => read_ext(rd, 8, arena)?,
+        Marker::FixExt16 => read_ext(rd, 16, arena)?,
+        Marker::Ext8 => {
+            let len = rd.read_data_u8()?;
+            read_ext(rd, len.into(), arena)?
+        }
+        Marker::Ext16 => {
+            let len = rd.read_data_u16()?;
+            read_ext(rd, len.into(), arena)?
+        }
+        Marker::Ext32 => {
+            let len = rd.read_data_u32()?;
+            read_ext(rd, len, arena)?
+        }
+        Marker::FixArray(..) |
+        Marker::Array16 |
+        Marker::Array32 |
+        Marker::FixMap(..) |
+        Marker::Map16 |
+        Marker::Map32 |
+        Marker::Reserved => ArenaValue::Nil,
+    };
+
+    Ok(val)
+}
+
+/// Reads exactly `len` bytes into the arena, growing the allocation as data arrives rather than
+/// trusting `len`.
+fn read_bytes<'a, R: Read>(rd: &mut R, len: u32, arena: &'a Bump) -> Result<&'a [u8], Error> {
+    let len = super::checked_len(len)?;
+    let mut buf = BumpVec::with_capacity_in(cmp::min(len, super::PREALLOC_MAX), arena);
+
+    while buf.len() < len {
+        let start = buf.len();
+        buf.resize(cmp::min(len, start + super::PREALLOC_MAX), 0);
+        rd.read_exact(&mut buf[start..]).map_err(Error::InvalidDataRead)?;
+    }
+
+    Ok(buf.into_bump_slice())
+}
+
+fn read_str<'a, R: Read>(rd: &mut R, len: u32, arena: &'a Bump) -> Result<ArenaValue<'a>, Error> {
+    let buf = read_bytes(rd, len, arena)?;
+    let s = match str::from_utf8(buf) {
+        Ok(s) => Utf8StringRef::from(s),
+        Err(err) => Utf8StringRef { s: Err((buf, err)) },
+    };
+
+    Ok(ArenaValue::String(s))
+}
+
+fn read_ext<'a, R: Read>(rd: &mut R, len: u32, arena: &'a Bump) -> Result<ArenaValue<'a>, Error> {
+    let ty = rd.read_data_i8()?;
+    Ok(ArenaValue::Ext(ty, read_bytes(rd, len, arena)?))
+}
+
+fn read_array<'a, R: Read>(rd: &mut R, len: u32, arena: &'a Bump, depth: usize) -> Result<ArenaValue<'a>, Error> {
+    let len = len as usize;
+    let mut vec = BumpVec::with_capacity_in(cmp::min(len, super::CONTAINER_PREALLOC_MAX), arena);
+    for _ in 0..len {
+        vec.push(read_arena_value(rd, arena, depth)?);
+    }
+
+    Ok(ArenaValue::Array(vec.into_bump_slice()))
+}
+
+fn read_map<'a, R: Read>(rd: &mut R, len: u32, arena: &'a Bump, depth: usize) -> Result<ArenaValue<'a>, Error> {
+    let len = len as usize;
+    let mut vec = BumpVec::with_capacity_in(cmp::min(len, super::CONTAINER_PREALLOC_MAX), arena);
+    for _ in 0..len {
+        let key = read_arena_value(rd, arena, depth)?;
+        let val = read_arena_value(rd, arena, depth)?;
+        vec.push((key, val));
+    }
+
+    Ok(ArenaValue::Map(vec.into_bump_slice()))
+}
@@ -0,0 +1,143 @@
+use std::cmp::min;
+
+use rmp::Marker;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use super::raw::{layout, Body};
+use super::{read_value, Error};
+use crate::Value;
+
+/// The largest number of bytes requested from the reader at once.
+const CHUNK_MAX: usize = 64 * 1024; // 64 KiB
+
+/// Scans a value as its bytes arrive, to tell how many more it needs.
+///
+/// The state is kept between calls: each resumes after the last item that was complete, so that a
+/// value arriving in many pieces isn't rescanned from its start every time.
+struct Scanner {
+    /// The position of the first item not completely scanned yet.
+    pos: usize,
+    /// Values that are known to follow but haven't been scanned yet. Each takes at least one byte.
+    pending: u64,
+}
+
+impl Scanner {
+    fn new() -> Self {
+        Self { pos: 0, pending: 1 }
+    }
+
+    /// Returns the minimum number of bytes that must be appended to `buf` for it to hold a
+    /// complete value, or `0` if it already does.
+    ///
+    /// The result never exceeds the number of bytes the value actually lacks, so reading exactly
+    /// that many bytes never consumes anything past the end of the value. `buf` must only have
+    /// been appended to since the previous call.
+    fn missing_len(&mut self, buf: &[u8]) -> usize {
+        while self.pending > 0 {
+            let mut pos = self.pos;
+            let mut pending = self.pending;
+
+            let marker = match buf.get(pos) {
+                Some(&byte) => Marker::from_u8(byte),
+                None => return clamp(pending),
+            };
+            pos += 1;
+            pending -= 1;
+
+            let (header, body) = layout(marker);
+
+            let len = match buf.get(pos..pos + header) {
+                Some(bytes) => bytes.iter().fold(0u64, |acc, &b| (acc << 8) | u64::from(b)),
+                None => return pos + header - buf.len(),
+            };
+            pos += header;
+
+            let skip = match body {
+                Body::Skip(n) => n,
+                Body::Bytes => len,
+                Body::Ext => 1 + len,
+                Body::Values(per_item) => {
+                    pending = pending.saturating_add(len * per_item);
+                    0
+                }
+                Body::Count(n) => {
+                    pending = pending.saturating_add(n);
+                    0
+                }
+            };
+            let available = (buf.len() - pos) as u64;
+            if skip > available {
+                return clamp(skip - available);
+            }
+            pos += skip as usize;
+
+            // The item is complete, so it isn't scanned again.
+            self.pos = pos;
+            self.pending = pending;
+        }
+
+        0
+    }
+}
+
+#[inline]
+fn clamp(len: u64) -> usize {
+    usize::try_from(len).unwrap_or(usize::MAX)
+}
+
+/// Attempts to read bytes from the given asynchronous reader and interpret them as a [`Value`].
+///
+/// The bytes are buffered as they arrive, requesting from the reader at most as many bytes as the
+/// value is known to still lack, so nothing past the end of the value is consumed. Once the value
+/// is complete, it is decoded with [`read_value`].
+///
+/// This function is available with the `tokio` feature.
+///
+/// # Errors
+///
+/// This function will return [`Error`] on any I/O error while reading, including an unexpected
+/// end of the stream, and on any error [`read_value`] returns for the buffered bytes.
+///
+/// # Examples
+///
+/// ```
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// use rmpv::decode::read_value_async;
+/// use rmpv::Value;
+///
+/// let buf = [0x92, 0x01, 0xa2, 0x6f, 0x6b, 0xc0];
+/// let mut rd = &buf[..];
+///
+/// let val = read_value_async(&mut rd).await.unwrap();
+///
+/// assert_eq!(Value::Array(vec![Value::from(1), Value::from("ok")]), val);
+/// // The trailing nil is left in the reader.
+/// assert_eq!([0xc0], rd);
+/// # }
+/// ```
+pub async fn read_value_async<R>(rd: &mut R) -> Result<Value, Error>
+    where R: AsyncRead + Unpin
+{
+    let mut buf = Vec::new();
+    let mut scanner = Scanner::new();
+
+    loop {
+        let missing = scanner.missing_len(&buf);
+        if missing == 0 {
+            break;
+        }
+
+        let start = buf.len();
+        buf.resize(start + min(missing, CHUNK_MAX), 0);
+        rd.read_exact(&mut buf[start..]).await.map_err(|err| {
+            if start == 0 {
+                Error::InvalidMarkerRead(err)
+            } else {
+                Error::InvalidDataRead(err)
+            }
+        })?;
+    }
+
+    read_value(&mut &buf[..])
+}
@@ -0,0 +1,121 @@
+use std::io::{self, ErrorKind, Read};
+
+use rmp::decode::{read_array_len, read_map_len};
+
+use super::{read_value, skip_value, Error};
+use crate::Value;
+
+/// The fields of an array of records, decoded by [`read_records_columnar`] into a column each.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Columns {
+    names: Vec<String>,
+    columns: Vec<Vec<Value>>,
+    rows: usize,
+}
+
+impl Columns {
+    /// Returns the number of records, which is the length of every column.
+    #[inline]
+    #[must_use]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the field names, in the order of the schema.
+    #[inline]
+    #[must_use]
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Returns the values of the given field, one per record, or `None` if it isn't in the schema.
+    #[must_use]
+    pub fn column(&self, name: &str) -> Option<&[Value]> {
+        let idx = self.names.iter().position(|n| n == name)?;
+        Some(&self.columns[idx])
+    }
+
+    /// Returns the field names along with their columns, in the order of the schema.
+    #[must_use]
+    pub fn into_columns(self) -> Vec<(String, Vec<Value>)> {
+        self.names.into_iter().zip(self.columns).collect()
+    }
+}
+
+/// Reads a map key, returning the index of the field it names, without allocating it.
+fn read_field_key<R: Read>(rd: &mut R, fields: &[&str], buf: &mut Vec<u8>) -> Result<Option<usize>, Error> {
+    let key = super::read_str_key(rd, buf)?;
+    Ok(key.and_then(|key| fields.iter().position(|field| field.as_bytes() == key)))
+}
+
+/// Attempts to read an array of records, each a map keyed by field name, into a column of values
+/// per field of `fields`, rather than into a value per record.
+///
+/// Keeping the values of a field together is much more cache-friendly for analytics, which
+/// usually process a few fields over many records. The records are decoded one by one, straight
+/// into the columns, without building a tree of the whole array.
+///
+/// A record missing a field gets nil in its column, so that all the columns have a value per
+/// record. Fields not in `fields`, including those whose key isn't a string, are skipped without
+/// being decoded. When a record repeats a field, its last value is kept.
+///
+/// # Errors
+///
+/// This function will return [`Error`] on any I/O error while reading, including an unexpected end
+/// of the input, or if the value isn't an array of maps. The values of the fields are decoded with
+/// [`read_value`], so any of its errors may be returned as well. Returns
+/// [`Error::InvalidDataRead`] with the `InvalidInput` kind, before reading anything, if `fields`
+/// names a field more than once.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::read_records_columnar;
+/// use rmpv::Value;
+///
+/// // [{"x": 1, "y": "a"}, {"y": "b", "x": 2}]
+/// let buf = [0x92, 0x82, 0xa1, 0x78, 0x01, 0xa1, 0x79, 0xa1, 0x61, 0x82, 0xa1, 0x79, 0xa1, 0x62, 0xa1, 0x78, 0x02];
+///
+/// let columns = read_records_columnar(&mut &buf[..], &["x", "y"]).unwrap();
+///
+/// assert_eq!(Some(&[Value::from(1), Value::from(2)][..]), columns.column("x"));
+/// assert_eq!(Some(&[Value::from("a"), Value::from("b")][..]), columns.column("y"));
+/// ```
+pub fn read_records_columnar<R: Read>(rd: &mut R, fields: &[&str]) -> Result<Columns, Error> {
+    if fields.iter().enumerate().any(|(idx, field)| fields[..idx].contains(field)) {
+        return Err(Error::InvalidDataRead(io::Error::new(ErrorKind::InvalidInput, "duplicate field name")));
+    }
+
+    let rows = read_array_len(rd)? as usize;
+    let mut columns: Vec<Vec<Value>> = fields.iter()
+        .map(|_| super::safe_with_capacity(rows, super::CONTAINER_PREALLOC_MAX))
+        .collect();
+    let mut buf = Vec::new();
+
+    for row in 0..rows {
+        let len = read_map_len(rd)?;
+        for _ in 0..len {
+            match read_field_key(rd, fields, &mut buf)? {
+                Some(idx) => {
+                    let val = read_value(rd)?;
+                    let column = &mut columns[idx];
+                    if column.len() > row {
+                        column[row] = val;
+                    } else {
+                        column.push(val);
+                    }
+                }
+                None => skip_value(rd)?,
+            }
+        }
+
+        for column in &mut columns {
+            if column.len() == row {
+                column.push(Value::Nil);
+            }
+        }
+    }
+
+    let names = fields.iter().map(|&field| field.to_owned()).collect();
+    Ok(Columns { names, columns, rows })
+}
@@ -0,0 +1,62 @@
+use std::io::{Chain, Read};
+
+use rmp::decode::read_marker;
+use rmp::Marker;
+
+use super::Error;
+
+/// A value of one of two types, as decoded by [`read_either`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Either<A, B> {
+    /// A value decoded by the first function.
+    Left(A),
+    /// A value decoded by the second function.
+    Right(B),
+}
+
+/// The reader passed to the functions of [`read_either`]: the marker already read, followed by the
+/// rest of the original reader.
+pub type Peeked<'a, R> = Chain<&'a [u8], &'a mut R>;
+
+/// Attempts to read a value that is either of two types, such as a number or a string, choosing
+/// how to decode it from its marker.
+///
+/// The marker is read first and passed to `is_left`. The value is then decoded from its start,
+/// marker included, with `fa` if `is_left` returns `true`, or else with `fb`. Both get a reader
+/// that yields the marker again before the rest of the input, so any decoding function can be used.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidMarkerRead`] if the marker can't be read. Any error returned by `fa` or
+/// `fb` is propagated as is.
+///
+/// # Examples
+///
+/// ```
+/// use rmp::decode::{read_int, read_str_len};
+/// use rmp::Marker;
+/// use rmpv::decode::{read_either, Either};
+///
+/// let is_int = |marker| !matches!(marker, Marker::FixStr(..) | Marker::Str8 | Marker::Str16 | Marker::Str32);
+///
+/// let buf = [0xa3, 0x61, 0x62, 0x63];
+/// let val = read_either(&mut &buf[..], is_int, |rd| Ok(read_int::<i64, _>(rd)?), |rd| Ok(read_str_len(rd)?));
+///
+/// assert_eq!(Either::Right(3), val.unwrap());
+/// ```
+pub fn read_either<R, A, B, P, FA, FB>(rd: &mut R, is_left: P, fa: FA, fb: FB) -> Result<Either<A, B>, Error>
+    where R: Read,
+          P: FnOnce(Marker) -> bool,
+          FA: FnOnce(&mut Peeked<'_, R>) -> Result<A, Error>,
+          FB: FnOnce(&mut Peeked<'_, R>) -> Result<B, Error>
+{
+    let marker = read_marker(rd)?;
+    let buf = [marker.to_u8()];
+    let mut rd = (&buf[..]).chain(rd);
+
+    if is_left(marker) {
+        fa(&mut rd).map(Either::Left)
+    } else {
+        fb(&mut rd).map(Either::Right)
+    }
+}
@@ -0,0 +1,136 @@
+use std::io::{self, ErrorKind, Read};
+use std::sync::mpsc::Sender;
+
+use rmp::decode::{read_marker, RmpRead};
+use rmp::Marker;
+
+use super::{read_value, Error};
+use crate::Value;
+
+/// A step of decoding a value, as sent by [`decode_to_channel`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecodeEvent {
+    /// The start of an array of the given number of elements, which follow.
+    BeginArray(u32),
+    /// The end of the array started last.
+    EndArray,
+    /// The start of a map of the given number of entries, whose keys and values follow in turn.
+    BeginMap(u32),
+    /// The end of the map started last.
+    EndMap,
+    /// A value that isn't a container.
+    Scalar(Value),
+}
+
+/// A container whose elements are being decoded, with the number of values left in it.
+enum Frame {
+    Array(u64),
+    Map(u64),
+}
+
+fn send(tx: &Sender<DecodeEvent>, event: DecodeEvent) -> Result<(), Error> {
+    tx.send(event).map_err(|_| {
+        Error::InvalidDataRead(io::Error::new(ErrorKind::BrokenPipe, "event receiver disconnected"))
+    })
+}
+
+/// Attempts to read a single value from the given reader, sending it as a sequence of
+/// [`DecodeEvent`]s into `tx` as it is decoded.
+///
+/// Every container is sent as its begin event, then the events of its elements, then its end
+/// event, so the receiver, for example on another thread, may process the value while it is still
+/// being read, without holding it whole in memory. Scalars are decoded with [`read_value`].
+///
+/// # Errors
+///
+/// This function will return [`Error`] on any I/O error while reading, including an unexpected end
+/// of the input, and [`Error::DepthLimitExceeded`] past [`MAX_DEPTH`](super::MAX_DEPTH) levels of
+/// nesting. The events of the value read so far have been sent then. If the receiver is dropped,
+/// decoding stops with [`Error::InvalidDataRead`] of the `BrokenPipe` kind.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::mpsc;
+///
+/// use rmpv::decode::{decode_to_channel, DecodeEvent};
+/// use rmpv::Value;
+///
+/// let (tx, rx) = mpsc::channel();
+/// decode_to_channel(&mut &[0x91, 0x2a][..], tx).unwrap();
+///
+/// let events: Vec<_> = rx.iter().collect();
+/// let expected = vec![DecodeEvent::BeginArray(1), DecodeEvent::Scalar(Value::from(42)), DecodeEvent::EndArray];
+/// assert_eq!(expected, events);
+/// ```
+pub fn decode_to_channel<R: Read>(rd: &mut R, tx: Sender<DecodeEvent>) -> Result<(), Error> {
+    let mut stack = Vec::new();
+
+    loop {
+        let marker = read_marker(rd)?;
+        let frame = match marker {
+            Marker::FixArray(len) => begin_array(len.into(), &tx)?,
+            Marker::Array16 => begin_array(rd.read_data_u16()?.into(), &tx)?,
+            Marker::Array32 => begin_array(rd.read_data_u32()?, &tx)?,
+            Marker::FixMap(len) => begin_map(len.into(), &tx)?,
+            Marker::Map16 => begin_map(rd.read_data_u16()?.into(), &tx)?,
+            Marker::Map32 => begin_map(rd.read_data_u32()?, &tx)?,
+            marker => {
+                let buf = [marker.to_u8()];
+                send(&tx, DecodeEvent::Scalar(read_value(&mut (&buf[..]).chain(&mut *rd))?))?;
+                None
+            }
+        };
+
+        if let Some(frame) = frame {
+            if stack.len() >= super::MAX_DEPTH {
+                return Err(Error::DepthLimitExceeded);
+            }
+            stack.push(frame);
+            continue;
+        }
+
+        // Close as many containers as the value just sent completes.
+        loop {
+            match stack.last_mut() {
+                None => return Ok(()),
+                Some(Frame::Array(0)) => {
+                    stack.pop();
+                    send(&tx, DecodeEvent::EndArray)?;
+                }
+                Some(Frame::Map(0)) => {
+                    stack.pop();
+                    send(&tx, DecodeEvent::EndMap)?;
+                }
+                Some(Frame::Array(remaining)) | Some(Frame::Map(remaining)) => {
+                    *remaining -= 1;
+                    if *remaining > 0 {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Sends the start of an array, returning its frame, or sends the whole array if it's empty.
+fn begin_array(len: u32, tx: &Sender<DecodeEvent>) -> Result<Option<Frame>, Error> {
+    send(tx, DecodeEvent::BeginArray(len))?;
+    if len == 0 {
+        send(tx, DecodeEvent::EndArray)?;
+        return Ok(None);
+    }
+
+    Ok(Some(Frame::Array(len.into())))
+}
+
+/// Sends the start of a map, returning its frame, or sends the whole map if it's empty.
+fn begin_map(len: u32, tx: &Sender<DecodeEvent>) -> Result<Option<Frame>, Error> {
+    send(tx, DecodeEvent::BeginMap(len))?;
+    if len == 0 {
+        send(tx, DecodeEvent::EndMap)?;
+        return Ok(None);
+    }
+
+    Ok(Some(Frame::Map(u64::from(len) * 2)))
+}
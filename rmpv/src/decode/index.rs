@@ -0,0 +1,240 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::{Read, Seek};
+
+use rmp::decode::{read_marker, RmpRead};
+use rmp::Marker;
+
+use super::value::Counting;
+use super::{read_value, Error};
+use crate::path::{self, PathSegment, ValuePath};
+use crate::Value;
+
+/// The location of an encoded value in the reader it was decoded from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ValueSpan {
+    /// The position of the marker of the value.
+    pub offset: u64,
+    /// The number of bytes the value takes, including its marker.
+    pub len: u64,
+}
+
+/// The locations of the map fields of a value, built by [`read_value_index`].
+///
+/// Every value of a map entry, however deeply nested, is recorded under its path from the
+/// root, so that it can later be read directly by seeking to its [`ValueSpan::offset`], without
+/// decoding the rest of the message.
+///
+/// Paths are stored as a tree of their segments, so that the fields sharing an ancestor share its
+/// key too, rather than each holding a copy of its whole path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValueIndex {
+    /// The nodes of the tree of paths, the root first.
+    nodes: Vec<Node>,
+    /// The number of nodes holding a span.
+    len: usize,
+}
+
+/// A segment of the paths of a [`ValueIndex`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Node {
+    /// The location of the field at this path, unless it only leads to other fields.
+    span: Option<ValueSpan>,
+    /// The indices of the nodes one segment further.
+    children: HashMap<PathSegment, usize>,
+}
+
+impl Default for ValueIndex {
+    fn default() -> Self {
+        Self { nodes: vec![Node::default()], len: 0 }
+    }
+}
+
+impl ValueIndex {
+    /// Returns the location of the map field at the given path, if any.
+    #[must_use]
+    pub fn get(&self, path: &[PathSegment]) -> Option<ValueSpan> {
+        let mut node = 0;
+        for segment in path {
+            node = *self.nodes[node].children.get(segment)?;
+        }
+        self.nodes[node].span
+    }
+
+    /// Returns the number of fields recorded.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no field is recorded, that is the value holds no map entries.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns an iterator over the paths and locations of the fields, in no particular order.
+    ///
+    /// The paths are built while iterating, each being a copy of all its segments.
+    pub fn iter(&self) -> impl Iterator<Item = (ValuePath, ValueSpan)> + '_ {
+        let mut fields = Vec::with_capacity(self.len);
+        self.collect(0, &mut Vec::new(), &mut fields);
+        fields.into_iter()
+    }
+
+    fn collect(&self, node: usize, path: &mut Vec<PathSegment>, fields: &mut Vec<(ValuePath, ValueSpan)>) {
+        if let Some(span) = self.nodes[node].span {
+            fields.push((path.clone().into(), span));
+        }
+        for (segment, &child) in &self.nodes[node].children {
+            path.push(segment.clone());
+            self.collect(child, path, fields);
+            path.pop();
+        }
+    }
+
+    /// Returns the node one `segment` further than `node`, adding it if missing.
+    fn child(&mut self, node: usize, segment: &PathSegment) -> usize {
+        if let Some(&child) = self.nodes[node].children.get(segment) {
+            return child;
+        }
+
+        let child = self.nodes.len();
+        self.nodes.push(Node::default());
+        self.nodes[node].children.insert(segment.clone(), child);
+        child
+    }
+
+    fn insert(&mut self, node: usize, span: ValueSpan) {
+        if self.nodes[node].span.replace(span).is_none() {
+            self.len += 1;
+        }
+    }
+}
+
+/// Attempts to read a [`Value`] from the given seekable reader, along with the location of each of
+/// its map fields in the reader.
+///
+/// Paths are built as in [`Value::diff`]: map fields are keyed by their string key, or by the
+/// `Display` output of other keys, and array elements by their index. When a map repeats a key,
+/// the last entry is recorded. Offsets are positions in the reader, so the index stays valid for
+/// as long as the data does, for example in a file.
+///
+/// # Errors
+///
+/// Returns the same errors as [`read_value`], including [`Error::DepthLimitExceeded`] past
+/// [`MAX_DEPTH`](super::MAX_DEPTH) levels of nesting, and [`Error::InvalidDataRead`] if the
+/// position of the reader can't be determined.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use rmpv::decode::{read_value_index, ValueSpan};
+/// use rmpv::path::PathSegment;
+///
+/// // {"a": [1, {"b": 2}]}
+/// let buf = [0x81, 0xa1, 0x61, 0x92, 0x01, 0x81, 0xa1, 0x62, 0x02];
+///
+/// let (_, index) = read_value_index(&mut Cursor::new(&buf[..])).unwrap();
+///
+/// let path = [PathSegment::Key("a".into()), PathSegment::Index(1), PathSegment::Key("b".into())];
+/// assert_eq!(Some(ValueSpan { offset: 8, len: 1 }), index.get(&path));
+/// ```
+pub fn read_value_index<R>(rd: &mut R) -> Result<(Value, ValueIndex), Error>
+    where R: Read + Seek
+{
+    let pos = Cell::new(rd.stream_position().map_err(Error::InvalidDataRead)?);
+    let mut indexer = Indexer { pos: &pos, path: Vec::new(), index: ValueIndex::default() };
+
+    let val = indexer.read(&mut Counting { rd, pos: &pos }, super::MAX_DEPTH)?;
+    Ok((val, indexer.index))
+}
+
+/// The state of a [`read_value_index`] call.
+struct Indexer<'a> {
+    /// The position in the reader.
+    pos: &'a Cell<u64>,
+    /// The path of the value being decoded, with the node of each segment once it has one.
+    path: Vec<(PathSegment, Option<usize>)>,
+    index: ValueIndex,
+}
+
+impl Indexer<'_> {
+    fn read<R: Read>(&mut self, rd: &mut R, depth: usize) -> Result<Value, Error> {
+        let depth = super::decrement_depth(depth)?;
+        let marker = read_marker(rd)?;
+
+        match marker {
+            Marker::FixArray(len) => self.read_array(rd, len.into(), depth),
+            Marker::Array16 => {
+                let len = rd.read_data_u16()?;
+                self.read_array(rd, len.into(), depth)
+            }
+            Marker::Array32 => {
+                let len = rd.read_data_u32()?;
+                self.read_array(rd, len, depth)
+            }
+            Marker::FixMap(len) => self.read_map(rd, len.into(), depth),
+            Marker::Map16 => {
+                let len = rd.read_data_u16()?;
+                self.read_map(rd, len.into(), depth)
+            }
+            Marker::Map32 => {
+                let len = rd.read_data_u32()?;
+                self.read_map(rd, len, depth)
+            }
+            marker => {
+                let buf = [marker.to_u8()];
+                read_value(&mut (&buf[..]).chain(rd))
+            }
+        }
+    }
+
+    fn read_array<R: Read>(&mut self, rd: &mut R, len: u32, depth: usize) -> Result<Value, Error> {
+        let mut vec = super::safe_with_capacity(len as usize, super::CONTAINER_PREALLOC_MAX);
+
+        for idx in 0..len as usize {
+            self.path.push((PathSegment::Index(idx), None));
+            vec.push(self.read(rd, depth)?);
+            self.path.pop();
+        }
+
+        Ok(Value::Array(vec))
+    }
+
+    fn read_map<R: Read>(&mut self, rd: &mut R, len: u32, depth: usize) -> Result<Value, Error> {
+        let mut map = super::safe_with_capacity(len as usize, super::CONTAINER_PREALLOC_MAX);
+
+        for _ in 0..len {
+            let key = read_value(rd)?;
+            self.path.push((path::key_segment(&key), None));
+
+            let offset = self.pos.get();
+            let val = self.read(rd, depth)?;
+            let span = ValueSpan { offset, len: self.pos.get() - offset };
+            self.record(span);
+
+            self.path.pop();
+            map.push((key, val));
+        }
+
+        Ok(Value::Map(map))
+    }
+
+    /// Records the location of the value at the current path, adding the nodes of its segments
+    /// that don't have one yet.
+    fn record(&mut self, span: ValueSpan) {
+        let mut node = 0;
+        for (segment, id) in &mut self.path {
+            node = match *id {
+                Some(id) => id,
+                None => *id.insert(self.index.child(node, segment)),
+            };
+        }
+        self.index.insert(node, span);
+    }
+}
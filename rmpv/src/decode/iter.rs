@@ -0,0 +1,86 @@
+use std::io::{ErrorKind, Read};
+use std::iter::FusedIterator;
+
+use super::{read_value, Error};
+use crate::Value;
+
+/// An iterator over the values of a stream, created by [`values`].
+#[derive(Debug)]
+pub struct Values<R> {
+    rd: R,
+    done: bool,
+}
+
+impl<R> Values<R> {
+    /// Returns a reference to the underlying reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.rd
+    }
+
+    /// Unwraps this iterator, returning the underlying reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.rd
+    }
+}
+
+impl<R: Read> Iterator for Values<R> {
+    type Item = Result<Value, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        // Read the marker separately, so that the end of the stream between two values can be told
+        // apart from a truncated value.
+        let mut marker = [0; 1];
+        loop {
+            match self.rd.read(&mut marker) {
+                Ok(0) => {
+                    self.done = true;
+                    return None;
+                }
+                Ok(..) => break,
+                Err(ref err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(Error::InvalidMarkerRead(err)));
+                }
+            }
+        }
+
+        let res = read_value(&mut (&marker[..]).chain(&mut self.rd));
+        self.done = res.is_err();
+        Some(res)
+    }
+}
+
+impl<R: Read> FusedIterator for Values<R> {}
+
+/// Returns an iterator decoding consecutive values from the given reader with [`read_value`],
+/// until the end of the stream.
+///
+/// The iterator takes the reader by value. Pass `&mut rd` to keep using the reader afterwards, or
+/// recover it with [`Values::into_inner`].
+///
+/// It ends after the last complete value. If the stream ends in the middle of a value, or any
+/// other error occurs, that error is yielded once and the iteration stops.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode;
+/// use rmpv::Value;
+///
+/// let buf = [0x01, 0xa2, 0x6f, 0x6b, 0xc0];
+///
+/// let vals: Vec<Value> = decode::values(&buf[..]).collect::<Result<_, _>>().unwrap();
+///
+/// assert_eq!(vec![Value::from(1), Value::from("ok"), Value::Nil], vals);
+/// ```
+#[inline]
+pub fn values<R: Read>(rd: R) -> Values<R> {
+    Values { rd, done: false }
+}
@@ -0,0 +1,517 @@
+use std::collections::HashSet;
+use std::io::{self, Read};
+use std::str;
+use std::sync::Arc;
+
+use rmp::decode::{read_map_len, read_marker, read_str_len, RmpRead, ValueReadError};
+use rmp::Marker;
+
+use super::{read_value, DecodeConfig, Error};
+use crate::Value;
+
+/// Reads the length of a map key that must be a string.
+fn read_key_len<R: Read>(rd: &mut R) -> Result<usize, Error> {
+    match read_str_len(rd) {
+        Ok(len) => super::checked_len(len),
+        Err(ValueReadError::TypeMismatch(marker)) => Err(Error::NonStringKey(marker)),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[cold]
+fn invalid_utf8(err: str::Utf8Error) -> Error {
+    Error::InvalidDataRead(io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Reads a map key that must be a valid UTF-8 string.
+fn read_string_key<R: Read>(rd: &mut R) -> Result<String, Error> {
+    let len = read_key_len(rd)?;
+    let buf = super::read_byte_vec(rd, len)?;
+
+    String::from_utf8(buf).map_err(|err| invalid_utf8(err.utf8_error()))
+}
+
+/// A cache of map keys, so that identical keys decoded with [`read_string_map_interned`] share a
+/// single allocation.
+///
+/// Reuse one interner across many maps with the same keys, such as the records of a table, to
+/// allocate each distinct key only once.
+#[derive(Debug, Default)]
+pub struct KeyInterner {
+    keys: HashSet<Arc<str>>,
+    // Scratch space for the key being decoded, reused to avoid allocating for known keys.
+    buf: Vec<u8>,
+}
+
+impl KeyInterner {
+    /// Constructs an empty interner.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of distinct keys interned so far.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Returns `true` if no key has been interned yet.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    fn read_key<R: Read>(&mut self, rd: &mut R) -> Result<Arc<str>, Error> {
+        let len = read_key_len(rd)?;
+        self.buf.clear();
+        super::read_bytes_into(rd, len, &mut self.buf)?;
+
+        let key = str::from_utf8(&self.buf).map_err(invalid_utf8)?;
+        if let Some(key) = self.keys.get(key) {
+            return Ok(key.clone());
+        }
+
+        let key: Arc<str> = Arc::from(key);
+        self.keys.insert(key.clone());
+        Ok(key)
+    }
+}
+
+/// Attempts to read a map with string keys and arbitrary values from the given reader.
+///
+/// The entries are returned in the order they are encoded; duplicate keys are kept as is.
+///
+/// # Errors
+///
+/// Returns [`Error::NonStringKey`] with the offending marker if a key is not a string, and
+/// [`Error::InvalidDataRead`] if a key is not valid UTF-8. Values are decoded with [`read_value`],
+/// so any of its errors may be returned as well.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::read_string_map;
+/// use rmpv::Value;
+///
+/// // {"id": 42}
+/// let buf = [0x81, 0xa2, 0x69, 0x64, 0x2a];
+///
+/// let map = read_string_map(&mut &buf[..]).unwrap();
+///
+/// assert_eq!(vec![("id".to_string(), Value::from(42))], map);
+/// ```
+pub fn read_string_map<R: Read>(rd: &mut R) -> Result<Vec<(String, Value)>, Error> {
+    let len = read_map_len(rd)?;
+    let mut vec = super::safe_with_capacity(len as usize, super::CONTAINER_PREALLOC_MAX);
+    for _ in 0..len {
+        let key = read_string_key(rd)?;
+        let val = read_value(rd)?;
+        vec.push((key, val));
+    }
+
+    Ok(vec)
+}
+
+/// Attempts to read a map with string keys and arbitrary values from the given reader, sharing
+/// keys seen before through `keys`.
+///
+/// This behaves like [`read_string_map`], except that each key is looked up in the interner first,
+/// so decoding many maps with the same keys allocates every distinct key only once.
+///
+/// # Errors
+///
+/// Returns the same errors as [`read_string_map`].
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use rmpv::decode::{read_string_map_interned, KeyInterner};
+///
+/// // {"id": 1}, {"id": 2}
+/// let buf = [0x81, 0xa2, 0x69, 0x64, 0x01, 0x81, 0xa2, 0x69, 0x64, 0x02];
+/// let mut rd = &buf[..];
+/// let mut keys = KeyInterner::new();
+///
+/// let first = read_string_map_interned(&mut rd, &mut keys).unwrap();
+/// let second = read_string_map_interned(&mut rd, &mut keys).unwrap();
+///
+/// assert!(Arc::ptr_eq(&first[0].0, &second[0].0));
+/// assert_eq!(1, keys.len());
+/// ```
+pub fn read_string_map_interned<R>(rd: &mut R, keys: &mut KeyInterner) -> Result<Vec<(Arc<str>, Value)>, Error>
+    where R: Read
+{
+    let len = read_map_len(rd)?;
+    let mut vec = super::safe_with_capacity(len as usize, super::CONTAINER_PREALLOC_MAX);
+    for _ in 0..len {
+        let key = keys.read_key(rd)?;
+        let val = read_value(rd)?;
+        vec.push((key, val));
+    }
+
+    Ok(vec)
+}
+
+/// A map whose entries are decoded one at a time, created by [`read_map_lazy`].
+#[derive(Debug)]
+pub struct LazyMap<'r, R> {
+    rd: &'r mut R,
+    remaining: u32,
+}
+
+impl<'r, R: Read> LazyMap<'r, R> {
+    /// Returns the number of entries not read yet.
+    #[inline]
+    pub fn remaining(&self) -> u32 {
+        self.remaining
+    }
+
+    /// Decodes the next entry with [`read_value`], or returns `None` if all entries have been read.
+    pub fn next_entry(&mut self) -> Option<Result<(Value, Value), Error>> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+
+        let entry = read_value(self.rd).and_then(|key| Ok((key, read_value(self.rd)?)));
+        if entry.is_err() {
+            // The reader is left in the middle of an entry, so nothing more can be read.
+            self.remaining = 0;
+        }
+        Some(entry)
+    }
+
+    /// Discards the entries not read yet with [`skip_value`](super::skip_value), leaving the
+    /// reader right after the map.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`skip_value`](super::skip_value).
+    pub fn skip_remaining(mut self) -> Result<(), Error> {
+        while self.remaining > 0 {
+            self.remaining -= 1;
+            super::skip_value(self.rd)?;
+            super::skip_value(self.rd)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the header of a map, returning a [`LazyMap`] to decode its entries on demand.
+///
+/// This avoids buffering all entries of a huge map when only some of them are needed. The entries
+/// the caller doesn't read remain in the reader, unless discarded with
+/// [`LazyMap::skip_remaining`].
+///
+/// # Errors
+///
+/// This function will return [`Error`] on any I/O error while reading the header, or if the next
+/// value is not a map.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::read_map_lazy;
+/// use rmpv::Value;
+///
+/// // {"a": 1, "b": 2}, then nil.
+/// let buf = [0x82, 0xa1, 0x61, 0x01, 0xa1, 0x62, 0x02, 0xc0];
+/// let mut rd = &buf[..];
+///
+/// let mut map = read_map_lazy(&mut rd).unwrap();
+/// assert_eq!((Value::from("a"), Value::from(1)), map.next_entry().unwrap().unwrap());
+/// map.skip_remaining().unwrap();
+///
+/// assert_eq!([0xc0], rd);
+/// ```
+pub fn read_map_lazy<R: Read>(rd: &mut R) -> Result<LazyMap<'_, R>, Error> {
+    let remaining = read_map_len(rd)?;
+
+    Ok(LazyMap { rd, remaining })
+}
+
+/// The result of [`read_map_recovering`]: the entries that could be decoded and the errors of
+/// those that couldn't.
+#[derive(Debug)]
+pub struct RecoveredMap {
+    /// The entries decoded, in the order they are encoded.
+    pub entries: Vec<(Value, Value)>,
+    /// The errors encountered, each with the index of the entry it occurred in.
+    pub errors: Vec<(usize, Error)>,
+}
+
+/// Decodes a single value from exactly the bytes in `buf`.
+fn read_buffered(buf: &[u8], config: &DecodeConfig) -> Result<Value, Error> {
+    super::read_value_with_config(&mut &buf[..], config)
+}
+
+/// Attempts to read a map, decoding as many of its entries as possible, for best-effort
+/// ingestion of damaged input.
+///
+/// Each key and value is first delimited by its markers and lengths, as with
+/// [`copy_value`](super::copy_value), and only then decoded with `config`. If either fails to
+/// decode, the error is recorded and the entry dropped, while decoding resumes at the next entry.
+///
+/// The bytes of all the entries count against [`DecodeConfig::max_total_alloc`] together, as they
+/// are copied. Exceeding it is recorded as [`Error::LengthLimitExceeded`] and ends the map, since
+/// the rest of the entry can't be delimited without reading it.
+///
+/// # Limitations
+///
+/// Recovery relies on the lengths in the input being intact. It handles values that are well
+/// delimited but fail to decode, for example because of [`DecodeConfig`] limits or strictness
+/// options, but a corrupted marker or length makes the following bytes be read at a wrong
+/// boundary, so the next entries may decode as garbage, or fail too. Once the input ends, the
+/// error is recorded and no further entry is read, leaving the reader wherever it stopped.
+///
+/// # Errors
+///
+/// Returns [`Error`] only if the map header can't be read; errors in entries are collected in
+/// [`RecoveredMap::errors`] instead.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::{read_map_recovering, DecodeConfig};
+/// use rmpv::Value;
+///
+/// // {"a": 1, "b": 5 as int8, "c": 3}, with non-canonical integers rejected.
+/// let buf = [0x83, 0xa1, 0x61, 0x01, 0xa1, 0x62, 0xd0, 0x05, 0xa1, 0x63, 0x03];
+/// let config = DecodeConfig { canonical_ints: true, ..DecodeConfig::default() };
+///
+/// let map = read_map_recovering(&mut &buf[..], &config).unwrap();
+///
+/// assert_eq!(vec![(Value::from("a"), Value::from(1)), (Value::from("c"), Value::from(3))], map.entries);
+/// assert_eq!(1, map.errors[0].0);
+/// ```
+pub fn read_map_recovering<R: Read>(rd: &mut R, config: &DecodeConfig) -> Result<RecoveredMap, Error> {
+    let len = read_map_len(rd)? as usize;
+
+    let mut map = RecoveredMap {
+        entries: Vec::with_capacity(len.min(super::CONTAINER_PREALLOC_MAX)),
+        errors: Vec::new(),
+    };
+    let mut key_buf = Vec::new();
+    let mut val_buf = Vec::new();
+    let mut alloc_remaining = config.max_total_alloc;
+
+    for idx in 0..len {
+        key_buf.clear();
+        val_buf.clear();
+        let res = super::raw::copy_value_limited(rd, &mut key_buf, alloc_remaining).and_then(|()| {
+            let remaining = alloc_remaining.map(|remaining| remaining - key_buf.len());
+            super::raw::copy_value_limited(rd, &mut val_buf, remaining)
+        });
+        if let Err(err) = res {
+            map.errors.push((idx, err));
+            break;
+        }
+        if let Some(ref mut remaining) = alloc_remaining {
+            *remaining -= key_buf.len() + val_buf.len();
+        }
+
+        match read_buffered(&key_buf, config).and_then(|key| Ok((key, read_buffered(&val_buf, config)?))) {
+            Ok(entry) => map.entries.push(entry),
+            Err(err) => map.errors.push((idx, err)),
+        }
+    }
+
+    Ok(map)
+}
+
+/// Reads a map key, returning whether it's the string `key`, without decoding it.
+fn is_key<R: Read>(rd: &mut R, key: &str, buf: &mut Vec<u8>) -> Result<bool, Error> {
+    Ok(super::read_str_key(rd, buf)? == Some(key.as_bytes()))
+}
+
+/// Attempts to read the value of the first entry of a map whose key is the string `key`, or `None`
+/// if there is no such entry.
+///
+/// Keys are compared without being decoded, and the entries before the match are skipped with
+/// [`skip_value`](super::skip_value), so only the value found is decoded. Reading stops there: the
+/// entries after it remain in the reader. Keys that aren't strings never match.
+///
+/// # Errors
+///
+/// This function will return [`Error`] on any I/O error while reading, including an unexpected end
+/// of the input, or if the next value is not a map. The value found is decoded with
+/// [`read_value`], so any of its errors may be returned as well.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::read_map_field;
+/// use rmpv::Value;
+///
+/// // {"a": [1, 2], "b": "ok", "c": nil}
+/// let buf = [0x83, 0xa1, 0x61, 0x92, 0x01, 0x02, 0xa1, 0x62, 0xa2, 0x6f, 0x6b, 0xa1, 0x63, 0xc0];
+/// let mut rd = &buf[..];
+///
+/// assert_eq!(Some(Value::from("ok")), read_map_field(&mut rd, "b").unwrap());
+/// assert_eq!([0xa1, 0x63, 0xc0], rd);
+/// ```
+pub fn read_map_field<R: Read>(rd: &mut R, key: &str) -> Result<Option<Value>, Error> {
+    let len = read_map_len(rd)?;
+    let mut buf = Vec::new();
+
+    for _ in 0..len {
+        if is_key(rd, key, &mut buf)? {
+            return read_value(rd).map(Some);
+        }
+        super::skip_value(rd)?;
+    }
+
+    Ok(None)
+}
+
+/// A setter of a field of a struct `T`, called by [`read_struct_with`] to decode the value of the
+/// field's entry into the struct.
+pub type FieldSetter<T, R> = fn(&mut T, &mut R) -> Result<(), Error>;
+
+/// The fields of a struct `T` and their setters, to decode it from a map with
+/// [`read_struct_with`] without serde.
+///
+/// Each setter reads exactly one value from the reader, typically with one of the `rmp` readers,
+/// and stores it into its field.
+pub struct FieldTable<T, R> {
+    fields: Vec<(&'static str, FieldSetter<T, R>)>,
+}
+
+impl<T, R> FieldTable<T, R> {
+    /// Creates a table without fields.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self { fields: Vec::new() }
+    }
+
+    /// Registers the setter of the entries whose key is `name`, replacing the previous one if any.
+    #[must_use]
+    pub fn field(mut self, name: &'static str, setter: FieldSetter<T, R>) -> Self {
+        match self.fields.iter_mut().find(|(field, ..)| *field == name) {
+            Some(entry) => entry.1 = setter,
+            None => self.fields.push((name, setter)),
+        }
+        self
+    }
+
+    fn setter(&self, key: &[u8]) -> Option<FieldSetter<T, R>> {
+        self.fields.iter().find(|(name, ..)| name.as_bytes() == key).map(|&(_, setter)| setter)
+    }
+}
+
+impl<T, R> Default for FieldTable<T, R> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Attempts to read a map into `target`, calling the setter registered in `table` for the key of
+/// each entry.
+///
+/// Entries whose key has no setter, including the ones whose key isn't a string, are skipped with
+/// [`skip_value`](super::skip_value) without being decoded. A key repeated in the map has its
+/// setter called once per entry, in order.
+///
+/// # Errors
+///
+/// This function will return [`Error`] on any I/O error while reading, including an unexpected end
+/// of the input, or if the next value is not a map. Errors of the setters are returned as is, and
+/// reading stops at them, with the fields set so far kept in `target`.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::{read_integer, read_struct_with, FieldTable};
+///
+/// #[derive(Debug, Default, PartialEq)]
+/// struct Point {
+///     x: i64,
+///     y: i64,
+/// }
+///
+/// let table = FieldTable::new()
+///     .field("x", |p: &mut Point, rd: &mut &[u8]| {
+///         p.x = read_integer(rd)?.as_i64().unwrap_or_default();
+///         Ok(())
+///     })
+///     .field("y", |p, rd| {
+///         p.y = read_integer(rd)?.as_i64().unwrap_or_default();
+///         Ok(())
+///     });
+///
+/// // {"x": 1, "y": -2}
+/// let buf = [0x82, 0xa1, 0x78, 0x01, 0xa1, 0x79, 0xfe];
+/// let mut point = Point::default();
+///
+/// read_struct_with(&mut &buf[..], &table, &mut point).unwrap();
+/// assert_eq!(Point { x: 1, y: -2 }, point);
+/// ```
+pub fn read_struct_with<T, R: Read>(rd: &mut R, table: &FieldTable<T, R>, target: &mut T) -> Result<(), Error> {
+    let len = read_map_len(rd)?;
+    let mut buf = Vec::new();
+
+    for _ in 0..len {
+        let setter = super::read_str_key(rd, &mut buf)?.and_then(|key| table.setter(key));
+        match setter {
+            Some(setter) => setter(target, rd)?,
+            None => super::skip_value(rd)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Attempts to read either a map or an array as a list of entries, for fields that evolved from
+/// one to the other.
+///
+/// A map yields its entries as is. An array yields an entry per element, whose key is the
+/// element's 0-based index as a [`Value::Integer`](crate::Value::Integer), so `["a", "b"]` reads
+/// the same as `{0: "a", 1: "b"}`.
+///
+/// # Errors
+///
+/// This function will return [`Error`] on any I/O error while reading, including an unexpected end
+/// of the input, or if the next value is neither a map nor an array, after consuming only its
+/// marker. The keys and values are decoded with [`read_value`], so any of its errors may be
+/// returned as well.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::read_entries;
+/// use rmpv::Value;
+///
+/// let expected = vec![(Value::from(0), Value::from("a")), (Value::from(1), Value::from("b"))];
+///
+/// // ["a", "b"]
+/// assert_eq!(expected, read_entries(&mut &[0x92, 0xa1, 0x61, 0xa1, 0x62][..]).unwrap());
+/// // {0: "a", 1: "b"}
+/// assert_eq!(expected, read_entries(&mut &[0x82, 0x00, 0xa1, 0x61, 0x01, 0xa1, 0x62][..]).unwrap());
+/// ```
+pub fn read_entries<R: Read>(rd: &mut R) -> Result<Vec<(Value, Value)>, Error> {
+    let (len, is_map) = match read_marker(rd)? {
+        Marker::FixMap(len) => (len.into(), true),
+        Marker::Map16 => (rd.read_data_u16()?.into(), true),
+        Marker::Map32 => (rd.read_data_u32()?, true),
+        Marker::FixArray(len) => (len.into(), false),
+        Marker::Array16 => (rd.read_data_u16()?.into(), false),
+        Marker::Array32 => (rd.read_data_u32()?, false),
+        marker => return Err(ValueReadError::TypeMismatch(marker).into()),
+    };
+
+    let mut entries = super::safe_with_capacity(len as usize, super::CONTAINER_PREALLOC_MAX);
+    for idx in 0..len {
+        let key = if is_map { read_value(rd)? } else { Value::from(idx) };
+        entries.push((key, read_value(rd)?));
+    }
+
+    Ok(entries)
+}
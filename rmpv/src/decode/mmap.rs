@@ -0,0 +1,76 @@
+#![allow(unsafe_code)]
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use super::{read_value_ref, Error};
+use crate::ValueRef;
+
+/// A memory-mapped file holding a MessagePack value, created by [`from_mmap`].
+///
+/// The decoded value borrows its strings and binaries from the mapping, which this struct keeps
+/// alive, so they are never copied to the heap.
+#[derive(Debug)]
+pub struct MappedValue {
+    map: Mmap,
+}
+
+impl MappedValue {
+    /// Decodes the first value of the file, borrowing from the mapping.
+    ///
+    /// Only the containers of the value are allocated, which makes decoding it again cheap.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`read_value_ref`], including when the file is empty.
+    #[inline]
+    pub fn value(&self) -> Result<ValueRef<'_>, Error> {
+        read_value_ref(&mut &self.map[..])
+    }
+
+    /// Returns the mapped bytes of the whole file.
+    #[inline]
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+/// Memory-maps the file at the given path for decoding the value it holds with
+/// [`MappedValue::value`], instead of reading the whole file into a heap buffer.
+///
+/// # Safety
+///
+/// The file must not be modified or truncated, by this process or any other, for as long as the
+/// returned [`MappedValue`] or any value decoded from it is alive. Otherwise the bytes borrowed by
+/// the decoded value could change under it, which is undefined behavior.
+///
+/// # Errors
+///
+/// Returns any I/O error raised while opening or mapping the file.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::from_mmap;
+/// use rmpv::ValueRef;
+///
+/// let path = std::env::temp_dir().join("rmpv-from-mmap-doctest.msgpack");
+/// std::fs::write(&path, [0x92, 0xa2, 0x6f, 0x6b, 0x2a]).unwrap();
+///
+/// // SAFETY: the file is not modified while mapped.
+/// let mapped = unsafe { from_mmap(&path) }.unwrap();
+/// assert_eq!(ValueRef::Array(vec![ValueRef::from("ok"), ValueRef::from(42)]), mapped.value().unwrap());
+/// # drop(mapped);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub unsafe fn from_mmap(path: &Path) -> io::Result<MappedValue> {
+    let file = File::open(path)?;
+    // SAFETY: upheld by the caller.
+    let map = unsafe { Mmap::map(&file)? };
+
+    Ok(MappedValue { map })
+}
@@ -1,18 +1,207 @@
 use std::error;
 use std::fmt::{self, Display, Formatter};
-use std::io::{self, ErrorKind};
+use std::io::{self, ErrorKind, Read};
 
-use rmp::decode::{MarkerReadError, ValueReadError};
+use rmp::decode::{MarkerReadError, NumValueReadError, ValueReadError};
+use rmp::Marker;
 
+use crate::Value;
+
+mod annotated;
+#[cfg(feature = "bumpalo")]
+mod arena;
+#[cfg(feature = "tokio")]
+mod async_read;
+mod columnar;
+mod either;
+mod events;
+mod index;
+mod iter;
+mod map;
+#[cfg(feature = "memmap")]
+mod mmap;
+mod raw;
+mod scalar;
+mod sequence;
+mod tuple;
 pub mod value;
 pub mod value_ref;
 
-pub use self::value::{read_value, read_value_with_max_depth};
+pub use self::annotated::{read_value_annotated, Annotation};
+#[cfg(feature = "bumpalo")]
+pub use self::arena::{read_value_in, ArenaValue};
+#[cfg(feature = "tokio")]
+pub use self::async_read::read_value_async;
+pub use self::columnar::{read_records_columnar, Columns};
+pub use self::either::{read_either, Either, Peeked};
+pub use self::events::{decode_to_channel, DecodeEvent};
+pub use self::index::{read_value_index, ValueIndex, ValueSpan};
+pub use self::iter::{values, Values};
+pub use self::map::{
+    read_entries, read_map_field, read_map_lazy, read_map_recovering, read_string_map, read_string_map_interned,
+    read_struct_with, FieldSetter, FieldTable, KeyInterner, LazyMap, RecoveredMap,
+};
+#[cfg(feature = "memmap")]
+pub use self::mmap::{from_mmap, MappedValue};
+pub use self::raw::{copy_value, skip_value};
+pub use self::scalar::{read_integer, read_integer_strict, read_scalar, read_scalar_array, read_str_max_chars, Scalar};
+pub use self::sequence::read_until_nil;
+pub use self::tuple::{read_pair, read_tagged, read_triple};
+pub use self::value::{
+    expect_value, read_value, read_value_seekable, read_value_with_binary_refs, read_value_with_config,
+    read_value_with_max_depth, read_value_with_strings, read_value_with_warnings,
+};
 pub use self::value_ref::{read_value_ref, read_value_ref_with_max_depth};
 
 /// The maximum recursion depth before [`Error::DepthLimitExceeded`] is returned.
 pub const MAX_DEPTH: usize = 1024;
 
+// See https://github.com/3Hren/msgpack-rust/issues/151
+const PREALLOC_MAX: usize = 64 * 1024; // 64 KiB
+
+/// The largest number of array or map elements reserved upfront. It's kept small, since every
+/// level of nesting reserves its own.
+const CONTAINER_PREALLOC_MAX: usize = 16;
+
+/// Limits applied while decoding a [`Value`](crate::Value).
+///
+/// Construct it by overriding the fields of interest and taking the rest from
+/// [`DecodeConfig::default()`].
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::DecodeConfig;
+///
+/// let config = DecodeConfig {
+///     max_total_alloc: Some(1024 * 1024),
+///     ..DecodeConfig::default()
+/// };
+/// assert_eq!(rmpv::decode::MAX_DEPTH, config.max_depth);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeConfig {
+    /// The maximum recursion depth before [`Error::DepthLimitExceeded`] is returned.
+    pub max_depth: usize,
+    /// The maximum number of bytes that may be allocated for strings, binaries, extension data and
+    /// container elements over the whole value, or `None` for no limit.
+    ///
+    /// Exceeding it results in [`Error::LengthLimitExceeded`].
+    pub max_total_alloc: Option<usize>,
+    /// The maximum number of allocations for strings, binaries, extension data and non-empty
+    /// containers over the whole value, or `None` for no limit.
+    ///
+    /// This bounds the pressure on the allocator from many tiny values, which
+    /// [`DecodeConfig::max_total_alloc`] lets through as long as they are small in total. Exceeding
+    /// it results in [`Error::LengthLimitExceeded`].
+    pub max_allocations: Option<usize>,
+    /// The maximum product of the declared lengths of a container and of all the containers
+    /// enclosing it, or `None` for no limit.
+    ///
+    /// This is the number of elements the nesting claims to hold at that depth, e.g. a map of a
+    /// million entries, each a map of a million entries, claims a trillion. Such headers take only
+    /// a few bytes each, so a small truncated message could announce an enormous tree: this rejects
+    /// it with [`Error::LengthLimitExceeded`] as soon as the offending header is read, well before
+    /// the input runs out. The length of a map is its number of entries.
+    pub max_declared_product: Option<u64>,
+    /// Whether 64-bit floats holding an integral value, like `3.0`, are decoded as
+    /// [`Value::Integer`](crate::Value::Integer) instead of [`Value::F64`](crate::Value::F64).
+    ///
+    /// This eases interoperability with producers that encode every number as a float, such as
+    /// JSON bridges. Floats that are not integral or don't fit in 64 bits stay floats.
+    pub coerce_integral_floats: bool,
+    /// Map keys whose values are captured as [`Value::Raw`](crate::Value::Raw), holding their
+    /// exact encoded bytes, instead of being decoded.
+    ///
+    /// This applies to string keys of maps at any depth. The captured bytes count against
+    /// [`DecodeConfig::max_total_alloc`].
+    pub raw_keys: Vec<String>,
+    /// Whether strings are stored without checking that they are valid UTF-8, which saves a pass
+    /// over strings that are only forwarded.
+    ///
+    /// Decoding then succeeds on invalid UTF-8, like it does anyway, yet the check is deferred to
+    /// the accessors of [`Utf8String`](crate::Utf8String): [`as_str`](crate::Utf8String::as_str)
+    /// returns `None` and [`as_err`](crate::Utf8String::as_err) the error only when called.
+    pub defer_utf8_validation: bool,
+    /// Whether subnormal floats are rejected with [`Error::SubnormalFloat`], for protocols that
+    /// flush them to zero.
+    pub reject_subnormal_floats: bool,
+    /// Whether integers not encoded in their shortest form are rejected with
+    /// [`Error::NonCanonicalInt`], for validating canonical encodings.
+    ///
+    /// The shortest form is the one the spec recommends and
+    /// [`write_uint`](rmp::encode::write_uint) and [`write_sint`](rmp::encode::write_sint) write:
+    ///
+    /// - a non-negative integer takes the first of positive fixint, uint8, uint16, uint32 and
+    ///   uint64 able to hold it. In particular, it is never encoded with a signed marker, even
+    ///   though int8 to int64 can represent it, e.g. 5 must be `0x05` rather than `0xd0 0x05`;
+    /// - a negative integer takes the first of negative fixint, int8, int16, int32 and int64 able
+    ///   to hold it.
+    pub canonical_ints: bool,
+    /// Whether non-empty arrays whose elements are all 2-element arrays are decoded as a
+    /// [`Value::Map`](crate::Value::Map) of those `[key, value]` pairs, for producers that encode
+    /// maps this way.
+    ///
+    /// Arrays are converted innermost first, so a pair whose key and value are both 2-element
+    /// arrays itself becomes a map, and then can't be a pair of the enclosing array anymore. The
+    /// entries of the map count towards [`DecodeConfig::max_total_alloc`] and
+    /// [`DecodeConfig::max_allocations`], on top of the arrays they are converted from.
+    pub arrays_of_pairs_as_maps: bool,
+    /// If set, arrays of ext chunks of this type, as written with
+    /// [`EncodeConfig::binary_chunks`](crate::encode::EncodeConfig::binary_chunks), are reassembled
+    /// into the [`Value::Binary`](crate::Value::Binary) they were split from.
+    ///
+    /// Only non-empty arrays whose elements all are chunks of this type, indexed from 0 in order
+    /// and with the final flag on the last one only, are reassembled. Other arrays are kept as is.
+    pub binary_chunks: Option<i8>,
+}
+
+impl Default for DecodeConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: MAX_DEPTH,
+            max_total_alloc: None,
+            max_allocations: None,
+            max_declared_product: None,
+            coerce_integral_floats: false,
+            raw_keys: Vec::new(),
+            defer_utf8_validation: false,
+            reject_subnormal_floats: false,
+            canonical_ints: false,
+            arrays_of_pairs_as_maps: false,
+            binary_chunks: None,
+        }
+    }
+}
+
+/// A coercion applied by [`read_value_with_warnings`] to make the input decode.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DecodeWarning {
+    /// The offset of the coerced value's marker, relative to where decoding started.
+    pub offset: u64,
+    /// The coercion applied.
+    pub kind: DecodeWarningKind,
+}
+
+/// The kind of coercion reported by a [`DecodeWarning`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum DecodeWarningKind {
+    /// A string isn't valid UTF-8 and has been kept as raw bytes.
+    InvalidUtf8,
+    /// A float has been converted to an integer by [`DecodeConfig::coerce_integral_floats`].
+    /// Contains the float.
+    IntegralFloat(f64),
+}
+
+/// The kind of container reported by [`Error::TruncatedContainer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContainerKind {
+    /// An array.
+    Array,
+    /// A map.
+    Map,
+}
+
 /// This type represents all possible errors that can occur when deserializing a value.
 #[derive(Debug)]
 pub enum Error {
@@ -22,6 +211,46 @@ pub enum Error {
     InvalidDataRead(io::Error),
     /// The depth limit [`MAX_DEPTH`] was exceeded.
     DepthLimitExceeded,
+    /// The container has a different number of elements than expected. Contains the actual length.
+    InvalidLength(u32),
+    /// One of the limits [`DecodeConfig::max_total_alloc`], [`DecodeConfig::max_allocations`] and
+    /// [`DecodeConfig::max_declared_product`] was exceeded, or a string didn't fit the limits of
+    /// [`read_str_max_chars`].
+    LengthLimitExceeded,
+    /// A map key is not a string. Contains the key's marker.
+    NonStringKey(Marker),
+    /// An array, map or extension was found where a scalar was expected. Contains its marker.
+    NotScalar(Marker),
+    /// The value differs from the expected one. Contains the value actually read.
+    UnexpectedValue(Value),
+    /// A subnormal float was found while [`DecodeConfig::reject_subnormal_floats`] is set. Contains
+    /// the value.
+    SubnormalFloat(f64),
+    /// An integer isn't encoded in its shortest form while [`DecodeConfig::canonical_ints`] is
+    /// set. Contains the marker it is encoded with.
+    NonCanonicalInt(Marker),
+    /// The input ended before all the elements of a container were decoded.
+    ///
+    /// It refers to the innermost container, whose element at `index` is missing or incomplete.
+    /// For maps, `index` and `expected` count entries rather than keys and values.
+    TruncatedContainer {
+        /// Whether the container is an array or a map.
+        kind: ContainerKind,
+        /// The index of the element that couldn't be decoded.
+        index: usize,
+        /// The number of elements the container declares.
+        expected: usize,
+    },
+    /// A reference read by [`string_table::read_value`](crate::string_table::read_value) is
+    /// malformed or refers to a string not read yet. Contains the reference's payload.
+    InvalidStringRef(Vec<u8>),
+    /// An element of an array read by [`read_scalar_array`] isn't of the expected category.
+    TypeMismatch {
+        /// The index of the offending element.
+        index: usize,
+        /// The marker of the offending element.
+        marker: Marker,
+    },
 }
 
 fn decrement_depth(depth: usize) -> Result<usize, Error> {
@@ -32,6 +261,66 @@ fn decrement_depth(depth: usize) -> Result<usize, Error> {
     }
 }
 
+/// Converts a declared length of an allocating value to `usize`, rejecting lengths that can't be
+/// allocated on this platform before any allocation is attempted.
+fn checked_len(len: u32) -> Result<usize, Error> {
+    checked_len_within(len, isize::MAX as usize)
+}
+
+fn checked_len_within(len: u32, max: usize) -> Result<usize, Error> {
+    match usize::try_from(len) {
+        Ok(len) if len <= max => Ok(len),
+        _ => Err(Error::LengthLimitExceeded),
+    }
+}
+
+/// Creates a vector for `declared` elements, reserving room for at most `cap` of them upfront.
+///
+/// A declared length comes from the input and can't be trusted to allocate for, see
+/// <https://github.com/3Hren/msgpack-rust/issues/151>. The vector grows as elements actually arrive.
+fn safe_with_capacity<T>(declared: usize, cap: usize) -> Vec<T> {
+    Vec::with_capacity(std::cmp::min(declared, cap))
+}
+
+/// Reads exactly `len` bytes, growing the buffer as data arrives rather than trusting `len`.
+fn read_byte_vec<R: Read>(rd: &mut R, len: usize) -> Result<Vec<u8>, Error> {
+    let mut buf = safe_with_capacity(len, PREALLOC_MAX);
+    read_bytes_into(rd, len, &mut buf)?;
+    Ok(buf)
+}
+
+/// Appends exactly `len` bytes to `buf`, growing it as data arrives rather than trusting `len`.
+fn read_bytes_into<R: Read>(rd: &mut R, len: usize, buf: &mut Vec<u8>) -> Result<(), Error> {
+    let bytes_read = rd.take(len as u64).read_to_end(buf).map_err(Error::InvalidDataRead)?;
+    if bytes_read != len {
+        return Err(Error::InvalidDataRead(io::Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("Expected {} bytes, read {} bytes", len, bytes_read),
+        )));
+    }
+
+    Ok(())
+}
+
+/// Reads a map key into `buf` and returns its bytes if it's a string, without decoding it, or
+/// skips it and returns `None` if it isn't.
+fn read_str_key<'a, R: Read>(rd: &mut R, buf: &'a mut Vec<u8>) -> Result<Option<&'a [u8]>, Error> {
+    let len = match rmp::decode::read_str_len(rd) {
+        Ok(len) => len as usize,
+        Err(ValueReadError::TypeMismatch(marker)) => {
+            // The marker has been consumed already, so the rest of the key is skipped after it.
+            let head = [marker.to_u8()];
+            skip_value(&mut (&head[..]).chain(rd))?;
+            return Ok(None);
+        }
+        Err(err) => return Err(err.into()),
+    };
+
+    buf.clear();
+    read_bytes_into(rd, len, buf)?;
+    Ok(Some(buf))
+}
+
 impl Error {
     #[cold]
     pub fn kind(&self) -> ErrorKind {
@@ -39,6 +328,16 @@ impl Error {
             Error::InvalidMarkerRead(ref err) => err.kind(),
             Error::InvalidDataRead(ref err) => err.kind(),
             Error::DepthLimitExceeded => ErrorKind::Unsupported,
+            Error::InvalidLength(..) => ErrorKind::InvalidData,
+            Error::LengthLimitExceeded => ErrorKind::Unsupported,
+            Error::NonStringKey(..) |
+            Error::NotScalar(..) |
+            Error::UnexpectedValue(..) |
+            Error::SubnormalFloat(..) |
+            Error::NonCanonicalInt(..) => ErrorKind::InvalidData,
+            Error::TruncatedContainer { .. } => ErrorKind::UnexpectedEof,
+            Error::InvalidStringRef(..) |
+            Error::TypeMismatch { .. } => ErrorKind::InvalidData,
         }
     }
 }
@@ -49,7 +348,17 @@ impl error::Error for Error {
         match *self {
             Error::InvalidMarkerRead(ref err) => Some(err),
             Error::InvalidDataRead(ref err) => Some(err),
-            Error::DepthLimitExceeded => None,
+            Error::DepthLimitExceeded |
+            Error::InvalidLength(..) |
+            Error::LengthLimitExceeded |
+            Error::NonStringKey(..) |
+            Error::NotScalar(..) |
+            Error::UnexpectedValue(..) |
+            Error::SubnormalFloat(..) |
+            Error::NonCanonicalInt(..) |
+            Error::TruncatedContainer { .. } |
+            Error::InvalidStringRef(..) |
+            Error::TypeMismatch { .. } => None,
         }
     }
 }
@@ -67,6 +376,40 @@ impl Display for Error {
             Error::DepthLimitExceeded => {
                 write!(fmt, "depth limit exceeded")
             }
+            Error::InvalidLength(len) => {
+                write!(fmt, "unexpected container length: {}", len)
+            }
+            Error::LengthLimitExceeded => {
+                write!(fmt, "allocation limit exceeded")
+            }
+            Error::NonStringKey(marker) => {
+                write!(fmt, "expected a string key, found {:?}", marker)
+            }
+            Error::NotScalar(marker) => {
+                write!(fmt, "expected a scalar, found {:?}; use a container reader instead", marker)
+            }
+            Error::UnexpectedValue(ref val) => {
+                write!(fmt, "unexpected value: {}", val)
+            }
+            Error::SubnormalFloat(val) => {
+                write!(fmt, "subnormal float {:e} is not allowed", val)
+            }
+            Error::NonCanonicalInt(marker) => {
+                write!(fmt, "integer encoded as {} is not in its shortest form", marker.name())
+            }
+            Error::TruncatedContainer { kind, index, expected } => {
+                let (container, element) = match kind {
+                    ContainerKind::Array => ("array", "element"),
+                    ContainerKind::Map => ("map", "entry"),
+                };
+                write!(fmt, "{} truncated: input ended at {} {} of {}", container, element, index, expected)
+            }
+            Error::InvalidStringRef(ref buf) => {
+                write!(fmt, "invalid string table reference {:?}", buf)
+            }
+            Error::TypeMismatch { index, marker } => {
+                write!(fmt, "unexpected {} at array index {}", marker.name(), index)
+            }
         }
     }
 }
@@ -84,20 +427,81 @@ impl From<ValueReadError> for Error {
         match err {
             ValueReadError::InvalidMarkerRead(err) => Error::InvalidMarkerRead(err),
             ValueReadError::InvalidDataRead(err) => Error::InvalidDataRead(err),
-            ValueReadError::TypeMismatch(..) => {
+            ValueReadError::TypeMismatch(..) |
+            ValueReadError::UnexpectedExtSize { .. } |
+            ValueReadError::UnexpectedExtType { .. } => {
                 Error::InvalidMarkerRead(io::Error::new(ErrorKind::Other, "type mismatch"))
             }
         }
     }
 }
 
+impl From<NumValueReadError> for Error {
+    #[cold]
+    fn from(err: NumValueReadError) -> Error {
+        match err {
+            NumValueReadError::InvalidMarkerRead(err) => Error::InvalidMarkerRead(err),
+            NumValueReadError::InvalidDataRead(err) => Error::InvalidDataRead(err),
+            NumValueReadError::TypeMismatch(..) => {
+                Error::InvalidMarkerRead(io::Error::new(ErrorKind::Other, "type mismatch"))
+            }
+            NumValueReadError::OutOfRange => {
+                Error::InvalidDataRead(io::Error::new(ErrorKind::InvalidData, "integer out of range"))
+            }
+        }
+    }
+}
+
 impl From<Error> for io::Error {
     #[cold]
     fn from(val: Error) -> Self {
         match val {
             Error::InvalidMarkerRead(err) |
             Error::InvalidDataRead(err) => err,
-            Error::DepthLimitExceeded => io::Error::new(val.kind(), val),
+            Error::DepthLimitExceeded |
+            Error::InvalidLength(..) |
+            Error::LengthLimitExceeded |
+            Error::NonStringKey(..) |
+            Error::NotScalar(..) |
+            Error::UnexpectedValue(..) |
+            Error::SubnormalFloat(..) |
+            Error::NonCanonicalInt(..) |
+            Error::TruncatedContainer { .. } |
+            Error::InvalidStringRef(..) |
+            Error::TypeMismatch { .. } => io::Error::new(val.kind(), val),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{checked_len, checked_len_within, safe_with_capacity, Error};
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn checked_len_accepts_u32_max() {
+        assert_eq!(u32::MAX as usize, checked_len(u32::MAX).unwrap());
+    }
+
+    #[test]
+    fn checked_len_rejects_oversized() {
+        // Mocks a platform where `isize::MAX` is below the declared length.
+        assert_eq!(0x7fff, checked_len_within(0x7fff, 0x7fff).unwrap());
+        match checked_len_within(0x8000, 0x7fff) {
+            Err(Error::LengthLimitExceeded) => (),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn safe_with_capacity_caps_reservation() {
+        let vec = safe_with_capacity::<u64>(u32::MAX as usize, 16);
+        // The allocator may round up, but not anywhere near the declared length.
+        assert!(vec.capacity() >= 16 && vec.capacity() < 1024);
+
+        let vec = safe_with_capacity::<u64>(3, 16);
+        assert!(vec.capacity() >= 3 && vec.capacity() < 16);
+
+        assert_eq!(0, safe_with_capacity::<u64>(0, 16).capacity());
+    }
+}
@@ -0,0 +1,187 @@
+use std::io::{self, Read};
+
+use rmp::decode::read_marker;
+use rmp::Marker;
+
+use super::Error;
+
+/// What follows a marker and its length bytes.
+pub(super) enum Body {
+    /// A fixed number of bytes.
+    Skip(u64),
+    /// As many bytes as the length says.
+    Bytes,
+    /// A type byte, then as many bytes as the length says.
+    Ext,
+    /// The length times the given number of values.
+    Values(u64),
+    /// A fixed number of values.
+    Count(u64),
+}
+
+/// Returns the number of big-endian length bytes following the marker, and what follows them.
+pub(super) fn layout(marker: Marker) -> (usize, Body) {
+    match marker {
+        Marker::Null | Marker::True | Marker::False | Marker::Reserved |
+        Marker::FixPos(..) | Marker::FixNeg(..) => (0, Body::Skip(0)),
+        Marker::U8 | Marker::I8 => (0, Body::Skip(1)),
+        Marker::U16 | Marker::I16 => (0, Body::Skip(2)),
+        Marker::U32 | Marker::I32 | Marker::F32 => (0, Body::Skip(4)),
+        Marker::U64 | Marker::I64 | Marker::F64 => (0, Body::Skip(8)),
+        Marker::FixStr(len) => (0, Body::Skip(u64::from(len))),
+        Marker::Str8 | Marker::Bin8 => (1, Body::Bytes),
+        Marker::Str16 | Marker::Bin16 => (2, Body::Bytes),
+        Marker::Str32 | Marker::Bin32 => (4, Body::Bytes),
+        Marker::FixArray(len) => (0, Body::Count(u64::from(len))),
+        Marker::Array16 => (2, Body::Values(1)),
+        Marker::Array32 => (4, Body::Values(1)),
+        Marker::FixMap(len) => (0, Body::Count(2 * u64::from(len))),
+        Marker::Map16 => (2, Body::Values(2)),
+        Marker::Map32 => (4, Body::Values(2)),
+        Marker::FixExt1 => (0, Body::Skip(1 + 1)),
+        Marker::FixExt2 => (0, Body::Skip(1 + 2)),
+        Marker::FixExt4 => (0, Body::Skip(1 + 4)),
+        Marker::FixExt8 => (0, Body::Skip(1 + 8)),
+        Marker::FixExt16 => (0, Body::Skip(1 + 16)),
+        Marker::Ext8 => (1, Body::Ext),
+        Marker::Ext16 => (2, Body::Ext),
+        Marker::Ext32 => (4, Body::Ext),
+    }
+}
+
+/// Reads the bytes of a single encoded value, however deeply nested, and appends them to `buf`
+/// as is.
+///
+/// This doesn't decode the value, so it doesn't validate UTF-8 or the extension types.
+///
+/// # Errors
+///
+/// This function will return [`Error`] on any I/O error while reading, including an unexpected end
+/// of the input.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::copy_value;
+///
+/// // [1, "a"], then nil.
+/// let buf = [0x92, 0x01, 0xa1, 0x61, 0xc0];
+/// let mut rd = &buf[..];
+/// let mut out = Vec::new();
+///
+/// copy_value(&mut rd, &mut out).unwrap();
+///
+/// assert_eq!(&buf[..4], &out[..]);
+/// assert_eq!([0xc0], rd);
+/// ```
+pub fn copy_value<R: Read>(rd: &mut R, buf: &mut Vec<u8>) -> Result<(), Error> {
+    copy_value_limited(rd, buf, None)
+}
+
+/// Like [`copy_value`], but fails with [`Error::LengthLimitExceeded`] before appending more than
+/// `limit` bytes, if set, so that a huge value can't exhaust memory.
+///
+/// The limit is checked against the declared lengths before their data is read.
+pub(super) fn copy_value_limited<R: Read>(rd: &mut R, buf: &mut Vec<u8>, limit: Option<usize>) -> Result<(), Error> {
+    let end = match limit {
+        Some(limit) => Some(buf.len().checked_add(limit).ok_or(Error::LengthLimitExceeded)?),
+        None => None,
+    };
+    let check = |buf: &Vec<u8>, len: usize| match end {
+        Some(end) if buf.len().checked_add(len).map_or(true, |len| len > end) => Err(Error::LengthLimitExceeded),
+        _ => Ok(()),
+    };
+
+    // Values that are known to follow but haven't been copied yet.
+    let mut pending = 1u64;
+
+    while pending > 0 {
+        pending -= 1;
+
+        let marker = read_marker(rd)?;
+        check(buf, 1)?;
+        buf.push(marker.to_u8());
+
+        let (header, body) = layout(marker);
+        check(buf, header)?;
+        super::read_bytes_into(rd, header, buf)?;
+        let len = buf[buf.len() - header..].iter().fold(0u64, |acc, &b| (acc << 8) | u64::from(b));
+
+        let skip = match body {
+            Body::Skip(n) => n,
+            Body::Bytes => len,
+            Body::Ext => 1 + len,
+            Body::Values(per_item) => {
+                pending = pending.saturating_add(len * per_item);
+                continue;
+            }
+            Body::Count(n) => {
+                pending = pending.saturating_add(n);
+                continue;
+            }
+        };
+        let skip = usize::try_from(skip).map_err(|_| Error::LengthLimitExceeded)?;
+        check(buf, skip)?;
+        super::read_bytes_into(rd, skip, buf)?;
+    }
+
+    Ok(())
+}
+
+/// Reads a single encoded value, however deeply nested, and discards it.
+///
+/// Like [`copy_value`], this doesn't decode the value, and it allocates nothing.
+///
+/// # Errors
+///
+/// This function will return [`Error`] on any I/O error while reading, including an unexpected end
+/// of the input.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::skip_value;
+///
+/// // {"a": [1, 2]}, then nil.
+/// let buf = [0x81, 0xa1, 0x61, 0x92, 0x01, 0x02, 0xc0];
+/// let mut rd = &buf[..];
+///
+/// skip_value(&mut rd).unwrap();
+///
+/// assert_eq!([0xc0], rd);
+/// ```
+pub fn skip_value<R: Read>(rd: &mut R) -> Result<(), Error> {
+    let mut pending = 1u64;
+
+    while pending > 0 {
+        pending -= 1;
+
+        let (header, body) = layout(read_marker(rd)?);
+        let mut buf = [0; 4];
+        rd.read_exact(&mut buf[..header]).map_err(Error::InvalidDataRead)?;
+        let len = buf[..header].iter().fold(0u64, |acc, &b| (acc << 8) | u64::from(b));
+
+        let skip = match body {
+            Body::Skip(n) => n,
+            Body::Bytes => len,
+            Body::Ext => 1 + len,
+            Body::Values(per_item) => {
+                pending = pending.saturating_add(len * per_item);
+                continue;
+            }
+            Body::Count(n) => {
+                pending = pending.saturating_add(n);
+                continue;
+            }
+        };
+        let skipped = io::copy(&mut rd.take(skip), &mut io::sink()).map_err(Error::InvalidDataRead)?;
+        if skipped != skip {
+            return Err(Error::InvalidDataRead(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("Expected {} bytes, read {} bytes", skip, skipped),
+            )));
+        }
+    }
+
+    Ok(())
+}
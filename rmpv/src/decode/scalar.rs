@@ -0,0 +1,282 @@
+use std::io::{self, ErrorKind, Read};
+
+use rmp::decode::{read_marker, NumValueReadError, RmpRead};
+use rmp::{Marker, MarkerCategory};
+
+use super::Error;
+use crate::{Integer, Utf8String, Value};
+
+/// A MessagePack value that is neither a container nor an extension.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Scalar {
+    /// Nil represents nil.
+    Nil,
+    /// Boolean represents true or false.
+    Boolean(bool),
+    /// Integer represents an integer.
+    Integer(Integer),
+    /// A 32-bit floating point number.
+    F32(f32),
+    /// A 64-bit floating point number.
+    F64(f64),
+    /// String extending Raw type represents a UTF-8 string.
+    String(Utf8String),
+    /// Binary extending Raw type represents a byte array.
+    Binary(Vec<u8>),
+}
+
+impl From<Scalar> for Value {
+    fn from(val: Scalar) -> Self {
+        match val {
+            Scalar::Nil => Value::Nil,
+            Scalar::Boolean(val) => Value::Boolean(val),
+            Scalar::Integer(val) => Value::Integer(val),
+            Scalar::F32(val) => Value::F32(val),
+            Scalar::F64(val) => Value::F64(val),
+            Scalar::String(val) => Value::String(val),
+            Scalar::Binary(val) => Value::Binary(val),
+        }
+    }
+}
+
+fn read_bin<R: Read>(rd: &mut R, len: u32) -> Result<Vec<u8>, Error> {
+    super::read_byte_vec(rd, super::checked_len(len)?)
+}
+
+fn read_string<R: Read>(rd: &mut R, len: u32) -> Result<Utf8String, Error> {
+    read_bin(rd, len).map(Utf8String::from_bytes)
+}
+
+/// Attempts to read whatever scalar value comes next from the given reader.
+///
+/// This is a middle ground between the typed readers of `rmp`, which require knowing the type in
+/// advance, and [`read_value`](super::read_value), which decodes whole trees.
+///
+/// # Errors
+///
+/// Returns [`Error::NotScalar`] with the marker if the next value is an array, a map or an
+/// extension, after consuming only the marker. Use the dedicated readers, like
+/// `rmp::decode::read_array_len`, for those. Any I/O error is returned as in
+/// [`read_value`](super::read_value).
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::{read_scalar, Scalar};
+///
+/// let buf = [0xa2, 0x6f, 0x6b, 0xcb, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+/// let mut rd = &buf[..];
+///
+/// assert_eq!(Scalar::String("ok".into()), read_scalar(&mut rd).unwrap());
+/// assert_eq!(Scalar::F64(1.5), read_scalar(&mut rd).unwrap());
+/// ```
+pub fn read_scalar<R: Read>(rd: &mut R) -> Result<Scalar, Error> {
+    let val = match read_marker(rd)? {
+        Marker::Null => Scalar::Nil,
+        Marker::True => Scalar::Boolean(true),
+        Marker::False => Scalar::Boolean(false),
+        Marker::FixPos(val) => Scalar::Integer(val.into()),
+        Marker::FixNeg(val) => Scalar::Integer(val.into()),
+        Marker::U8 => Scalar::Integer(rd.read_data_u8()?.into()),
+        Marker::U16 => Scalar::Integer(rd.read_data_u16()?.into()),
+        Marker::U32 => Scalar::Integer(rd.read_data_u32()?.into()),
+        Marker::U64 => Scalar::Integer(rd.read_data_u64()?.into()),
+        Marker::I8 => Scalar::Integer(rd.read_data_i8()?.into()),
+        Marker::I16 => Scalar::Integer(rd.read_data_i16()?.into()),
+        Marker::I32 => Scalar::Integer(rd.read_data_i32()?.into()),
+        Marker::I64 => Scalar::Integer(rd.read_data_i64()?.into()),
+        Marker::F32 => Scalar::F32(rd.read_data_f32()?),
+        Marker::F64 => Scalar::F64(rd.read_data_f64()?),
+        Marker::FixStr(len) => Scalar::String(read_string(rd, len.into())?),
+        Marker::Str8 => {
+            let len = rd.read_data_u8()?;
+            Scalar::String(read_string(rd, len.into())?)
+        }
+        Marker::Str16 => {
+            let len = rd.read_data_u16()?;
+            Scalar::String(read_string(rd, len.into())?)
+        }
+        Marker::Str32 => {
+            let len = rd.read_data_u32()?;
+            Scalar::String(read_string(rd, len)?)
+        }
+        Marker::Bin8 => {
+            let len = rd.read_data_u8()?;
+            Scalar::Binary(read_bin(rd, len.into())?)
+        }
+        Marker::Bin16 => {
+            let len = rd.read_data_u16()?;
+            Scalar::Binary(read_bin(rd, len.into())?)
+        }
+        Marker::Bin32 => {
+            let len = rd.read_data_u32()?;
+            Scalar::Binary(read_bin(rd, len)?)
+        }
+        Marker::Reserved => Scalar::Nil,
+        marker => return Err(Error::NotScalar(marker)),
+    };
+
+    Ok(val)
+}
+
+/// Reads an integer along with the marker it is encoded with.
+fn read_int_with_marker<R: Read>(rd: &mut R) -> Result<(Marker, Integer), Error> {
+    let marker = read_marker(rd)?;
+    let val = match marker {
+        Marker::FixPos(val) => val.into(),
+        Marker::FixNeg(val) => val.into(),
+        Marker::U8 => rd.read_data_u8()?.into(),
+        Marker::U16 => rd.read_data_u16()?.into(),
+        Marker::U32 => rd.read_data_u32()?.into(),
+        Marker::U64 => rd.read_data_u64()?.into(),
+        Marker::I8 => rd.read_data_i8()?.into(),
+        Marker::I16 => rd.read_data_i16()?.into(),
+        Marker::I32 => rd.read_data_i32()?.into(),
+        Marker::I64 => rd.read_data_i64()?.into(),
+        marker => return Err(NumValueReadError::TypeMismatch(marker).into()),
+    };
+
+    Ok((marker, val))
+}
+
+/// Attempts to read an integer of any encoding from the given reader, keeping the full 64-bit
+/// range of both signed and unsigned values.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidMarkerRead`] if the next value isn't an integer, after consuming only its
+/// marker. Any I/O error is returned as in [`read_value`](super::read_value).
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::read_integer;
+///
+/// let buf = [0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+///
+/// assert_eq!(Some(u64::MAX), read_integer(&mut &buf[..]).unwrap().as_u64());
+/// ```
+pub fn read_integer<R: Read>(rd: &mut R) -> Result<Integer, Error> {
+    read_int_with_marker(rd).map(|(_, val)| val)
+}
+
+/// Attempts to read an integer from the given reader, requiring it to be encoded in its shortest
+/// form.
+///
+/// The shortest form is the one [`write_uint`](rmp::encode::write_uint) and
+/// [`write_sint`](rmp::encode::write_sint) write: a fixint if the value fits in one, or else the
+/// narrowest of the 8, 16, 32 and 64-bit formats that holds it, unsigned for non-negative values
+/// and signed for negative ones. So `5` must be a positive fixint rather than, for instance, a
+/// `U8`, and `200` a `U8` rather than an `I16`. This is the check
+/// [`DecodeConfig::canonical_ints`](super::DecodeConfig::canonical_ints) applies to whole values.
+///
+/// # Errors
+///
+/// In addition to the errors of [`read_integer`], returns [`Error::NonCanonicalInt`] with the
+/// marker read if the integer isn't in its shortest form. The integer is consumed then.
+///
+/// # Examples
+///
+/// ```
+/// use rmp::Marker;
+/// use rmpv::decode::{read_integer_strict, Error};
+///
+/// assert_eq!(Some(5), read_integer_strict(&mut &[0x05][..]).unwrap().as_u64());
+///
+/// match read_integer_strict(&mut &[0xcc, 0x05][..]) {
+///     Err(Error::NonCanonicalInt(Marker::U8)) => (),
+///     other => panic!("unexpected result: {:?}", other),
+/// }
+/// ```
+pub fn read_integer_strict<R: Read>(rd: &mut R) -> Result<Integer, Error> {
+    let (marker, val) = read_int_with_marker(rd)?;
+    if marker != super::value::canonical_int_marker(val) {
+        return Err(Error::NonCanonicalInt(marker));
+    }
+
+    Ok(val)
+}
+
+/// Attempts to read a string into the given buffer, requiring it to have at most `max_chars`
+/// characters.
+///
+/// The limit counts Unicode scalar values, as `str::chars` does, rather than bytes, for fields
+/// whose constraint is a number of characters. The byte length is capped by the size of `buf`
+/// instead, so a string of many multibyte characters may fit the former and not the latter.
+///
+/// # Errors
+///
+/// Returns [`Error::LengthLimitExceeded`] if the string has more than `max_chars` characters, or
+/// more bytes than `buf` holds; its data isn't consumed in the latter case. Returns
+/// [`Error::InvalidDataRead`] with the `InvalidData` kind if it isn't valid UTF-8, and the errors
+/// of [`read_value`](super::read_value) on I/O errors or if the next value isn't a string.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::{read_str_max_chars, Error};
+///
+/// // "héé", which is 3 characters in 5 bytes.
+/// let buf = [0xa5, 0x68, 0xc3, 0xa9, 0xc3, 0xa9];
+/// let mut out = [0; 16];
+///
+/// assert_eq!("héé", read_str_max_chars(&mut &buf[..], &mut out, 3).unwrap());
+/// assert!(matches!(read_str_max_chars(&mut &buf[..], &mut out, 2), Err(Error::LengthLimitExceeded)));
+/// ```
+pub fn read_str_max_chars<'r, R: Read>(rd: &mut R, buf: &'r mut [u8], max_chars: usize) -> Result<&'r str, Error> {
+    let len = rmp::decode::read_str_len(rd)? as usize;
+    let buf = buf.get_mut(..len).ok_or(Error::LengthLimitExceeded)?;
+    rd.read_exact(buf).map_err(Error::InvalidDataRead)?;
+
+    let s = std::str::from_utf8(buf)
+        .map_err(|err| Error::InvalidDataRead(io::Error::new(ErrorKind::InvalidData, err)))?;
+    // A string can't have more characters than bytes.
+    if s.len() > max_chars && s.chars().count() > max_chars {
+        return Err(Error::LengthLimitExceeded);
+    }
+
+    Ok(s)
+}
+
+/// Attempts to read an array whose elements all are of the `expected` category, appending them
+/// to `out`.
+///
+/// This guards the assumptions of columnar data, such as an array of floats, while decoding it:
+/// every element's marker is checked before the element is decoded. The categories merge the
+/// formats of different widths, so `F32` and `F64` elements are both [`MarkerCategory::Float`].
+///
+/// # Errors
+///
+/// Returns [`Error::TypeMismatch`] with the index and marker of the first element of another
+/// category, after consuming only its marker. The elements before it have been appended to `out`
+/// then. Returns the errors of [`read_value`](super::read_value) if the next value isn't an array
+/// or on I/O errors.
+///
+/// # Examples
+///
+/// ```
+/// use rmp::MarkerCategory;
+/// use rmpv::decode::read_scalar_array;
+/// use rmpv::Value;
+///
+/// let buf = [0x92, 0xca, 0x3f, 0x00, 0x00, 0x00, 0xcb, 0x3f, 0xf8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+/// let mut out = Vec::new();
+///
+/// read_scalar_array(&mut &buf[..], MarkerCategory::Float, &mut out).unwrap();
+/// assert_eq!(vec![Value::F32(0.5), Value::F64(1.5)], out);
+/// ```
+pub fn read_scalar_array<R: Read>(rd: &mut R, expected: MarkerCategory, out: &mut Vec<Value>) -> Result<(), Error> {
+    let len = rmp::decode::read_array_len(rd)? as usize;
+    out.reserve(std::cmp::min(len, super::CONTAINER_PREALLOC_MAX));
+
+    for index in 0..len {
+        let marker = read_marker(rd)?;
+        if marker.category() != expected {
+            return Err(Error::TypeMismatch { index, marker });
+        }
+        let buf = [marker.to_u8()];
+        out.push(super::read_value(&mut (&buf[..]).chain(&mut *rd))?);
+    }
+
+    Ok(())
+}
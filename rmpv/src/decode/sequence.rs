@@ -0,0 +1,50 @@
+use std::io::Read;
+
+use rmp::decode::read_marker;
+use rmp::Marker;
+
+use super::{Error, Peeked};
+
+/// Attempts to read a sequence of values terminated by a nil, rather than prefixed with their
+/// number, decoding each element with the given function.
+///
+/// Before each element its marker is read: a nil ends the sequence and is consumed. Otherwise
+/// the element is decoded from its start with `f`, which gets a reader that yields the marker
+/// again before the rest of the input, as with [`read_either`](super::read_either). Elements
+/// therefore can't be nil themselves.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidMarkerRead`] if a marker can't be read, including when the input ends
+/// before the nil. Any error returned by `f` is propagated as is.
+///
+/// # Examples
+///
+/// ```
+/// use rmp::decode::read_int;
+/// use rmpv::decode::read_until_nil;
+///
+/// let buf = [0x01, 0xcd, 0x01, 0x00, 0xc0, 0x2a];
+/// let mut rd = &buf[..];
+///
+/// let vec = read_until_nil(&mut rd, |rd| Ok(read_int::<u32, _>(rd)?));
+///
+/// assert_eq!(vec![1, 256], vec.unwrap());
+/// assert_eq!([0x2a], rd);
+/// ```
+pub fn read_until_nil<R, T, F>(rd: &mut R, mut f: F) -> Result<Vec<T>, Error>
+    where R: Read,
+          F: FnMut(&mut Peeked<'_, R>) -> Result<T, Error>
+{
+    let mut vec = Vec::new();
+
+    loop {
+        let marker = read_marker(rd)?;
+        if marker == Marker::Null {
+            return Ok(vec);
+        }
+
+        let buf = [marker.to_u8()];
+        vec.push(f(&mut (&buf[..]).chain(&mut *rd))?);
+    }
+}
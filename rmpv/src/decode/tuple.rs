@@ -0,0 +1,113 @@
+use std::io::Read;
+
+use rmp::decode::{read_array_len, read_int};
+
+use super::Error;
+
+fn read_tuple_len<R: Read>(rd: &mut R, expected: u32) -> Result<(), Error> {
+    let len = read_array_len(rd)?;
+    if len == expected {
+        Ok(())
+    } else {
+        Err(Error::InvalidLength(len))
+    }
+}
+
+/// Attempts to read a 2-element array from the given reader, decoding its elements with the given
+/// functions.
+///
+/// This is the usual way tuples are encoded in MessagePack.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidLength`] with the actual length if the array does not hold exactly two
+/// elements. Any error returned by `fa` or `fb` is propagated as is.
+///
+/// # Examples
+///
+/// ```
+/// use rmp::decode::{read_pfix, read_str_len};
+/// use rmpv::decode::read_pair;
+///
+/// let buf = [0x92, 0x2a, 0xa3, 0x61, 0x62, 0x63];
+///
+/// let pair = read_pair(&mut &buf[..], |rd| Ok(read_pfix(rd)?), |rd| Ok(read_str_len(rd)?));
+///
+/// assert_eq!((42, 3), pair.unwrap());
+/// ```
+pub fn read_pair<R, A, B, FA, FB>(rd: &mut R, fa: FA, fb: FB) -> Result<(A, B), Error>
+    where R: Read,
+          FA: FnOnce(&mut R) -> Result<A, Error>,
+          FB: FnOnce(&mut R) -> Result<B, Error>
+{
+    read_tuple_len(rd, 2)?;
+
+    let a = fa(rd)?;
+    let b = fb(rd)?;
+
+    Ok((a, b))
+}
+
+/// Attempts to read a 3-element array from the given reader, decoding its elements with the given
+/// functions.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidLength`] with the actual length if the array does not hold exactly
+/// three elements. Any error returned by `fa`, `fb` or `fc` is propagated as is.
+pub fn read_triple<R, A, B, C, FA, FB, FC>(rd: &mut R, fa: FA, fb: FB, fc: FC) -> Result<(A, B, C), Error>
+    where R: Read,
+          FA: FnOnce(&mut R) -> Result<A, Error>,
+          FB: FnOnce(&mut R) -> Result<B, Error>,
+          FC: FnOnce(&mut R) -> Result<C, Error>
+{
+    read_tuple_len(rd, 3)?;
+
+    let a = fa(rd)?;
+    let b = fb(rd)?;
+    let c = fc(rd)?;
+
+    Ok((a, b, c))
+}
+
+/// Attempts to read an adjacently tagged value, encoded as a `[tag, payload]` array, from the given
+/// reader.
+///
+/// The tag must be a non-negative integer. It is passed to `f` together with the reader, which is
+/// positioned at the start of the payload, so that `f` can decode the payload according to the tag.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidLength`] with the actual length if the array does not hold exactly two
+/// elements. Any error returned by `f` is propagated as is.
+///
+/// # Examples
+///
+/// ```
+/// use rmp::decode::{read_bool, read_pfix};
+/// use rmpv::decode::read_tagged;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Event {
+///     Key(u8),
+///     Focus(bool),
+/// }
+///
+/// let buf = [0x92, 0x01, 0xc3];
+///
+/// let event = read_tagged(&mut &buf[..], |tag, rd| match tag {
+///     0 => Ok(Event::Key(read_pfix(rd)?)),
+///     _ => Ok(Event::Focus(read_bool(rd)?)),
+/// });
+///
+/// assert_eq!(Event::Focus(true), event.unwrap());
+/// ```
+pub fn read_tagged<R, T, F>(rd: &mut R, f: F) -> Result<T, Error>
+    where R: Read,
+          F: FnOnce(u64, &mut R) -> Result<T, Error>
+{
+    read_tuple_len(rd, 2)?;
+
+    let tag = read_int(rd)?;
+    f(tag, rd)
+}
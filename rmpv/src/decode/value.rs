@@ -1,208 +1,543 @@
-use std::cmp::min;
-use std::io::{self, Read};
+use std::collections::HashSet;
+use std::cell::Cell;
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+use std::mem;
+use std::str;
+use std::sync::Arc;
 
 use rmp::decode::{RmpRead, read_marker};
 use rmp::Marker;
 
-use super::Error;
-use crate::{Utf8String, Value};
+use super::{ContainerKind, DecodeConfig, DecodeWarning, DecodeWarningKind, Error};
+use crate::{Integer, Utf8String, Value};
 
-// See https://github.com/3Hren/msgpack-rust/issues/151
-const PREALLOC_MAX: usize = 64 * 1024; // 64 KiB
+/// State shared by all levels of a single `read_value` call.
+struct Context<'a> {
+    config: &'a DecodeConfig,
+    /// Bytes left in the [`DecodeConfig::max_total_alloc`] budget, if there is one.
+    alloc_remaining: Option<usize>,
+    /// Allocations left in the [`DecodeConfig::max_allocations`] budget, if there is one.
+    allocs_remaining: Option<usize>,
+    /// Number of bytes left in the input when decoding started, if known, and the number of bytes
+    /// read since.
+    input_len: Option<(u64, &'a Cell<u64>)>,
+    /// Strings whose allocation decoded string values equal to them share.
+    strings: Option<&'a HashSet<Arc<str>>>,
+    // Scratch space for string values looked up in `strings`.
+    buf: Vec<u8>,
+    /// The number of bytes read so far and the warnings recorded, if requested.
+    warnings: Option<(&'a Cell<u64>, &'a mut Vec<DecodeWarning>)>,
+    /// The offset of the marker of the value being decoded, if tracked.
+    offset: u64,
+    /// How binaries are left in the input, if requested.
+    binary_refs: Option<BinaryRefs<'a>>,
+}
 
-fn read_array_data<R: Read>(rd: &mut R, mut len: usize, depth: usize) -> Result<Vec<Value>, Error> {
-    let depth = super::decrement_depth(depth)?;
+impl<'a> Context<'a> {
+    fn new(config: &'a DecodeConfig) -> Self {
+        Self {
+            config,
+            alloc_remaining: config.max_total_alloc,
+            allocs_remaining: config.max_allocations,
+            input_len: None,
+            strings: None,
+            buf: Vec::new(),
+            warnings: None,
+            offset: 0,
+            binary_refs: None,
+        }
+    }
 
-    // Note: Do not preallocate a Vec of size `len`.
-    // See https://github.com/3Hren/msgpack-rust/issues/151
-    let mut vec = Vec::new();
+    /// Notes the start of the value about to be decoded, for its warnings.
+    #[inline]
+    fn mark(&mut self) {
+        if let Some((pos, _)) = self.warnings {
+            self.offset = pos.get();
+        }
+    }
 
-    while len > 0 {
-        vec.push(read_value_inner(rd, depth)?);
-        len -= 1;
+    /// Records a coercion applied to the value being decoded, if warnings are requested.
+    fn warn(&mut self, kind: DecodeWarningKind) {
+        if let Some((_, ref mut warnings)) = self.warnings {
+            warnings.push(DecodeWarning { offset: self.offset, kind });
+        }
     }
 
-    Ok(vec)
-}
+    /// Rejects containers declaring more elements than the rest of the input could possibly hold,
+    /// given that each element takes at least `min_size` bytes.
+    fn check_input_len(&self, len: usize, min_size: u64) -> Result<(), Error> {
+        match self.input_len {
+            Some((input_len, pos)) if (len as u64).saturating_mul(min_size) > input_len.saturating_sub(pos.get()) => {
+                Err(Error::LengthLimitExceeded)
+            }
+            _ => Ok(()),
+        }
+    }
 
-fn read_map_data<R: Read>(rd: &mut R, mut len: usize, depth: usize) -> Result<Vec<(Value, Value)>, Error> {
-    let depth = super::decrement_depth(depth)?;
+    /// Applies [`DecodeConfig::reject_subnormal_floats`] to a decoded float.
+    fn check_float(&self, is_subnormal: bool, val: f64) -> Result<(), Error> {
+        if is_subnormal && self.config.reject_subnormal_floats {
+            Err(Error::SubnormalFloat(val))
+        } else {
+            Ok(())
+        }
+    }
 
-    // Note: Do not preallocate a Vec of size `len`.
-    // See https://github.com/3Hren/msgpack-rust/issues/151
-    let mut vec = Vec::new();
+    /// Applies [`DecodeConfig::canonical_ints`] to an integer decoded after the given marker.
+    fn check_int(&self, marker: Marker, val: Integer) -> Result<Value, Error> {
+        if self.config.canonical_ints && marker != canonical_int_marker(val) {
+            Err(Error::NonCanonicalInt(marker))
+        } else {
+            Ok(Value::Integer(val))
+        }
+    }
 
-    while len > 0 {
-        vec.push((read_value_inner(rd, depth)?, read_value_inner(rd, depth)?));
-        len -= 1;
+    /// Returns whether the value of the map entry with the given key is to be captured raw.
+    fn is_raw_key(&self, key: &Value) -> bool {
+        match key.as_str() {
+            Some(key) => self.config.raw_keys.iter().any(|raw| raw == key),
+            None => false,
+        }
     }
 
-    Ok(vec)
+    /// Charges `len` elements of type `T` against the allocation budgets.
+    ///
+    /// The full claimed length is charged up front, so that a huge length prefix is rejected
+    /// before any of its data is read. Empty values don't allocate, so they are free.
+    fn allocate<T>(&mut self, len: usize) -> Result<(), Error> {
+        if let Some(ref mut remaining) = self.allocs_remaining {
+            if len > 0 {
+                *remaining = remaining.checked_sub(1).ok_or(Error::LengthLimitExceeded)?;
+            }
+        }
+        if let Some(ref mut remaining) = self.alloc_remaining {
+            let size = len.checked_mul(mem::size_of::<T>()).ok_or(Error::LengthLimitExceeded)?;
+            *remaining = remaining.checked_sub(size).ok_or(Error::LengthLimitExceeded)?;
+        }
+        Ok(())
+    }
 }
 
-fn read_str_data<R: Read>(rd: &mut R, len: usize, depth: usize) -> Result<Utf8String, Error> {
-    let depth = super::decrement_depth(depth)?;
+/// Returns the marker of the shortest encoding of the given integer, the one
+/// [`write_uint`](rmp::encode::write_uint) and [`write_sint`](rmp::encode::write_sint) use.
+pub(super) fn canonical_int_marker(val: Integer) -> Marker {
+    match (val.as_u64(), val.as_i64()) {
+        (Some(val), _) if val < 128 => Marker::FixPos(val as u8),
+        (Some(val), _) if val <= u64::from(u8::MAX) => Marker::U8,
+        (Some(val), _) if val <= u64::from(u16::MAX) => Marker::U16,
+        (Some(val), _) if val <= u64::from(u32::MAX) => Marker::U32,
+        (Some(..), _) => Marker::U64,
+        (None, Some(val)) if val >= -32 => Marker::FixNeg(val as i8),
+        (None, Some(val)) if val >= i64::from(i8::MIN) => Marker::I8,
+        (None, Some(val)) if val >= i64::from(i16::MIN) => Marker::I16,
+        (None, Some(val)) if val >= i64::from(i32::MIN) => Marker::I32,
+        (None, _) => Marker::I64,
+    }
+}
 
-    match String::from_utf8(read_bin_data(rd, len, depth)?) {
-        Ok(s) => Ok(Utf8String::from(s)),
-        Err(err) => {
-            let e = err.utf8_error();
-            let s = Utf8String {
-                s: Err((err.into_bytes(), e)),
-            };
-            Ok(s)
-        }
+/// Converts an integral float to an integer value, if it fits in 64 bits.
+fn integral_float(val: f64) -> Option<Value> {
+    // Also rejects NaN and infinities, whose fractional part is NaN.
+    if val.fract() != 0.0 {
+        None
+    } else if (-9223372036854775808.0..9223372036854775808.0).contains(&val) {
+        Some(Value::from(val as i64))
+    } else if (0.0..18446744073709551616.0).contains(&val) {
+        Some(Value::from(val as u64))
+    } else {
+        None
     }
 }
 
-fn read_bin_data<R: Read>(rd: &mut R, len: usize, depth: usize) -> Result<Vec<u8>, Error> {
-    let _depth = super::decrement_depth(depth)?;
+fn read_str_data<R: Read>(rd: &mut R, len: usize, cx: &mut Context<'_>) -> Result<Utf8String, Error> {
+    let buf = match cx.strings {
+        Some(strings) => {
+            cx.allocate::<u8>(len)?;
+            cx.buf.clear();
+            super::read_bytes_into(rd, len, &mut cx.buf)?;
 
-    let mut buf = Vec::with_capacity(min(len, PREALLOC_MAX));
-    let bytes_read = rd.take(len as u64).read_to_end(&mut buf).map_err(Error::InvalidDataRead)?;
-    if bytes_read != len {
-        return Err(Error::InvalidDataRead(io::Error::new(
-            io::ErrorKind::UnexpectedEof,
-            format!("Expected {} bytes, read {} bytes", len, bytes_read),
-        )));
+            if let Some(s) = str::from_utf8(&cx.buf).ok().and_then(|s| strings.get(s)) {
+                return Ok(Utf8String::from(s.clone()));
+            }
+            cx.buf.clone()
+        }
+        None => read_bin_data(rd, len, cx)?,
+    };
+    if cx.config.defer_utf8_validation {
+        Ok(Utf8String::deferred(buf))
+    } else {
+        let s = Utf8String::from_bytes(buf);
+        if s.is_err() {
+            cx.warn(DecodeWarningKind::InvalidUtf8);
+        }
+        Ok(s)
     }
+}
 
-    Ok(buf)
+fn read_bin_data<R: Read>(rd: &mut R, len: usize, cx: &mut Context<'_>) -> Result<Vec<u8>, Error> {
+    cx.allocate::<u8>(len)?;
+
+    super::read_byte_vec(rd, len)
 }
 
-fn read_ext_body<R: Read>(rd: &mut R, len: usize, depth: usize) -> Result<(i8, Vec<u8>), Error> {
-    let depth = super::decrement_depth(depth)?;
+/// Reads binary data as a [`Value::Binary`], or skips it and returns a [`Value::BinaryRef`] if it
+/// is longer than the threshold of [`read_value_with_binary_refs`].
+fn read_bin_value<R: Read>(rd: &mut R, len: usize, cx: &mut Context<'_>) -> Result<Value, Error> {
+    match cx.binary_refs {
+        Some(ref refs) if len > refs.threshold => {
+            let offset = refs.pos.get();
+            if offset.saturating_add(len as u64) > refs.end {
+                return Err(Error::InvalidDataRead(ErrorKind::UnexpectedEof.into()));
+            }
+            // The reader seeks past the data before reading further.
+            refs.pos.set(offset + len as u64);
+            refs.skip.set(refs.skip.get() + len as u64);
+            // The length came from a 32-bit header.
+            Ok(Value::BinaryRef { offset, len: len as u32 })
+        }
+        _ => read_bin_data(rd, len, cx).map(Value::Binary),
+    }
+}
 
+fn read_ext_body<R: Read>(rd: &mut R, len: usize, cx: &mut Context<'_>) -> Result<(i8, Vec<u8>), Error> {
     let ty = rd.read_data_i8()?;
-    let vec = read_bin_data(rd, len, depth)?;
+    let vec = read_bin_data(rd, len, cx)?;
 
     Ok((ty, vec))
 }
 
-fn read_value_inner<R>(rd: &mut R, depth: usize) -> Result<Value, Error> where R: Read {
-    let depth = super::decrement_depth(depth)?;
-    let val = match read_marker(rd)? {
+/// A single step of decoding: either a complete value or the header of a non-empty container,
+/// whose elements follow.
+enum Item {
+    Value(Value),
+    Array(usize),
+    Map(usize),
+}
+
+fn array_item(len: usize, cx: &mut Context<'_>) -> Result<Item, Error> {
+    cx.check_input_len(len, 1)?;
+    cx.allocate::<Value>(len)?;
+
+    if len == 0 {
+        Ok(Item::Value(Value::Array(Vec::new())))
+    } else {
+        Ok(Item::Array(len))
+    }
+}
+
+fn map_item(len: usize, cx: &mut Context<'_>) -> Result<Item, Error> {
+    cx.check_input_len(len, 2)?;
+    cx.allocate::<(Value, Value)>(len)?;
+
+    if len == 0 {
+        Ok(Item::Value(Value::Map(Vec::new())))
+    } else {
+        Ok(Item::Map(len))
+    }
+}
+
+fn read_item<R>(rd: &mut R, cx: &mut Context<'_>) -> Result<Item, Error> where R: Read {
+    cx.mark();
+    let marker = read_marker(rd)?;
+    let val = match marker {
         Marker::Null => Value::Nil,
         Marker::True => Value::Boolean(true),
         Marker::False => Value::Boolean(false),
         Marker::FixPos(val) => Value::from(val),
         Marker::FixNeg(val) => Value::from(val),
-        Marker::U8 => Value::from(rd.read_data_u8()?),
-        Marker::U16 => Value::from(rd.read_data_u16()?),
-        Marker::U32 => Value::from(rd.read_data_u32()?),
-        Marker::U64 => Value::from(rd.read_data_u64()?),
-        Marker::I8 => Value::from(rd.read_data_i8()?),
-        Marker::I16 => Value::from(rd.read_data_i16()?),
-        Marker::I32 => Value::from(rd.read_data_i32()?),
-        Marker::I64 => Value::from(rd.read_data_i64()?),
-        Marker::F32 => Value::F32(rd.read_data_f32()?),
-        Marker::F64 => Value::F64(rd.read_data_f64()?),
+        Marker::U8 => cx.check_int(marker, rd.read_data_u8()?.into())?,
+        Marker::U16 => cx.check_int(marker, rd.read_data_u16()?.into())?,
+        Marker::U32 => cx.check_int(marker, rd.read_data_u32()?.into())?,
+        Marker::U64 => cx.check_int(marker, rd.read_data_u64()?.into())?,
+        Marker::I8 => cx.check_int(marker, rd.read_data_i8()?.into())?,
+        Marker::I16 => cx.check_int(marker, rd.read_data_i16()?.into())?,
+        Marker::I32 => cx.check_int(marker, rd.read_data_i32()?.into())?,
+        Marker::I64 => cx.check_int(marker, rd.read_data_i64()?.into())?,
+        Marker::F32 => {
+            let val = rd.read_data_f32()?;
+            cx.check_float(val.is_subnormal(), val.into())?;
+            Value::F32(val)
+        }
+        Marker::F64 => {
+            let val = rd.read_data_f64()?;
+            cx.check_float(val.is_subnormal(), val)?;
+            match integral_float(val) {
+                Some(int) if cx.config.coerce_integral_floats => {
+                    cx.warn(DecodeWarningKind::IntegralFloat(val));
+                    int
+                }
+                _ => Value::F64(val),
+            }
+        }
         Marker::FixStr(len) => {
-            let res = read_str_data(rd, len as usize, depth)?;
+            let res = read_str_data(rd, super::checked_len(u32::from(len))?, cx)?;
             Value::String(res)
         }
         Marker::Str8 => {
             let len = rd.read_data_u8()?;
-            let res = read_str_data(rd, len as usize, depth)?;
+            let res = read_str_data(rd, super::checked_len(u32::from(len))?, cx)?;
             Value::String(res)
         }
         Marker::Str16 => {
             let len = rd.read_data_u16()?;
-            let res = read_str_data(rd, len as usize, depth)?;
+            let res = read_str_data(rd, super::checked_len(u32::from(len))?, cx)?;
             Value::String(res)
         }
         Marker::Str32 => {
             let len = rd.read_data_u32()?;
-            let res = read_str_data(rd, len as usize, depth)?;
+            let res = read_str_data(rd, super::checked_len(len)?, cx)?;
             Value::String(res)
         }
         Marker::FixArray(len) => {
-            let vec = read_array_data(rd, len as usize, depth)?;
-            Value::Array(vec)
+            return array_item(len as usize, cx);
         }
         Marker::Array16 => {
             let len = rd.read_data_u16()?;
-            let vec = read_array_data(rd, len as usize, depth)?;
-            Value::Array(vec)
+            return array_item(len as usize, cx);
         }
         Marker::Array32 => {
             let len = rd.read_data_u32()?;
-            let vec = read_array_data(rd, len as usize, depth)?;
-            Value::Array(vec)
+            return array_item(len as usize, cx);
         }
         Marker::FixMap(len) => {
-            let map = read_map_data(rd, len as usize, depth)?;
-            Value::Map(map)
+            return map_item(len as usize, cx);
         }
         Marker::Map16 => {
             let len = rd.read_data_u16()?;
-            let map = read_map_data(rd, len as usize, depth)?;
-            Value::Map(map)
+            return map_item(len as usize, cx);
         }
         Marker::Map32 => {
             let len = rd.read_data_u32()?;
-            let map = read_map_data(rd, len as usize, depth)?;
-            Value::Map(map)
+            return map_item(len as usize, cx);
         }
         Marker::Bin8 => {
             let len = rd.read_data_u8()?;
-            let vec = read_bin_data(rd, len as usize, depth)?;
-            Value::Binary(vec)
+            read_bin_value(rd, super::checked_len(u32::from(len))?, cx)?
         }
         Marker::Bin16 => {
             let len = rd.read_data_u16()?;
-            let vec = read_bin_data(rd, len as usize, depth)?;
-            Value::Binary(vec)
+            read_bin_value(rd, super::checked_len(u32::from(len))?, cx)?
         }
         Marker::Bin32 => {
             let len = rd.read_data_u32()?;
-            let vec = read_bin_data(rd, len as usize, depth)?;
-            Value::Binary(vec)
+            read_bin_value(rd, super::checked_len(len)?, cx)?
         }
         Marker::FixExt1 => {
             let len = 1_usize;
-            let (ty, vec) = read_ext_body(rd, len, depth)?;
+            let (ty, vec) = read_ext_body(rd, len, cx)?;
             Value::Ext(ty, vec)
         }
         Marker::FixExt2 => {
             let len = 2_usize;
-            let (ty, vec) = read_ext_body(rd, len, depth)?;
+            let (ty, vec) = read_ext_body(rd, len, cx)?;
             Value::Ext(ty, vec)
         }
         Marker::FixExt4 => {
             let len = 4_usize;
-            let (ty, vec) = read_ext_body(rd, len, depth)?;
+            let (ty, vec) = read_ext_body(rd, len, cx)?;
             Value::Ext(ty, vec)
         }
         Marker::FixExt8 => {
             let len = 8_usize;
-            let (ty, vec) = read_ext_body(rd, len, depth)?;
+            let (ty, vec) = read_ext_body(rd, len, cx)?;
             Value::Ext(ty, vec)
         }
         Marker::FixExt16 => {
             let len = 16_usize;
-            let (ty, vec) = read_ext_body(rd, len, depth)?;
+            let (ty, vec) = read_ext_body(rd, len, cx)?;
             Value::Ext(ty, vec)
         }
         Marker::Ext8 => {
-            let len = rd.read_data_u8()? as usize;
-            let (ty, vec) = read_ext_body(rd, len, depth)?;
+            let len = super::checked_len(rd.read_data_u8()?.into())?;
+            let (ty, vec) = read_ext_body(rd, len, cx)?;
             Value::Ext(ty, vec)
         }
         Marker::Ext16 => {
-            let len = rd.read_data_u16()? as usize;
-            let (ty, vec) = read_ext_body(rd, len, depth)?;
+            let len = super::checked_len(rd.read_data_u16()?.into())?;
+            let (ty, vec) = read_ext_body(rd, len, cx)?;
             Value::Ext(ty, vec)
         }
         Marker::Ext32 => {
-            let len = rd.read_data_u32()? as usize;
-            let (ty, vec) = read_ext_body(rd, len, depth)?;
+            let len = super::checked_len(rd.read_data_u32()?)?;
+            let (ty, vec) = read_ext_body(rd, len, cx)?;
             Value::Ext(ty, vec)
         }
         Marker::Reserved => Value::Nil,
     };
 
-    Ok(val)
+    Ok(Item::Value(val))
+}
+
+fn read_raw_item<R: Read>(rd: &mut R, cx: &mut Context<'_>) -> Result<Item, Error> {
+    let mut buf = Vec::new();
+    // Stop copying as soon as the budget is exhausted, rather than charging the whole value after.
+    super::raw::copy_value_limited(rd, &mut buf, cx.alloc_remaining)?;
+    cx.allocate::<u8>(buf.len())?;
+
+    Ok(Item::Value(Value::Raw(buf)))
+}
+
+/// A container whose elements are being decoded.
+enum Frame {
+    Array(Vec<Value>, usize),
+    /// Entries, the key of the entry being decoded, if already read, and the remaining entries.
+    Map(Vec<(Value, Value)>, Option<Value>, usize),
+}
+
+impl Frame {
+    /// Adds a decoded element, returning whether the container is complete.
+    fn push(&mut self, val: Value) -> bool {
+        match *self {
+            Frame::Array(ref mut vec, ref mut remaining) => {
+                vec.push(val);
+                *remaining -= 1;
+                *remaining == 0
+            }
+            Frame::Map(ref mut vec, ref mut key, ref mut remaining) => match key.take() {
+                None => {
+                    *key = Some(val);
+                    false
+                }
+                Some(key) => {
+                    vec.push((key, val));
+                    *remaining -= 1;
+                    *remaining == 0
+                }
+            },
+        }
+    }
+
+    /// Returns the key of the map entry whose value is to be decoded next.
+    fn pending_key(&self) -> Option<&Value> {
+        match *self {
+            Frame::Array(..) => None,
+            Frame::Map(_, ref key, _) => key.as_ref(),
+        }
+    }
+
+    /// Describes the truncation of this container, found while decoding its next element.
+    fn truncated(&self) -> Error {
+        let (kind, index, remaining) = match *self {
+            Frame::Array(ref vec, remaining) => (ContainerKind::Array, vec.len(), remaining),
+            Frame::Map(ref vec, _, remaining) => (ContainerKind::Map, vec.len(), remaining),
+        };
+        Error::TruncatedContainer { kind, index, expected: index + remaining }
+    }
+
+    fn into_value(self, cx: &mut Context<'_>) -> Result<Value, Error> {
+        let config = cx.config;
+        let val = match self {
+            Frame::Array(vec, ..) if config.binary_chunks.is_some_and(|ty| is_binary_chunks(&vec, ty)) => {
+                join_binary_chunks(vec)
+            }
+            Frame::Array(vec, ..) if config.arrays_of_pairs_as_maps => pairs_to_map(vec, cx)?,
+            Frame::Array(vec, ..) => Value::Array(vec),
+            Frame::Map(vec, ..) => Value::Map(vec),
+        };
+        Ok(val)
+    }
+}
+
+/// Returns whether the array is a sequence of binary chunks of the given ext type, for
+/// [`DecodeConfig::binary_chunks`].
+fn is_binary_chunks(vec: &[Value], ty: i8) -> bool {
+    !vec.is_empty() && vec.iter().enumerate().all(|(idx, val)| match *val {
+        Value::Ext(t, ref buf) if t == ty && buf.len() >= 5 => {
+            let is_final = idx + 1 == vec.len();
+            buf[..4] == (idx as u32).to_be_bytes() && buf[4] == u8::from(is_final)
+        }
+        _ => false,
+    })
+}
+
+/// Concatenates the data of binary chunks checked with [`is_binary_chunks`].
+fn join_binary_chunks(vec: Vec<Value>) -> Value {
+    let mut buf = Vec::new();
+    for val in vec {
+        if let Value::Ext(_, chunk) = val {
+            buf.extend_from_slice(&chunk[5..]);
+        }
+    }
+
+    Value::Binary(buf)
+}
+
+/// Converts an array whose elements are all 2-element arrays to a map of those pairs, for
+/// [`DecodeConfig::arrays_of_pairs_as_maps`]. Other arrays are kept as is.
+///
+/// The entries of the map are charged to the budgets, like those of a decoded map.
+fn pairs_to_map(vec: Vec<Value>, cx: &mut Context<'_>) -> Result<Value, Error> {
+    if !vec.iter().all(|val| matches!(val, Value::Array(pair) if pair.len() == 2)) {
+        return Ok(Value::Array(vec));
+    }
+    cx.allocate::<(Value, Value)>(vec.len())?;
+
+    let map = vec.into_iter().filter_map(|val| match val {
+        Value::Array(pair) => <[Value; 2]>::try_from(pair).ok(),
+        _ => None,
+    });
+    Ok(Value::Map(map.map(|[key, val]| (key, val)).collect()))
+}
+
+/// Decodes a value without recursion, keeping the containers being decoded on the heap, so that
+/// deeply nested input can't overflow the call stack.
+fn read_value_inner<R>(rd: &mut R, max_depth: usize, cx: &mut Context<'_>) -> Result<Value, Error> where R: Read {
+    let mut stack = Vec::new();
+    // The product of the declared lengths of each container in `stack` and its ancestors.
+    let mut products = Vec::new();
+
+    loop {
+        if stack.len() >= max_depth {
+            return Err(Error::DepthLimitExceeded);
+        }
+
+        let item = match stack.last().and_then(Frame::pending_key) {
+            Some(key) if cx.is_raw_key(key) => read_raw_item(rd, cx),
+            _ => read_item(rd, cx),
+        };
+        let item = match (item, stack.last()) {
+            (Ok(item), _) => item,
+            (Err(Error::InvalidMarkerRead(ref err)), Some(frame)) |
+            (Err(Error::InvalidDataRead(ref err)), Some(frame)) if err.kind() == ErrorKind::UnexpectedEof => {
+                return Err(frame.truncated());
+            }
+            (Err(err), _) => return Err(err),
+        };
+        if let Item::Array(len) | Item::Map(len) = item {
+            let product = products.last().copied().unwrap_or(1u64).saturating_mul(len as u64);
+            match cx.config.max_declared_product {
+                Some(max) if product > max => return Err(Error::LengthLimitExceeded),
+                _ => products.push(product),
+            }
+        }
+        let mut val = match item {
+            Item::Value(val) => val,
+            Item::Array(len) => {
+                stack.push(Frame::Array(super::safe_with_capacity(len, super::CONTAINER_PREALLOC_MAX), len));
+                continue;
+            }
+            Item::Map(len) => {
+                stack.push(Frame::Map(super::safe_with_capacity(len, super::CONTAINER_PREALLOC_MAX), None, len));
+                continue;
+            }
+        };
+
+        // Hand the value to its container, completing as many containers as possible.
+        loop {
+            match stack.pop() {
+                None => return Ok(val),
+                Some(mut frame) => {
+                    if frame.push(val) {
+                        products.pop();
+                        val = frame.into_value(cx)?;
+                    } else {
+                        stack.push(frame);
+                        break;
+                    }
+                }
+            }
+        }
+    }
 }
 
 /// Attempts to read bytes from the given reader and interpret them as a [`Value`].
@@ -213,14 +548,24 @@ fn read_value_inner<R>(rd: &mut R, depth: usize) -> Result<Value, Error> where R
 /// All instances of [`ErrorKind::Interrupted`](io::ErrorKind) are handled by this function and the
 /// underlying operation is retried.
 ///
-/// [`Error::DepthLimitExceeded`] is returned if this function recurses
-/// [`MAX_DEPTH`](super::MAX_DEPTH) times. To configure the maximum recursion depth, use
+/// [`Error::DepthLimitExceeded`] is returned if the value is nested more than
+/// [`MAX_DEPTH`](super::MAX_DEPTH) levels deep. To configure the maximum depth, use
 /// [`read_value_with_max_depth`] instead.
+///
+/// # Truncated input
+///
+/// An array or map header may declare far more elements than the input actually contains. Since a
+/// plain [`Read`] can't tell how much data is left, such input is only rejected once an element
+/// hits the end of the stream, after the preceding elements have been decoded. Use
+/// [`read_value_seekable`] to reject it upfront when the reader is seekable.
+///
+/// Input ending in the middle of an array or map is reported as [`Error::TruncatedContainer`],
+/// pointing at the element that couldn't be decoded.
 #[inline(never)]
 pub fn read_value<R>(rd: &mut R) -> Result<Value, Error>
     where R: Read
 {
-    read_value_inner(rd, super::MAX_DEPTH)
+    read_value_with_config(rd, &DecodeConfig::default())
 }
 
 /// Attempts to read bytes from the given reader and interpret them as a [`Value`].
@@ -231,12 +576,300 @@ pub fn read_value<R>(rd: &mut R) -> Result<Value, Error>
 /// All instances of [`ErrorKind::Interrupted`](io::ErrorKind) are handled by this function and the
 /// underlying operation is retried.
 ///
-/// [`Error::DepthLimitExceeded`] is returned if this function recurses
-/// `max_depth` times. If the default [`MAX_DEPTH`](super::MAX_DEPTH) is sufficient or you do not
-/// need recursion depth checking for your data, consider using [`read_value`] instead.
+/// [`Error::DepthLimitExceeded`] is returned if the value is nested more than `max_depth` levels
+/// deep. If the default [`MAX_DEPTH`](super::MAX_DEPTH) is sufficient or you do not
+/// need depth checking for your data, consider using [`read_value`] instead.
 #[inline(never)]
 pub fn read_value_with_max_depth<R>(rd: &mut R, max_depth: usize) -> Result<Value, Error>
     where R: Read
 {
-    read_value_inner(rd, max_depth)
+    read_value_with_config(rd, &DecodeConfig { max_depth, ..DecodeConfig::default() })
+}
+
+/// Attempts to read bytes from the given reader and interpret them as a [`Value`], applying the
+/// limits from `config`.
+///
+/// # Errors
+///
+/// This function will return [`Error`] on any I/O error while either reading or decoding a [`Value`].
+/// All instances of [`ErrorKind::Interrupted`](io::ErrorKind) are handled by this function and the
+/// underlying operation is retried.
+///
+/// [`Error::DepthLimitExceeded`] is returned if the value is nested more than
+/// [`DecodeConfig::max_depth`] levels deep, and [`Error::LengthLimitExceeded`] if the value would
+/// allocate more than [`DecodeConfig::max_total_alloc`] bytes or [`DecodeConfig::max_allocations`]
+/// times in total, or declares more nested elements than [`DecodeConfig::max_declared_product`].
+#[inline(never)]
+pub fn read_value_with_config<R>(rd: &mut R, config: &DecodeConfig) -> Result<Value, Error>
+    where R: Read
+{
+    read_value_inner(rd, config.max_depth, &mut Context::new(config))
+}
+
+/// Attempts to read bytes from the given seekable reader and interpret them as a [`Value`],
+/// applying the limits from `config`.
+///
+/// Unlike [`read_value_with_config`], the number of bytes left in the reader is determined before
+/// decoding, so that an array or map declaring more elements than could possibly fit in the input
+/// left after its header is rejected without decoding any of them, wherever it's nested.
+///
+/// # Errors
+///
+/// In addition to the errors of [`read_value_with_config`], returns [`Error::InvalidDataRead`] if
+/// seeking fails and [`Error::LengthLimitExceeded`] if a container declares more elements than the
+/// input can hold.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use rmpv::decode::{read_value_seekable, DecodeConfig, Error};
+///
+/// // An array claiming a million elements, followed by just a few bytes.
+/// let buf = [0xdd, 0x00, 0x0f, 0x42, 0x40, 0x01, 0x02, 0x03];
+///
+/// match read_value_seekable(&mut Cursor::new(&buf[..]), &DecodeConfig::default()) {
+///     Err(Error::LengthLimitExceeded) => (),
+///     other => panic!("unexpected result: {:?}", other),
+/// }
+/// ```
+#[inline(never)]
+pub fn read_value_seekable<R>(rd: &mut R, config: &DecodeConfig) -> Result<Value, Error>
+    where R: Read + Seek
+{
+    let pos = rd.stream_position().map_err(Error::InvalidDataRead)?;
+    let end = rd.seek(SeekFrom::End(0)).map_err(Error::InvalidDataRead)?;
+    rd.seek(SeekFrom::Start(pos)).map_err(Error::InvalidDataRead)?;
+
+    let read = Cell::new(0);
+    let mut rd = Counting { rd, pos: &read };
+
+    let mut cx = Context::new(config);
+    cx.input_len = Some((end.saturating_sub(pos), &read));
+    read_value_inner(&mut rd, config.max_depth, &mut cx)
+}
+
+/// Attempts to read bytes from the given reader and interpret them as a [`Value`], applying the
+/// limits from `config` and deduplicating string values against `strings`.
+///
+/// Every string value equal to one of `strings` shares its allocation instead of allocating a copy,
+/// which cuts allocations when decoding many records with categorical fields, like enums. Map keys
+/// are deduplicated as well; other strings are decoded as usual.
+///
+/// # Errors
+///
+/// This function returns the same errors as [`read_value_with_config`].
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashSet;
+/// use std::sync::Arc;
+///
+/// use rmpv::decode::{read_value_with_strings, DecodeConfig};
+/// use rmpv::Value;
+///
+/// let strings: HashSet<Arc<str>> = ["red", "green"].into_iter().map(Arc::from).collect();
+///
+/// // ["red", "blue"]
+/// let buf = [0x92, 0xa3, 0x72, 0x65, 0x64, 0xa4, 0x62, 0x6c, 0x75, 0x65];
+///
+/// let val = read_value_with_strings(&mut &buf[..], &DecodeConfig::default(), &strings).unwrap();
+///
+/// assert_eq!(Value::Array(vec![Value::from("red"), Value::from("blue")]), val);
+/// ```
+#[inline(never)]
+pub fn read_value_with_strings<R>(rd: &mut R, config: &DecodeConfig, strings: &HashSet<Arc<str>>) -> Result<Value, Error>
+    where R: Read
+{
+    let mut cx = Context::new(config);
+    cx.strings = Some(strings);
+    read_value_inner(rd, config.max_depth, &mut cx)
+}
+
+/// Counts the bytes read through it, for the offsets of [`DecodeWarning`]s and the input left to
+/// [`read_value_seekable`].
+pub(super) struct Counting<'a, R> {
+    pub(super) rd: R,
+    pub(super) pos: &'a Cell<u64>,
+}
+
+impl<R: Read> Read for Counting<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.rd.read(buf)?;
+        self.pos.set(self.pos.get() + len as u64);
+        Ok(len)
+    }
+}
+
+/// Attempts to read bytes from the given reader and interpret them as a [`Value`], applying the
+/// options from `config` and recording a [`DecodeWarning`] in `warnings` for every coercion made.
+///
+/// The coercions reported are strings that aren't valid UTF-8, unless
+/// [`DecodeConfig::defer_utf8_validation`] is set, and floats converted to integers with
+/// [`DecodeConfig::coerce_integral_floats`]. This allows logging or rejecting messages that only
+/// decode thanks to lenient options.
+///
+/// # Errors
+///
+/// This function returns the same errors as [`read_value_with_config`]. The warnings recorded
+/// before a failure are kept.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::{read_value_with_warnings, DecodeConfig, DecodeWarning, DecodeWarningKind};
+/// use rmpv::Value;
+///
+/// let config = DecodeConfig { coerce_integral_floats: true, ..DecodeConfig::default() };
+///
+/// // [1, 2.0]
+/// let buf = [0x92, 0x01, 0xcb, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+/// let mut warnings = Vec::new();
+///
+/// let val = read_value_with_warnings(&mut &buf[..], &config, &mut warnings).unwrap();
+///
+/// assert_eq!(Value::Array(vec![Value::from(1), Value::from(2)]), val);
+/// assert_eq!(vec![DecodeWarning { offset: 2, kind: DecodeWarningKind::IntegralFloat(2.0) }], warnings);
+/// ```
+pub fn read_value_with_warnings<R>(rd: &mut R, config: &DecodeConfig, warnings: &mut Vec<DecodeWarning>) -> Result<Value, Error>
+    where R: Read
+{
+    let pos = Cell::new(0);
+    let mut rd = Counting { rd, pos: &pos };
+
+    let mut cx = Context::new(config);
+    cx.warnings = Some((&pos, warnings));
+    read_value_inner(&mut rd, config.max_depth, &mut cx)
+}
+
+/// Attempts to read bytes from the given seekable reader and interpret them as a [`Value`], applying
+/// the options from `config`, but leaving binaries longer than `threshold` bytes in the input.
+///
+/// Each such binary is skipped and decoded as a [`Value::BinaryRef`] holding the position of its
+/// data in the reader and its length, so that a structure holding large blobs the caller may not
+/// need can be decoded without allocating them. The data can be read later by seeking to that
+/// position. Skipped bytes aren't read: the reader seeks past them, and a binary extending past the
+/// end of the input is detected from the length of the stream.
+///
+/// # Errors
+///
+/// In addition to the errors of [`read_value_with_config`], returns [`Error::InvalidDataRead`] if
+/// the position or length of the reader can't be determined, or seeking fails.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::{Cursor, Read, Seek, SeekFrom};
+///
+/// use rmpv::decode::{read_value_with_binary_refs, DecodeConfig};
+/// use rmpv::Value;
+///
+/// // [1, <binary of 4 bytes>]
+/// let buf = [0x92, 0x01, 0xc4, 0x04, 0xde, 0xad, 0xbe, 0xef];
+/// let mut rd = Cursor::new(&buf[..]);
+///
+/// let val = read_value_with_binary_refs(&mut rd, &DecodeConfig::default(), 2).unwrap();
+/// assert_eq!(Value::Array(vec![Value::from(1), Value::BinaryRef { offset: 4, len: 4 }]), val);
+///
+/// let mut data = vec![0; 4];
+/// rd.seek(SeekFrom::Start(4)).unwrap();
+/// rd.read_exact(&mut data).unwrap();
+/// assert_eq!(vec![0xde, 0xad, 0xbe, 0xef], data);
+/// ```
+pub fn read_value_with_binary_refs<R>(rd: &mut R, config: &DecodeConfig, threshold: usize) -> Result<Value, Error>
+    where R: Read + Seek
+{
+    let start = rd.stream_position().map_err(Error::InvalidDataRead)?;
+    let end = rd.seek(SeekFrom::End(0)).map_err(Error::InvalidDataRead)?;
+    rd.seek(SeekFrom::Start(start)).map_err(Error::InvalidDataRead)?;
+
+    let pos = Cell::new(start);
+    let skip = Cell::new(0);
+    let mut rd = Skipping { rd, pos: &pos, skip: &skip };
+
+    let mut cx = Context::new(config);
+    cx.binary_refs = Some(BinaryRefs { pos: &pos, end, skip: &skip, threshold });
+    let val = read_value_inner(&mut rd, config.max_depth, &mut cx)?;
+    // The value may end with a binary left in the input.
+    rd.seek_skipped().map_err(Error::InvalidDataRead)?;
+    Ok(val)
+}
+
+/// How [`read_value_with_binary_refs`] leaves binaries in the input.
+struct BinaryRefs<'a> {
+    /// The position in the input.
+    pos: &'a Cell<u64>,
+    /// The length of the input, past which binaries are truncated.
+    end: u64,
+    /// The number of bytes of binaries left in the input that the reader hasn't seeked past yet.
+    skip: &'a Cell<u64>,
+    /// The length above which binaries are left in the input.
+    threshold: usize,
+}
+
+/// A reader that seeks past the binaries left in the input before reading further, and keeps
+/// track of the position like [`Counting`].
+struct Skipping<'a, R> {
+    rd: R,
+    pos: &'a Cell<u64>,
+    skip: &'a Cell<u64>,
+}
+
+impl<R: Seek> Skipping<'_, R> {
+    fn seek_skipped(&mut self) -> io::Result<()> {
+        let skip = self.skip.replace(0);
+        if skip > 0 {
+            // Skipped binaries are shorter than the input, whose length was determined by seeking.
+            self.rd.seek(SeekFrom::Current(skip as i64))?;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> Read for Skipping<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.seek_skipped()?;
+        let len = self.rd.read(buf)?;
+        self.pos.set(self.pos.get() + len as u64);
+        Ok(len)
+    }
+}
+
+/// Reads the next [`Value`] and checks that it equals `expected`.
+///
+/// This is handy for verifying magic numbers or version fields at the start of a stream.
+///
+/// # Errors
+///
+/// In addition to the errors of [`read_value`], returns [`Error::UnexpectedValue`] containing the
+/// value read if it differs from `expected`.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::decode::{expect_value, Error};
+/// use rmpv::Value;
+///
+/// let magic = Value::from("RMP1");
+///
+/// let buf = [0xa4, 0x52, 0x4d, 0x50, 0x31];
+/// expect_value(&mut &buf[..], &magic).unwrap();
+///
+/// let buf = [0xa4, 0x52, 0x4d, 0x50, 0x32];
+/// match expect_value(&mut &buf[..], &magic) {
+///     Err(Error::UnexpectedValue(val)) => assert_eq!(Value::from("RMP2"), val),
+///     other => panic!("unexpected result: {:?}", other),
+/// }
+/// ```
+pub fn expect_value<R>(rd: &mut R, expected: &Value) -> Result<(), Error>
+    where R: Read
+{
+    let val = read_value(rd)?;
+    if val == *expected {
+        Ok(())
+    } else {
+        Err(Error::UnexpectedValue(val))
+    }
 }
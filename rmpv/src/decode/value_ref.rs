@@ -55,9 +55,7 @@ fn read_array_data<'a, R>(rd: &mut R, mut len: usize, depth: usize) -> Result<Ve
     where R: BorrowRead<'a>
 {
     let depth = super::decrement_depth(depth)?;
-    // Note: Do not preallocate a Vec of size `len`.
-    // See https://github.com/3Hren/msgpack-rust/issues/151
-    let mut vec = Vec::new();
+    let mut vec = super::safe_with_capacity(len, super::CONTAINER_PREALLOC_MAX);
 
     while len > 0 {
         vec.push(read_value_ref_inner(rd, depth)?);
@@ -71,9 +69,7 @@ fn read_map_data<'a, R>(rd: &mut R, mut len: usize, depth: usize) -> Result<Vec<
     where R: BorrowRead<'a>
 {
     let depth = super::decrement_depth(depth)?;
-    // Note: Do not preallocate a Vec of size `len`.
-    // See https://github.com/3Hren/msgpack-rust/issues/151
-    let mut vec = Vec::new();
+    let mut vec = super::safe_with_capacity(len, super::CONTAINER_PREALLOC_MAX);
 
     while len > 0 {
         vec.push((read_value_ref_inner(rd, depth)?, read_value_ref_inner(rd, depth)?));
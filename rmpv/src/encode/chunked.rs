@@ -0,0 +1,231 @@
+use std::slice;
+
+use rmp::encode::{write_array_len, write_bin_len, write_map_len, write_str_len};
+
+use super::value::{binary_chunks_layout, sorted_entries, write_binary_chunk_header, write_ext_header, SortedEntries};
+use super::{write_value_with_config, BinaryChunks, EncodeConfig, Error};
+use crate::Value;
+
+/// A container whose elements are being encoded.
+enum Frame<'a> {
+    Array(slice::Iter<'a, Value>),
+    /// Remaining entries and the value of the entry whose key has just been encoded.
+    Map(SortedEntries<'a>, Option<&'a Value>),
+    /// The remaining chunks of a binary split by [`EncodeConfig::binary_chunks`], the index of the
+    /// next one and their number.
+    Chunks(slice::Chunks<'a, u8>, usize, usize, BinaryChunks),
+}
+
+/// Encodes a [`Value`] tree piece by piece into caller-provided buffers, instead of writing it to
+/// a [`Write`](std::io::Write) all at once.
+///
+/// This lets the caller produce the encoded bytes only as fast as they are consumed, for example
+/// when sending them into a bounded channel. The encoder suspends anywhere in the tree, including
+/// in the middle of a string or binary, whose payload is copied directly from the value.
+///
+/// The bytes produced are exactly the ones [`write_value_with_config`] writes.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::encode::ChunkedEncoder;
+/// use rmpv::Value;
+///
+/// let val = Value::Array(vec![Value::from("le message"), Value::from(42)]);
+///
+/// let mut enc = ChunkedEncoder::new(&val);
+/// let mut out = Vec::new();
+/// let mut chunk = [0; 4];
+/// loop {
+///     let len = enc.next_chunk(&mut chunk).unwrap();
+///     if len == 0 {
+///         break;
+///     }
+///     out.extend_from_slice(&chunk[..len]);
+/// }
+///
+/// let mut expected = Vec::new();
+/// rmpv::encode::write_value(&mut expected, &val).unwrap();
+/// assert_eq!(expected, out);
+/// ```
+pub struct ChunkedEncoder<'a> {
+    config: EncodeConfig,
+    /// The value to encode next, if its container hasn't been entered yet.
+    root: Option<&'a Value>,
+    stack: Vec<Frame<'a>>,
+    /// Encoded bytes not handed out yet, and how many of them have been already.
+    head: Vec<u8>,
+    head_pos: usize,
+    /// The rest of the payload of the value being encoded, borrowed from the value.
+    data: &'a [u8],
+}
+
+impl<'a> ChunkedEncoder<'a> {
+    /// Constructs an encoder producing the given value with the default [`EncodeConfig`].
+    #[inline]
+    #[must_use]
+    pub fn new(val: &'a Value) -> Self {
+        Self::with_config(val, EncodeConfig::default())
+    }
+
+    /// Constructs an encoder producing the given value, applying the options from `config`.
+    #[must_use]
+    pub fn with_config(val: &'a Value, config: EncodeConfig) -> Self {
+        Self {
+            config,
+            root: Some(val),
+            stack: Vec::new(),
+            head: Vec::new(),
+            head_pos: 0,
+            data: &[],
+        }
+    }
+
+    /// Returns `true` once all the bytes have been produced.
+    #[inline]
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.root.is_none() && self.stack.is_empty() && self.head_pos == self.head.len() && self.data.is_empty()
+    }
+
+    /// Fills `buf` with the next encoded bytes, returning how many have been written.
+    ///
+    /// The buffer is filled completely unless the end of the value is reached, so `0` is only
+    /// returned for an empty `buf` or once the whole value has been produced.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`write_value_with_config`] for the value being encoded, such as a
+    /// non-finite float rejected by [`EncodeConfig::non_finite_floats`]. The encoder is finished
+    /// afterwards.
+    pub fn next_chunk(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut len = 0;
+
+        while len < buf.len() {
+            if self.head_pos < self.head.len() {
+                let n = (buf.len() - len).min(self.head.len() - self.head_pos);
+                buf[len..len + n].copy_from_slice(&self.head[self.head_pos..self.head_pos + n]);
+                self.head_pos += n;
+                len += n;
+            } else if !self.data.is_empty() {
+                let n = (buf.len() - len).min(self.data.len());
+                let (chunk, rest) = self.data.split_at(n);
+                buf[len..len + n].copy_from_slice(chunk);
+                self.data = rest;
+                len += n;
+            } else {
+                self.head.clear();
+                self.head_pos = 0;
+                match self.advance() {
+                    Ok(true) => {}
+                    Ok(false) => break,
+                    Err(err) => {
+                        self.root = None;
+                        self.stack.clear();
+                        self.head.clear();
+                        return Err(err);
+                    }
+                }
+            }
+        }
+
+        Ok(len)
+    }
+
+    /// Encodes the header of the next value, or of the next binary chunk, in the order their bytes
+    /// appear, returning `false` once there is none left.
+    fn advance(&mut self) -> Result<bool, Error> {
+        if let Some(val) = self.root.take() {
+            self.start(val)?;
+            return Ok(true);
+        }
+
+        loop {
+            let next = match self.stack.last_mut() {
+                None => return Ok(false),
+                Some(Frame::Chunks(iter, idx, count, chunks)) => match iter.next() {
+                    Some(chunk) => {
+                        write_binary_chunk_header(&mut self.head, *idx, *count, chunk.len(), *chunks, &self.config)?;
+                        *idx += 1;
+                        self.data = chunk;
+                        return Ok(true);
+                    }
+                    None => None,
+                },
+                Some(Frame::Array(iter)) => iter.next(),
+                Some(Frame::Map(iter, pending)) => match pending.take() {
+                    Some(val) => Some(val),
+                    None => iter.next().map(|(key, val)| {
+                        *pending = Some(val);
+                        key
+                    }),
+                },
+            };
+
+            match next {
+                Some(val) => {
+                    self.start(val)?;
+                    return Ok(true);
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+
+    /// Encodes the header of the given value, leaving its payload or elements for later.
+    fn start(&mut self, val: &'a Value) -> Result<(), Error> {
+        let wr = &mut self.head;
+
+        match *val {
+            Value::String(ref s) => match s.as_result() {
+                Ok(s) => {
+                    write_str_len(wr, s.len() as u32)?;
+                    self.data = s.as_bytes();
+                }
+                Err(buf) => {
+                    write_bin_len(wr, buf.len() as u32)?;
+                    self.data = buf;
+                }
+            },
+            Value::Binary(ref buf) => match self.config.binary_chunks {
+                Some(chunks) if buf.len() > chunks.max_len => {
+                    // The chunks are encoded one by one, each copied from the value like a binary.
+                    let (max_len, count) = binary_chunks_layout(buf.len(), chunks);
+                    write_array_len(wr, count as u32)?;
+                    self.stack.push(Frame::Chunks(buf.chunks(max_len), 0, count, chunks));
+                }
+                _ => {
+                    write_bin_len(wr, buf.len() as u32)?;
+                    self.data = buf;
+                }
+            },
+            Value::Ext(ty, ref buf) => {
+                write_ext_header(wr, buf.len() as u32, ty, &self.config)?;
+                self.data = buf;
+            }
+            Value::Raw(ref buf) => {
+                self.data = buf;
+            }
+            Value::Array(ref vec) => {
+                write_array_len(wr, vec.len() as u32)?;
+                self.stack.push(Frame::Array(vec.iter()));
+            }
+            Value::Map(ref map) => {
+                write_map_len(wr, map.len() as u32)?;
+                self.stack.push(Frame::Map(sorted_entries(map, &self.config)?, None));
+            }
+            Value::Nil |
+            Value::BinaryRef { .. } |
+            Value::Boolean(..) |
+            Value::Integer(..) |
+            Value::F32(..) |
+            Value::F64(..) => {
+                write_value_with_config(wr, val, &self.config)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+use std::hash::Hasher;
+use std::io::{self, Write};
+
+/// A writer that feeds every byte written through it into a [`Hasher`], before passing them on.
+///
+/// Writing a value through it with [`write_value`](super::write_value) computes the hash of its
+/// encoding as it is written, without another pass over the output. With keys sorted by
+/// [`SortMode::Canonical`](super::SortMode::Canonical), equal values give equal hashes, which can
+/// serve as content addresses.
+///
+/// Only the bytes the inner writer accepts are hashed, so the hash matches its output even when
+/// writing fails halfway.
+///
+/// The encoding reaches the hasher in as many [`Hasher::write`] calls as the encoder makes, and
+/// `Hasher` doesn't guarantee that `write(a); write(b)` gives the same hash as `write(ab)`, so the
+/// hash may differ from the one of the whole output hashed at once. Neither is the output of
+/// [`DefaultHasher`](std::collections::hash_map::DefaultHasher) stable across Rust releases. For
+/// content addresses that outlive the process, use a `Hasher` adapter over a cryptographic digest
+/// with a streaming API, whose result only depends on the bytes fed into it.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::hash_map::DefaultHasher;
+/// use std::hash::Hasher;
+///
+/// use rmpv::encode::{write_value, HashingWriter};
+/// use rmpv::Value;
+///
+/// fn hash(val: &Value) -> u64 {
+///     let mut wr = HashingWriter::new(Vec::new(), DefaultHasher::new());
+///     write_value(&mut wr, val).unwrap();
+///     wr.hasher().finish()
+/// }
+///
+/// assert_eq!(hash(&Value::from("le message")), hash(&Value::from("le message")));
+/// assert_ne!(hash(&Value::from("le message")), hash(&Value::from("la message")));
+/// ```
+#[derive(Clone, Debug)]
+pub struct HashingWriter<W, H> {
+    wr: W,
+    hasher: H,
+}
+
+impl<W: Write, H: Hasher> HashingWriter<W, H> {
+    /// Wraps the given writer, hashing the bytes written into `hasher`.
+    #[inline]
+    pub fn new(wr: W, hasher: H) -> Self {
+        Self { wr, hasher }
+    }
+
+    /// Returns the hasher, fed with all the bytes written so far.
+    #[inline]
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// Returns a reference to the inner writer.
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.wr
+    }
+
+    /// Returns the inner writer and the hasher.
+    #[inline]
+    pub fn into_inner(self) -> (W, H) {
+        (self.wr, self.hasher)
+    }
+}
+
+impl<W: Write, H: Hasher> Write for HashingWriter<W, H> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = self.wr.write(buf)?;
+        self.hasher.write(&buf[..len]);
+        Ok(len)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.wr.flush()
+    }
+}
@@ -1,7 +1,148 @@
+use std::error;
+use std::fmt::{self, Display, Formatter};
+
 pub use rmp::encode::ValueWriteError as Error;
 
+mod chunked;
+mod hashing;
 mod value;
 mod value_ref;
 
-pub use self::value::write_value;
+pub use self::chunked::ChunkedEncoder;
+pub use self::hashing::HashingWriter;
+pub use self::value::{write_btreemap, write_value, write_value_padded, write_value_with_config};
 pub use self::value_ref::write_value_ref;
+
+/// Options applied while encoding a [`Value`](crate::Value).
+///
+/// Construct it by overriding the fields of interest and taking the rest from
+/// [`EncodeConfig::default()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodeConfig {
+    /// Whether [`Value::F64`](crate::Value::F64) values that are exactly representable as `f32`
+    /// are written with the 4-byte F32 marker, saving 4 bytes each.
+    ///
+    /// A value is only shrunk if it survives the round trip through `f32` unchanged, so decoding
+    /// yields the same number, albeit as [`Value::F32`](crate::Value::F32).
+    pub shrink_floats: bool,
+    /// How NaN and infinite floats are written.
+    pub non_finite_floats: NonFinitePolicy,
+    /// Whether `-0.0` is written as `0.0`, for applications that treat them as equal and want
+    /// equal values to have the same encoding.
+    ///
+    /// Floats are otherwise written with their exact IEEE 754 bits, so the two zeros, which
+    /// compare equal, encode differently. Enabling this changes the bit pattern of negative zeros,
+    /// which then decode as positive ones.
+    pub normalize_negative_zero: bool,
+    /// Whether extensions whose payload is 1, 2, 4, 8 or 16 bytes long are written with the
+    /// matching FixExt marker, as the spec recommends, rather than with Ext8.
+    ///
+    /// Both forms decode to the same value. Disabling this mimics the encoders that never use the
+    /// fixext forms, for testing decoders against them.
+    pub prefer_fixext: bool,
+    /// The order map entries are written in.
+    pub sort_keys: SortMode,
+    /// If set, [`Value::Binary`](crate::Value::Binary) values longer than its
+    /// [`max_len`](BinaryChunks::max_len) are split into an array of ext chunks, for transports
+    /// that cap the size of individual fields.
+    pub binary_chunks: Option<BinaryChunks>,
+}
+
+impl Default for EncodeConfig {
+    fn default() -> Self {
+        Self {
+            shrink_floats: false,
+            non_finite_floats: NonFinitePolicy::AsIs,
+            normalize_negative_zero: false,
+            prefer_fixext: true,
+            sort_keys: SortMode::None,
+            binary_chunks: None,
+        }
+    }
+}
+
+/// How large binaries are split into chunks, see [`EncodeConfig::binary_chunks`].
+///
+/// A binary longer than `max_len` bytes is written as an array of exts of type `ext_type`, one per
+/// chunk of at most `max_len` bytes, in order. The payload of each ext is the index of the chunk,
+/// counted from 0, as a big-endian `u32`, then a flags byte, whose lowest bit is set on the final
+/// chunk only, then the chunk's bytes. So every ext is at most `max_len + 5` bytes long.
+///
+/// Decoding with [`DecodeConfig::binary_chunks`](crate::decode::DecodeConfig::binary_chunks) set to
+/// the same ext type reassembles such arrays into the original binary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BinaryChunks {
+    /// The ext type of the chunks. Values written with this option shouldn't contain arrays of
+    /// exts of this type themselves, which would be reassembled too.
+    pub ext_type: i8,
+    /// The maximum number of bytes of a binary written as is, and of each chunk. Zero is treated
+    /// as one.
+    pub max_len: usize,
+}
+
+/// The order of the entries of every map written, see [`EncodeConfig::sort_keys`].
+///
+/// Sorting makes the output of equal maps reproducible, whatever the order their entries were
+/// inserted in. Entries with equal keys keep their relative order.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortMode {
+    /// Write the entries in their current order.
+    #[default]
+    None,
+    /// Sort the entries by the encoded bytes of their keys, as canonical forms of MessagePack
+    /// require, see [`Value::cmp_encoded`](crate::Value::cmp_encoded).
+    Canonical,
+    /// Sort the entries by the meaning of their keys, for example integers numerically, for
+    /// output meant for people, see [`Value::cmp_semantic`](crate::Value::cmp_semantic).
+    Semantic,
+}
+
+/// What to do with NaN and infinite floats when encoding, see
+/// [`EncodeConfig::non_finite_floats`].
+///
+/// Formats like JSON can't represent them, which matters when MessagePack is used as a staging
+/// format.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NonFinitePolicy {
+    /// Write them as is, keeping their IEEE 754 bits.
+    #[default]
+    AsIs,
+    /// Write nil instead.
+    AsNull,
+    /// Fail with [`Error::InvalidDataWrite`], whose I/O error has the `InvalidInput` kind and
+    /// wraps a [`NonFiniteFloat`].
+    Reject,
+}
+
+/// The error wrapped in the I/O error returned when writing a non-finite float with
+/// [`NonFinitePolicy::Reject`]. Contains the offending value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NonFiniteFloat(pub f64);
+
+impl Display for NonFiniteFloat {
+    #[cold]
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(fmt, "non-finite float {} is not allowed", self.0)
+    }
+}
+
+impl error::Error for NonFiniteFloat {}
+
+/// The error wrapped in the I/O error returned by [`write_value_padded`] when the value doesn't
+/// fit in the frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValueTooLarge {
+    /// The length of the encoded value.
+    pub len: usize,
+    /// The size of the frame.
+    pub frame_size: usize,
+}
+
+impl Display for ValueTooLarge {
+    #[cold]
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(fmt, "encoded value of {} bytes exceeds the frame of {} bytes", self.len, self.frame_size)
+    }
+}
+
+impl error::Error for ValueTooLarge {}
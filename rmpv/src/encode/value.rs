@@ -1,12 +1,16 @@
-use std::io::Write;
+use std::cmp;
+use std::collections::BTreeMap;
+use std::io::{self, ErrorKind, Write};
+use std::{slice, vec};
 
 use rmp::encode::{
     write_array_len, write_bin, write_bool, write_ext_meta, write_f32, write_f64, write_map_len,
     write_nil, write_sint, write_str, write_uint,
 };
+use rmp::Marker;
 
-use super::Error;
-use crate::{IntPriv, Integer, Utf8String, Value};
+use super::{BinaryChunks, EncodeConfig, Error, NonFiniteFloat, NonFinitePolicy, SortMode, ValueTooLarge};
+use crate::{IntPriv, Integer, Value};
 
 /// Encodes and attempts to write the most efficient representation of the given Value.
 ///
@@ -16,6 +20,74 @@ use crate::{IntPriv, Integer, Utf8String, Value};
 /// operation is retried.
 pub fn write_value<W>(wr: &mut W, val: &Value) -> Result<(), Error>
     where W: Write
+{
+    write_value_with_config(wr, val, &EncodeConfig::default())
+}
+
+/// Applies [`EncodeConfig::non_finite_floats`], returning whether the float has been handled.
+fn write_non_finite<W: Write>(wr: &mut W, val: f64, config: &EncodeConfig) -> Result<bool, Error> {
+    match config.non_finite_floats {
+        NonFinitePolicy::AsIs => Ok(false),
+        NonFinitePolicy::AsNull => {
+            write_nil(wr).map_err(Error::InvalidMarkerWrite)?;
+            Ok(true)
+        }
+        NonFinitePolicy::Reject => {
+            Err(Error::InvalidDataWrite(io::Error::new(ErrorKind::InvalidInput, NonFiniteFloat(val))))
+        }
+    }
+}
+
+/// Writes the header of an extension, applying [`EncodeConfig::prefer_fixext`].
+pub(super) fn write_ext_header<W: Write>(wr: &mut W, len: u32, ty: i8, config: &EncodeConfig) -> Result<(), Error> {
+    if config.prefer_fixext || !matches!(len, 1 | 2 | 4 | 8 | 16) {
+        write_ext_meta(wr, len, ty)?;
+        return Ok(());
+    }
+
+    // The lengths of the fixext forms all fit in an Ext8 header.
+    wr.write_all(&[Marker::Ext8.to_u8(), len as u8, ty as u8]).map_err(Error::InvalidMarkerWrite)
+}
+
+/// Returns the length of the chunks of a binary written with [`BinaryChunks`], all but the last
+/// one, and their number.
+pub(super) fn binary_chunks_layout(len: usize, chunks: BinaryChunks) -> (usize, usize) {
+    let max_len = cmp::max(chunks.max_len, 1);
+    (max_len, (len + max_len - 1) / max_len)
+}
+
+/// Writes the ext header of the chunk at `idx` out of `count`, followed by its index and flags,
+/// leaving its `len` bytes of data to the caller, see [`BinaryChunks`].
+pub(super) fn write_binary_chunk_header<W: Write>(wr: &mut W, idx: usize, count: usize, len: usize, chunks: BinaryChunks,
+                                                  config: &EncodeConfig) -> Result<(), Error> {
+    write_ext_header(wr, len as u32 + 5, chunks.ext_type, config)?;
+    let mut header = [0; 5];
+    header[..4].copy_from_slice(&(idx as u32).to_be_bytes());
+    header[4] = u8::from(idx + 1 == count);
+    wr.write_all(&header).map_err(Error::InvalidDataWrite)
+}
+
+/// Writes a binary as an array of ext chunks, see [`BinaryChunks`].
+fn write_binary_chunks<W: Write>(wr: &mut W, buf: &[u8], chunks: BinaryChunks, config: &EncodeConfig) -> Result<(), Error> {
+    let (max_len, count) = binary_chunks_layout(buf.len(), chunks);
+    write_array_len(wr, count as u32)?;
+
+    for (idx, chunk) in buf.chunks(max_len).enumerate() {
+        write_binary_chunk_header(wr, idx, count, chunk.len(), chunks, config)?;
+        wr.write_all(chunk).map_err(Error::InvalidDataWrite)?;
+    }
+
+    Ok(())
+}
+
+/// Encodes and attempts to write the given Value, applying the options from `config`.
+///
+/// # Note
+///
+/// All instances of `ErrorKind::Interrupted` are handled by this function and the underlying
+/// operation is retried.
+pub fn write_value_with_config<W>(wr: &mut W, val: &Value, config: &EncodeConfig) -> Result<(), Error>
+    where W: Write
 {
     match *val {
         Value::Nil => {
@@ -34,39 +106,183 @@ pub fn write_value<W>(wr: &mut W, val: &Value) -> Result<(), Error>
                 }
             }
         }
+        Value::F32(val) if !val.is_finite() && write_non_finite(wr, val.into(), config)? => {}
+        Value::F64(val) if !val.is_finite() && write_non_finite(wr, val, config)? => {}
         Value::F32(val) => {
+            let val = if config.normalize_negative_zero && val == 0.0 { 0.0 } else { val };
             write_f32(wr, val)?;
         }
         Value::F64(val) => {
-            write_f64(wr, val)?;
-        }
-        Value::String(Utf8String { ref s }) => {
-            match *s {
-                Ok(ref val) => write_str(wr, val)?,
-                Err(ref err) => write_bin(wr, &err.0)?,
+            let val = if config.normalize_negative_zero && val == 0.0 { 0.0 } else { val };
+            if config.shrink_floats && f64::from(val as f32).to_bits() == val.to_bits() {
+                write_f32(wr, val as f32)?;
+            } else {
+                write_f64(wr, val)?;
             }
         }
-        Value::Binary(ref val) => {
-            write_bin(wr, val)?;
+        Value::String(ref s) => {
+            match s.as_result() {
+                Ok(val) => write_str(wr, val)?,
+                Err(buf) => write_bin(wr, buf)?,
+            }
         }
+        Value::Binary(ref val) => match config.binary_chunks {
+            Some(chunks) if val.len() > chunks.max_len => write_binary_chunks(wr, val, chunks, config)?,
+            _ => write_bin(wr, val)?,
+        },
         Value::Array(ref vec) => {
             write_array_len(wr, vec.len() as u32)?;
             for v in vec {
-                write_value(wr, v)?;
+                write_value_with_config(wr, v, config)?;
             }
         }
         Value::Map(ref map) => {
             write_map_len(wr, map.len() as u32)?;
-            for (key, val) in map {
-                write_value(wr, key)?;
-                write_value(wr, val)?;
+            for (key, val) in sorted_entries(map, config)? {
+                write_value_with_config(wr, key, config)?;
+                write_value_with_config(wr, val, config)?;
             }
         }
         Value::Ext(ty, ref data) => {
-            write_ext_meta(wr, data.len() as u32, ty)?;
+            write_ext_header(wr, data.len() as u32, ty, config)?;
             wr.write_all(data).map_err(Error::InvalidDataWrite)?;
         }
+        Value::Raw(ref buf) => {
+            wr.write_all(buf).map_err(Error::InvalidDataWrite)?;
+        }
+        Value::BinaryRef { .. } => {
+            return Err(Error::InvalidDataWrite(io::Error::new(
+                ErrorKind::InvalidInput,
+                "binary reference can't be encoded without its data",
+            )));
+        }
     }
 
     Ok(())
 }
+
+/// The entries of a map in the order set by [`EncodeConfig::sort_keys`], see [`sorted_entries`].
+pub(super) enum SortedEntries<'a> {
+    /// The entries in their current order, which needn't be collected.
+    InOrder(slice::Iter<'a, (Value, Value)>),
+    /// The entries collected and sorted.
+    Sorted(vec::IntoIter<&'a (Value, Value)>),
+}
+
+impl<'a> Iterator for SortedEntries<'a> {
+    type Item = &'a (Value, Value);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            SortedEntries::InOrder(ref mut iter) => iter.next(),
+            SortedEntries::Sorted(ref mut iter) => iter.next(),
+        }
+    }
+}
+
+/// Returns the entries of the given map in the order set by [`EncodeConfig::sort_keys`].
+///
+/// They are only collected when sorted, so that maps written in their current order don't
+/// allocate.
+pub(super) fn sorted_entries<'a>(map: &'a [(Value, Value)], config: &EncodeConfig) -> Result<SortedEntries<'a>, Error> {
+    let entries = match config.sort_keys {
+        SortMode::None => return Ok(SortedEntries::InOrder(map.iter())),
+        SortMode::Canonical => {
+            let mut keyed = Vec::with_capacity(map.len());
+            for entry in map {
+                let mut buf = Vec::new();
+                write_value_with_config(&mut buf, &entry.0, config)?;
+                keyed.push((buf, entry));
+            }
+            keyed.sort_by(|(lhs, _), (rhs, _)| lhs.cmp(rhs));
+            keyed.into_iter().map(|(_, entry)| entry).collect()
+        }
+        SortMode::Semantic => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(lhs, _), (rhs, _)| lhs.cmp_semantic(rhs));
+            entries
+        }
+    };
+
+    Ok(SortedEntries::Sorted(entries.into_iter()))
+}
+
+/// Encodes and attempts to write the given map of string keys, with its entries in the sorted order
+/// of the `BTreeMap`.
+///
+/// Keys are thus written sorted by their bytes, which isn't the order of their encodings that
+/// [`SortMode::Canonical`] gives: the length prefix of a key comes first in its encoding, so `"b"`
+/// (`a1 62`) sorts before `"aa"` (`a2 61 61`) there, but after it here. Decoding the output yields
+/// a [`Value::Map`] with the entries in the order of the `BTreeMap`.
+///
+/// # Errors
+///
+/// Returns the same errors as [`write_value`].
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// use rmpv::encode::write_btreemap;
+/// use rmpv::Value;
+///
+/// let mut map = BTreeMap::new();
+/// map.insert("b".to_string(), Value::from(2));
+/// map.insert("a".to_string(), Value::from(1));
+///
+/// let mut buf = Vec::new();
+/// write_btreemap(&mut buf, &map).unwrap();
+///
+/// assert_eq!(vec![0x82, 0xa1, 0x61, 0x01, 0xa1, 0x62, 0x02], buf);
+/// ```
+pub fn write_btreemap<W>(wr: &mut W, map: &BTreeMap<String, Value>) -> Result<(), Error>
+    where W: Write
+{
+    write_map_len(wr, map.len() as u32)?;
+    for (key, val) in map {
+        write_str(wr, key)?;
+        write_value(wr, val)?;
+    }
+
+    Ok(())
+}
+
+/// Encodes and attempts to write the given Value followed by nil bytes (`0xc0`), so that exactly
+/// `frame_size` bytes are written, as fixed-size frame protocols expect.
+///
+/// The value is encoded first, so nothing is written if it doesn't fit. Decoding the frame with
+/// [`read_value`](crate::decode::read_value) yields the value and leaves the padding unread, which
+/// the receiver is expected to ignore.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidDataWrite`] with the `InvalidInput` kind, wrapping a [`ValueTooLarge`],
+/// if the encoded value is longer than `frame_size`, and any error of [`write_value`] otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use rmpv::encode::write_value_padded;
+/// use rmpv::Value;
+///
+/// let mut buf = Vec::new();
+/// write_value_padded(&mut buf, &Value::from("ok"), 8).unwrap();
+///
+/// assert_eq!(vec![0xa2, 0x6f, 0x6b, 0xc0, 0xc0, 0xc0, 0xc0, 0xc0], buf);
+/// ```
+pub fn write_value_padded<W>(wr: &mut W, val: &Value, frame_size: usize) -> Result<(), Error>
+    where W: Write
+{
+    let mut buf = Vec::new();
+    write_value(&mut buf, val)?;
+
+    if buf.len() > frame_size {
+        let err = ValueTooLarge { len: buf.len(), frame_size };
+        return Err(Error::InvalidDataWrite(io::Error::new(ErrorKind::InvalidInput, err)));
+    }
+    buf.resize(frame_size, Marker::Null.to_u8());
+
+    wr.write_all(&buf).map_err(Error::InvalidDataWrite)
+}
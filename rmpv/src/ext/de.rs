@@ -312,6 +312,8 @@ impl<'de> Deserializer<'de> for Value {
         where V: Visitor<'de>
     {
         match self {
+            Value::Raw(buf) => crate::raw_value(&buf).deserialize_any(visitor),
+            Value::BinaryRef { .. } => Err(de::Error::invalid_type(Unexpected::Other("binary reference"), &visitor)),
             Value::Nil => visitor.visit_unit(),
             Value::Boolean(v) => visitor.visit_bool(v),
             Value::Integer(Integer { n }) => {
@@ -323,7 +325,7 @@ impl<'de> Deserializer<'de> for Value {
             Value::F32(v) => visitor.visit_f32(v),
             Value::F64(v) => visitor.visit_f64(v),
             Value::String(v) => {
-                match v.s {
+                match v.into_result() {
                     Ok(v) => visitor.visit_string(v),
                     Err(v) => visitor.visit_byte_buf(v.0),
                 }
@@ -46,15 +46,17 @@ impl ValueExt for Value {
             Value::F32(v) => Unexpected::Float(v as f64),
             Value::F64(v) => Unexpected::Float(v),
             Value::String(ref v) => {
-                match v.s {
-                    Ok(ref v) => Unexpected::Str(v),
-                    Err(ref v) => Unexpected::Bytes(&v.0[..]),
+                match v.as_result() {
+                    Ok(v) => Unexpected::Str(v),
+                    Err(v) => Unexpected::Bytes(v),
                 }
             }
             Value::Binary(ref v) => Unexpected::Bytes(v),
             Value::Array(..) => Unexpected::Seq,
             Value::Map(..) => Unexpected::Map,
             Value::Ext(..) => Unexpected::Seq,
+            Value::Raw(..) => Unexpected::Other("raw value"),
+            Value::BinaryRef { .. } => Unexpected::Other("binary reference"),
         }
     }
 }
@@ -27,12 +27,14 @@ impl Serialize for Value {
             Value::F32(v) => s.serialize_f32(v),
             Value::F64(v) => s.serialize_f64(v),
             Value::String(ref v) => {
-                match v.s {
-                    Ok(ref v) => s.serialize_str(v),
-                    Err(ref v) => Bytes::new(&v.0[..]).serialize(s),
+                match v.as_result() {
+                    Ok(v) => s.serialize_str(v),
+                    Err(v) => Bytes::new(v).serialize(s),
                 }
             }
             Value::Binary(ref v) => Bytes::new(&v[..]).serialize(s),
+            Value::Raw(ref buf) => crate::raw_value(buf).serialize(s),
+            Value::BinaryRef { .. } => Err(ser::Error::custom("binary reference can't be serialized without its data")),
             Value::Array(ref array) => {
                 let mut state = s.serialize_seq(Some(array.len()))?;
                 for item in array {
@@ -0,0 +1,140 @@
+//! A self-describing header, written before a value so that consumers can reject messages from
+//! incompatible producers before decoding them.
+//!
+//! The header is a fixext8 of an application-chosen type, whose payload is the
+//! [`MSGPACK_VERSION`] of the producer followed by its application schema version, both as
+//! big-endian `u32`s:
+//!
+//! ```text
+//! +------+------+------------------+------------------+
+//! | 0xd7 | type | MSGPACK_VERSION  | schema version   |
+//! +------+------+------------------+------------------+
+//!   1 B    1 B         4 B                4 B
+//! ```
+//!
+//! A header is compatible if neither version is newer than the consumer's: consumers are expected
+//! to understand the messages of older producers, but not those of newer ones.
+//!
+//! # Examples
+//!
+//! ```
+//! use rmpv::decode::read_value;
+//! use rmpv::header::{read_header, write_value_with_header, Header};
+//! use rmpv::Value;
+//!
+//! let header = Header { ext_type: 42, schema_version: 3 };
+//!
+//! let mut buf = Vec::new();
+//! write_value_with_header(&mut buf, &Value::from("body"), header).unwrap();
+//!
+//! let mut rd = &buf[..];
+//! assert_eq!(3, read_header(&mut rd, header).unwrap());
+//! assert_eq!(Value::from("body"), read_value(&mut rd).unwrap());
+//! ```
+
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, ErrorKind, Read, Write};
+
+use rmp::decode::{read_ext_meta, ValueReadError};
+use rmp::encode::write_ext_meta;
+use rmp::MSGPACK_VERSION;
+
+use crate::decode::Error as DecodeError;
+use crate::encode::{write_value, Error as EncodeError};
+use crate::Value;
+
+const PAYLOAD_LEN: u32 = 8;
+
+/// The ext type and schema version of a header.
+///
+/// Producers write their own, and consumers pass theirs to [`read_header`] as the newest versions
+/// they understand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    /// The ext type of the header. It should differ from the types of the exts of the body, so
+    /// that a message without a header can't be mistaken for one with it.
+    pub ext_type: i8,
+    /// The version of the application schema the body follows.
+    pub schema_version: u32,
+}
+
+/// The error wrapped in the I/O error returned by [`read_header`] when the header has been written
+/// by a newer producer. Contains the versions found in the header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IncompatibleHeader {
+    /// The MessagePack version of the producer.
+    pub msgpack_version: u32,
+    /// The schema version of the producer.
+    pub schema_version: u32,
+}
+
+impl Display for IncompatibleHeader {
+    #[cold]
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(fmt, "incompatible header with MessagePack version {} and schema version {}",
+               self.msgpack_version, self.schema_version)
+    }
+}
+
+impl error::Error for IncompatibleHeader {}
+
+/// Encodes and attempts to write a header with the current [`MSGPACK_VERSION`] and the given
+/// schema version.
+///
+/// # Errors
+///
+/// This function will return [`Error`](crate::encode::Error) on any I/O error while writing.
+pub fn write_header<W: Write>(wr: &mut W, header: Header) -> Result<(), EncodeError> {
+    write_ext_meta(wr, PAYLOAD_LEN, header.ext_type)?;
+
+    let mut buf = [0; PAYLOAD_LEN as usize];
+    buf[..4].copy_from_slice(&MSGPACK_VERSION.to_be_bytes());
+    buf[4..].copy_from_slice(&header.schema_version.to_be_bytes());
+    wr.write_all(&buf).map_err(EncodeError::InvalidDataWrite)
+}
+
+/// Encodes and attempts to write the given value, preceded by a header.
+///
+/// # Errors
+///
+/// This function will return [`Error`](crate::encode::Error) on any I/O error while writing.
+pub fn write_value_with_header<W: Write>(wr: &mut W, val: &Value, header: Header) -> Result<(), EncodeError> {
+    write_header(wr, header)?;
+    write_value(wr, val)
+}
+
+/// Attempts to read a header and to verify that it is compatible with `expected`, returning the
+/// schema version of the producer.
+///
+/// On success the reader is left at the start of the body.
+///
+/// # Errors
+///
+/// This function will return [`Error`](crate::decode::Error) on any I/O error while reading,
+/// [`InvalidMarkerRead`](crate::decode::Error::InvalidMarkerRead) if the value isn't an ext of type
+/// `expected.ext_type`, and [`InvalidDataRead`](crate::decode::Error::InvalidDataRead) with the
+/// `InvalidData` kind if its payload isn't 8 bytes long, or wrapping an [`IncompatibleHeader`] if
+/// either its MessagePack version is newer than [`MSGPACK_VERSION`] or its schema version is newer
+/// than `expected.schema_version`.
+pub fn read_header<R: Read>(rd: &mut R, expected: Header) -> Result<u32, DecodeError> {
+    let meta = read_ext_meta(rd)?;
+    if meta.typeid != expected.ext_type {
+        return Err(ValueReadError::UnexpectedExtType { expected: expected.ext_type, found: meta.typeid }.into());
+    }
+    if meta.size != PAYLOAD_LEN {
+        return Err(DecodeError::InvalidDataRead(io::Error::new(ErrorKind::InvalidData, "header of invalid length")));
+    }
+
+    let mut buf = [0; PAYLOAD_LEN as usize];
+    rd.read_exact(&mut buf).map_err(DecodeError::InvalidDataRead)?;
+    let msgpack_version = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]);
+    let schema_version = u32::from_be_bytes([buf[4], buf[5], buf[6], buf[7]]);
+
+    if msgpack_version > MSGPACK_VERSION || schema_version > expected.schema_version {
+        let err = IncompatibleHeader { msgpack_version, schema_version };
+        return Err(DecodeError::InvalidDataRead(io::Error::new(ErrorKind::InvalidData, err)));
+    }
+
+    Ok(schema_version)
+}
@@ -0,0 +1,165 @@
+//! Conversions between [`Value`] and [`serde_json::Value`].
+//!
+//! JSON has no notion of raw bytes, extension types or non-string map keys, so converting a
+//! `Value` into JSON is lossy:
+//!
+//! - `Binary` values and strings with invalid UTF-8 become standard (padded) base64 strings.
+//! - `Ext(ty, data)` becomes a 2-element array `[ty, "<base64 data>"]`.
+//! - Map keys that are not strings are replaced with their `Display` representation.
+//! - Non-finite floats (NaN and infinities) become `null`.
+//! - A `BinaryRef { offset, len }`, which has no data to convert, becomes an object
+//!   `{"offset": offset, "len": len}` when it's a value.
+//!
+//! [`Value::try_into_json`] converts the same way, but returns `None` for values containing a
+//! binary reference instead, for data decoded with
+//! [`read_value_with_binary_refs`](crate::decode::read_value_with_binary_refs). It is a method
+//! rather than a `TryFrom` implementation, which would conflict with the infallible `From` one.
+//!
+//! Converting JSON into a `Value` is lossless, with integers mapped to `Integer` and all other
+//! numbers mapped to `F64`.
+//!
+//! This module is behind the `with-json` feature, named after `with-serde`. Enabling it pulls in
+//! `serde_json`, whose `PartialEq<serde_json::Value>` impls for the primitive types break type
+//! inference wherever a primitive is compared with a value of an inferred type, such as
+//! `assert_eq!(0, from_value(val).unwrap())`. As features are unified across the build, this
+//! affects every crate depending on `rmpv` with it enabled, which then has to name the type, as in
+//! `from_value::<i32>(val)`.
+
+use std::convert::Infallible;
+
+use serde_json::{Map, Number};
+
+use crate::{Utf8String, Value};
+
+const BASE64_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() + 2) / 3 * 4);
+
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for idx in 0..4 {
+            if idx <= chunk.len() {
+                out.push(BASE64_ALPHABET[(n >> (18 - 6 * idx) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+
+    out
+}
+
+fn float_to_json(val: f64) -> serde_json::Value {
+    Number::from_f64(val).map_or(serde_json::Value::Null, serde_json::Value::Number)
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(val: serde_json::Value) -> Self {
+        match val {
+            serde_json::Value::Null => Value::Nil,
+            serde_json::Value::Bool(val) => Value::Boolean(val),
+            serde_json::Value::Number(n) => {
+                if let Some(n) = n.as_u64() {
+                    Value::from(n)
+                } else if let Some(n) = n.as_i64() {
+                    Value::from(n)
+                } else {
+                    Value::F64(n.as_f64().unwrap_or(f64::NAN))
+                }
+            }
+            serde_json::Value::String(s) => Value::String(Utf8String::from(s)),
+            serde_json::Value::Array(vec) => Value::Array(vec.into_iter().map(Value::from).collect()),
+            serde_json::Value::Object(map) => {
+                Value::Map(map.into_iter().map(|(k, v)| (Value::from(k), Value::from(v))).collect())
+            }
+        }
+    }
+}
+
+impl From<Value> for serde_json::Value {
+    fn from(val: Value) -> Self {
+        let binary_ref = |offset, len| {
+            let mut map = Map::new();
+            map.insert("offset".into(), serde_json::Value::from(offset));
+            map.insert("len".into(), serde_json::Value::from(len));
+            Ok(serde_json::Value::Object(map))
+        };
+        match to_json::<Infallible, _>(val, &binary_ref) {
+            Ok(val) => val,
+            Err(never) => match never {},
+        }
+    }
+}
+
+impl Value {
+    /// Converts the value into JSON as its `From` implementation does, or returns `None` if it is
+    /// or contains a [`Value::BinaryRef`], whose data isn't there to convert.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmpv::Value;
+    ///
+    /// assert_eq!(Some(serde_json::Value::from("a")), Value::from("a").try_into_json());
+    /// assert_eq!(None, Value::Array(vec![Value::BinaryRef { offset: 0, len: 4 }]).try_into_json());
+    /// ```
+    pub fn try_into_json(self) -> Option<serde_json::Value> {
+        to_json(self, &|_, _| Err(())).ok()
+    }
+}
+
+/// Converts a value into JSON, with binary references converted by `binary_ref`.
+fn to_json<E, F>(val: Value, binary_ref: &F) -> Result<serde_json::Value, E>
+    where F: Fn(u64, u32) -> Result<serde_json::Value, E>
+{
+    let val = match val {
+        Value::Nil => serde_json::Value::Null,
+        Value::Boolean(val) => serde_json::Value::Bool(val),
+        Value::Integer(n) => {
+            if let Some(n) = n.as_u64() {
+                serde_json::Value::from(n)
+            } else {
+                serde_json::Value::from(n.as_i64().unwrap_or_default())
+            }
+        }
+        Value::F32(val) => float_to_json(val as f64),
+        Value::F64(val) => float_to_json(val),
+        Value::String(s) => match s.into_result() {
+            Ok(s) => serde_json::Value::String(s),
+            Err((buf, _)) => serde_json::Value::String(base64_encode(&buf)),
+        },
+        Value::Binary(data) => serde_json::Value::String(base64_encode(&data)),
+        Value::Raw(buf) => to_json(crate::raw_value(&buf), binary_ref)?,
+        Value::BinaryRef { offset, len } => binary_ref(offset, len)?,
+        Value::Array(vec) => {
+            serde_json::Value::Array(vec.into_iter().map(|v| to_json(v, binary_ref)).collect::<Result<_, E>>()?)
+        }
+        Value::Map(vec) => {
+            let map = vec.into_iter()
+                .map(|(k, v)| {
+                    let key = match k.into_string() {
+                        Ok(key) => key,
+                        Err(Value::BinaryRef { offset, len }) => {
+                            binary_ref(offset, len)?;
+                            Value::BinaryRef { offset, len }.to_string()
+                        }
+                        Err(k) => k.to_string(),
+                    };
+                    Ok((key, to_json(v, binary_ref)?))
+                })
+                .collect::<Result<Map<String, serde_json::Value>, E>>()?;
+
+            serde_json::Value::Object(map)
+        }
+        Value::Ext(ty, data) => {
+            serde_json::Value::Array(vec![
+                serde_json::Value::from(ty),
+                serde_json::Value::String(base64_encode(&data)),
+            ])
+        }
+    };
+    Ok(val)
+}
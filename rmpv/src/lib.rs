@@ -4,27 +4,43 @@
 //!
 //! ```
 //! ```
-#![forbid(unsafe_code)]
+// Mapping a file is the only unsafe operation, confined to `decode::mmap`.
+#![cfg_attr(not(feature = "memmap"), forbid(unsafe_code))]
+#![cfg_attr(feature = "memmap", deny(unsafe_code))]
 
 #[cfg(feature = "with-serde")]
 #[macro_use]
 extern crate serde;
 
 use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::convert::TryFrom;
 use std::fmt::{self, Debug, Display};
 use std::iter::FromIterator;
+use std::mem;
 use std::ops::Index;
-use std::str::Utf8Error;
+use std::str::{self, Utf8Error};
+use std::sync::{Arc, OnceLock};
 
 use num_traits::NumCast;
 
+pub mod builder;
 pub mod decode;
 pub mod encode;
+pub mod header;
+pub mod path;
+pub mod schema;
+pub mod small;
+pub mod string_table;
+pub mod text;
+pub mod timestamp;
 
 #[cfg(feature = "with-serde")]
 pub mod ext;
 
+#[cfg(feature = "with-json")]
+mod json;
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 enum IntPriv {
     /// Always non-less than zero.
@@ -220,31 +236,100 @@ impl From<isize> for Integer {
 /// it is possible to obtain an underlying bytes that were attempted to convert to a `String`. This
 /// may happen when trying to unpack strings that were decoded using older MessagePack spec with
 /// raw types instead of string/binary.
-#[derive(Clone, Debug, PartialEq)]
+///
+/// When decoding with [`DecodeConfig::defer_utf8_validation`](decode::DecodeConfig), the bytes are
+/// only validated when the string is accessed, see [`Utf8String::as_str`].
+#[derive(Clone, Debug)]
 pub struct Utf8String {
-    s: Result<String, (Vec<u8>, Utf8Error)>,
+    s: Utf8Repr,
+}
+
+#[derive(Clone, Debug)]
+enum Utf8Repr {
+    /// Validated on construction.
+    Checked(Result<String, (Vec<u8>, Utf8Error)>),
+    /// Not validated yet. Boxed, so that the cache doesn't make every `Value` larger.
+    Deferred(Box<Deferred>),
+    /// Valid UTF-8, whose allocation may be shared with other strings.
+    Shared(Arc<str>),
+}
+
+/// The bytes of a string whose validation was deferred.
+#[derive(Clone, Debug)]
+struct Deferred {
+    buf: Vec<u8>,
+    /// The validation error, if any, once [`Utf8String::as_err`] has computed it.
+    err: OnceLock<Option<Utf8Error>>,
 }
 
 impl Utf8String {
+    /// Validates the given bytes, keeping them as is if they aren't valid UTF-8.
+    pub(crate) fn from_bytes(buf: Vec<u8>) -> Self {
+        match String::from_utf8(buf) {
+            Ok(s) => Utf8String::from(s),
+            Err(err) => {
+                let e = err.utf8_error();
+                Utf8String { s: Utf8Repr::Checked(Err((err.into_bytes(), e))) }
+            }
+        }
+    }
+
+    /// Wraps the given bytes without validating them.
+    pub(crate) fn deferred(buf: Vec<u8>) -> Self {
+        Utf8String { s: Utf8Repr::Deferred(Box::new(Deferred { buf, err: OnceLock::new() })) }
+    }
+
+    /// Returns the string if it's valid UTF-8, or else its bytes.
+    pub(crate) fn as_result(&self) -> Result<&str, &[u8]> {
+        match self.s {
+            Utf8Repr::Checked(Ok(ref s)) => Ok(s.as_str()),
+            Utf8Repr::Checked(Err((ref buf, _))) => Err(&buf[..]),
+            Utf8Repr::Deferred(ref d) => str::from_utf8(&d.buf).map_err(|_| &d.buf[..]),
+            Utf8Repr::Shared(ref s) => Ok(s),
+        }
+    }
+
+    /// Consumes this object, yielding the string if it's valid UTF-8, or else its bytes and the
+    /// validation error.
+    pub(crate) fn into_result(self) -> Result<String, (Vec<u8>, Utf8Error)> {
+        match self.s {
+            Utf8Repr::Checked(s) => s,
+            Utf8Repr::Deferred(d) => String::from_utf8(d.buf).map_err(|err| {
+                let e = err.utf8_error();
+                (err.into_bytes(), e)
+            }),
+            Utf8Repr::Shared(s) => Ok(s.as_ref().into()),
+        }
+    }
+
+    /// Returns the number of heap bytes owned by the string.
+    pub(crate) fn heap_size(&self) -> usize {
+        match self.s {
+            Utf8Repr::Checked(Ok(ref s)) => s.capacity(),
+            Utf8Repr::Checked(Err((ref buf, _))) => buf.capacity(),
+            Utf8Repr::Deferred(ref d) => mem::size_of::<Deferred>() + d.buf.capacity(),
+            Utf8Repr::Shared(ref s) => s.len(),
+        }
+    }
+
     /// Returns `true` if the string is valid UTF-8.
     #[inline]
     pub fn is_str(&self) -> bool {
-        self.s.is_ok()
+        self.as_result().is_ok()
     }
 
     /// Returns `true` if the string contains invalid UTF-8 sequence.
     #[inline]
     pub fn is_err(&self) -> bool {
-        self.s.is_err()
+        self.as_result().is_err()
     }
 
     /// Returns the string reference if the string is valid UTF-8, or else `None`.
+    ///
+    /// If validation was deferred while decoding, it happens here, on every call.
     #[inline]
     pub fn as_str(&self) -> Option<&str> {
-        match self.s {
-            Ok(ref s) => Some(s.as_str()),
-            Err(..) => None,
-        }
+        self.as_result().ok()
     }
 
     /// Returns the underlying `Utf8Error` if the string contains invalud UTF-8 sequence, or
@@ -252,8 +337,10 @@ impl Utf8String {
     #[inline]
     pub fn as_err(&self) -> Option<&Utf8Error> {
         match self.s {
-            Ok(..) => None,
-            Err((_, ref err)) => Some(err),
+            Utf8Repr::Checked(Ok(..)) |
+            Utf8Repr::Shared(..) => None,
+            Utf8Repr::Checked(Err((_, ref err))) => Some(err),
+            Utf8Repr::Deferred(ref d) => d.err.get_or_init(|| str::from_utf8(&d.buf).err()).as_ref(),
         }
     }
 
@@ -261,41 +348,57 @@ impl Utf8String {
     #[inline]
     pub fn as_bytes(&self) -> &[u8] {
         match self.s {
-            Ok(ref s) => s.as_bytes(),
-            Err(ref err) => &err.0[..],
+            Utf8Repr::Checked(Ok(ref s)) => s.as_bytes(),
+            Utf8Repr::Checked(Err(ref err)) => &err.0[..],
+            Utf8Repr::Deferred(ref d) => &d.buf[..],
+            Utf8Repr::Shared(ref s) => s.as_bytes(),
         }
     }
 
     /// Consumes this object, yielding the string if the string is valid UTF-8, or else `None`.
     #[inline]
     pub fn into_str(self) -> Option<String> {
-        self.s.ok()
+        self.into_result().ok()
     }
 
     /// Converts a `Utf8String` into a byte vector.
     #[inline]
     pub fn into_bytes(self) -> Vec<u8> {
         match self.s {
-            Ok(s) => s.into_bytes(),
-            Err(err) => err.0,
+            Utf8Repr::Checked(Ok(s)) => s.into_bytes(),
+            Utf8Repr::Checked(Err(err)) => err.0,
+            Utf8Repr::Deferred(d) => d.buf,
+            Utf8Repr::Shared(s) => s.as_bytes().to_vec(),
         }
     }
 
     #[inline]
     pub fn as_ref(&self) -> Utf8StringRef<'_> {
         match self.s {
-            Ok(ref s) => Utf8StringRef { s: Ok(s.as_str()) },
-            Err((ref buf, err)) => Utf8StringRef { s: Err((&buf[..], err)) },
+            Utf8Repr::Checked(Ok(ref s)) => Utf8StringRef { s: Ok(s.as_str()) },
+            Utf8Repr::Checked(Err((ref buf, err))) => Utf8StringRef { s: Err((&buf[..], err)) },
+            Utf8Repr::Deferred(ref d) => Utf8StringRef {
+                s: str::from_utf8(&d.buf).map_err(|err| (&d.buf[..], err)),
+            },
+            Utf8Repr::Shared(ref s) => Utf8StringRef { s: Ok(s) },
         }
     }
 }
 
+impl PartialEq for Utf8String {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        // Whether the bytes are valid UTF-8 depends on the bytes alone.
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
 impl Display for Utf8String {
     #[cold]
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
-        match self.s {
-            Ok(ref s) => Debug::fmt(&s, fmt),
-            Err(ref err) => Debug::fmt(&err.0, fmt),
+        match self.as_result() {
+            Ok(s) => Debug::fmt(&s, fmt),
+            Err(buf) => Debug::fmt(&buf, fmt),
         }
     }
 }
@@ -304,7 +407,7 @@ impl<'a> From<String> for Utf8String {
     #[inline]
     fn from(val: String) -> Self {
         Utf8String {
-            s: Ok(val),
+            s: Utf8Repr::Checked(Ok(val)),
         }
     }
 }
@@ -313,7 +416,7 @@ impl<'a> From<&'a str> for Utf8String {
     #[inline]
     fn from(val: &str) -> Self {
         Utf8String {
-            s: Ok(val.into()),
+            s: Utf8Repr::Checked(Ok(val.into())),
         }
     }
 }
@@ -322,7 +425,17 @@ impl<'a> From<Cow<'a, str>> for Utf8String {
     #[inline]
     fn from(val: Cow<'a, str>) -> Self {
         Utf8String {
-            s: Ok(val.into_owned()),
+            s: Utf8Repr::Checked(Ok(val.into_owned())),
+        }
+    }
+}
+
+/// Shares the allocation of the given string instead of copying it.
+impl From<Arc<str>> for Utf8String {
+    #[inline]
+    fn from(val: Arc<str>) -> Self {
+        Utf8String {
+            s: Utf8Repr::Shared(val),
         }
     }
 }
@@ -418,8 +531,8 @@ impl<'a> From<&'a str> for Utf8StringRef<'a> {
 impl<'a> From<Utf8StringRef<'a>> for Utf8String {
     fn from(val: Utf8StringRef<'a>) -> Self {
         match val.s {
-            Ok(s) => Utf8String { s: Ok(s.into()) },
-            Err((buf, err)) => Utf8String { s: Err((buf.into(), err)) }
+            Ok(s) => Utf8String { s: Utf8Repr::Checked(Ok(s.into())) },
+            Err((buf, err)) => Utf8String { s: Utf8Repr::Checked(Err((buf.into(), err))) }
         }
     }
 }
@@ -465,6 +578,25 @@ pub enum Value {
     /// Extended implements Extension interface: represents a tuple of type information and a byte
     /// array where type information is an integer whose meaning is defined by applications.
     Ext(i8, Vec<u8>),
+    /// An already encoded value, kept as its exact MessagePack bytes.
+    ///
+    /// It is produced when decoding with [`DecodeConfig::raw_keys`](decode::DecodeConfig::raw_keys)
+    /// and written back verbatim, which allows forwarding fields without interpreting them.
+    ///
+    /// Conversions that need the actual value, like `Display` or serialization, decode the bytes
+    /// on the fly, treating malformed ones as [`Value::Binary`].
+    Raw(Vec<u8>),
+    /// A binary left in the input instead of being decoded, of `len` bytes starting at `offset`.
+    ///
+    /// It is produced by [`read_value_with_binary_refs`](decode::read_value_with_binary_refs) for
+    /// large binaries, which can later be read from the same reader on demand. It can't be encoded
+    /// or serialized, since its data isn't there.
+    BinaryRef {
+        /// The position of the first byte of the data in the reader it was decoded from.
+        offset: u64,
+        /// The length of the data in bytes.
+        len: u32,
+    },
 }
 
 impl Value {
@@ -474,6 +606,9 @@ impl Value {
     ///
     /// Panics in unable to allocate memory to keep all internal structures and buffers.
     ///
+    /// Panics if the value is or contains a [`Value::BinaryRef`], whose data isn't there to borrow.
+    /// Use [`try_as_ref`](Value::try_as_ref) for values that may hold them.
+    ///
     /// # Examples
     /// ```
     /// use rmpv::{Value, ValueRef};
@@ -497,7 +632,24 @@ impl Value {
     /// assert_eq!(expected, val.as_ref());
     /// ```
     pub fn as_ref(&self) -> ValueRef<'_> {
-        match *self {
+        self.try_as_ref().expect("binary reference can't be borrowed without its data")
+    }
+
+    /// Converts the current owned Value to a ValueRef, or returns `None` if it is or contains a
+    /// [`Value::BinaryRef`], whose data isn't there to borrow.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmpv::{Value, ValueRef};
+    ///
+    /// assert_eq!(Some(ValueRef::from(42)), Value::from(42).try_as_ref());
+    ///
+    /// let val = Value::Array(vec![Value::from(42), Value::BinaryRef { offset: 10, len: 4 }]);
+    /// assert_eq!(None, val.try_as_ref());
+    /// ```
+    pub fn try_as_ref(&self) -> Option<ValueRef<'_>> {
+        let val = match *self {
             Value::Nil => ValueRef::Nil,
             Value::Boolean(val) => ValueRef::Boolean(val),
             Value::Integer(val) => ValueRef::Integer(val),
@@ -506,13 +658,18 @@ impl Value {
             Value::String(ref val) => ValueRef::String(val.as_ref()),
             Value::Binary(ref val) => ValueRef::Binary(val.as_slice()),
             Value::Array(ref val) => {
-                ValueRef::Array(val.iter().map(|v| v.as_ref()).collect())
+                ValueRef::Array(val.iter().map(Value::try_as_ref).collect::<Option<_>>()?)
             }
             Value::Map(ref val) => {
-                ValueRef::Map(val.iter().map(|(k, v)| (k.as_ref(), v.as_ref())).collect())
+                ValueRef::Map(val.iter().map(|(k, v)| Some((k.try_as_ref()?, v.try_as_ref()?))).collect::<Option<_>>()?)
             }
             Value::Ext(ty, ref buf) => ValueRef::Ext(ty, buf.as_slice()),
-        }
+            Value::Raw(ref buf) => {
+                decode::read_value_ref(&mut buf.as_slice()).unwrap_or(ValueRef::Binary(buf.as_slice()))
+            }
+            Value::BinaryRef { .. } => return None,
+        };
+        Some(val)
     }
 
     /// Returns true if the `Value` is a Null. Returns false otherwise.
@@ -892,6 +1049,403 @@ impl Value {
             None
         }
     }
+
+    /// Takes the value out, leaving a `Value::Nil` in its place.
+    ///
+    /// This allows moving parts out of a decoded tree without cloning them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmpv::Value;
+    ///
+    /// let mut val = Value::from("le message");
+    ///
+    /// assert_eq!(Value::from("le message"), val.take());
+    /// assert_eq!(Value::Nil, val);
+    /// ```
+    #[inline]
+    pub fn take(&mut self) -> Value {
+        mem::replace(self, Value::Nil)
+    }
+
+    /// If the `Value` is a valid UTF-8 String, returns the owned string.
+    /// Returns the `Value` back otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmpv::Value;
+    ///
+    /// assert_eq!(Ok("le message".to_string()), Value::from("le message").into_string());
+    ///
+    /// assert_eq!(Err(Value::Nil), Value::Nil.into_string());
+    /// ```
+    pub fn into_string(self) -> Result<String, Value> {
+        match self {
+            Value::String(val) => val.into_result().map_err(|(buf, err)| {
+                Value::String(Utf8String { s: Utf8Repr::Checked(Err((buf, err))) })
+            }),
+            val => Err(val),
+        }
+    }
+
+    /// If the `Value` is a Binary, returns the owned bytes.
+    /// Returns the `Value` back otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmpv::Value;
+    ///
+    /// assert_eq!(Ok(vec![1, 2, 3]), Value::Binary(vec![1, 2, 3]).into_bytes());
+    ///
+    /// assert_eq!(Err(Value::Nil), Value::Nil.into_bytes());
+    /// ```
+    pub fn into_bytes(self) -> Result<Vec<u8>, Value> {
+        match self {
+            Value::Binary(val) => Ok(val),
+            val => Err(val),
+        }
+    }
+
+    /// If the `Value` is an Array, returns the owned vector.
+    /// Returns the `Value` back otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmpv::Value;
+    ///
+    /// assert_eq!(Ok(vec![Value::Nil]), Value::Array(vec![Value::Nil]).into_array());
+    ///
+    /// assert_eq!(Err(Value::Nil), Value::Nil.into_array());
+    /// ```
+    pub fn into_array(self) -> Result<Vec<Value>, Value> {
+        match self {
+            Value::Array(array) => Ok(array),
+            val => Err(val),
+        }
+    }
+
+    /// If the `Value` is a Map, returns the owned vector of key-value tuples.
+    /// Returns the `Value` back otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmpv::Value;
+    ///
+    /// let val = Value::Map(vec![(Value::Nil, Value::Boolean(true))]);
+    ///
+    /// assert_eq!(Ok(vec![(Value::Nil, Value::Boolean(true))]), val.into_map());
+    ///
+    /// assert_eq!(Err(Value::Nil), Value::Nil.into_map());
+    /// ```
+    pub fn into_map(self) -> Result<Vec<(Value, Value)>, Value> {
+        match self {
+            Value::Map(map) => Ok(map),
+            val => Err(val),
+        }
+    }
+
+    /// If the `Value` is an Ext, returns the owned tuple with a ty and data.
+    /// Returns the `Value` back otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmpv::Value;
+    ///
+    /// assert_eq!(Ok((42, vec![1, 2, 3])), Value::Ext(42, vec![1, 2, 3]).into_ext());
+    ///
+    /// assert_eq!(Err(Value::Nil), Value::Nil.into_ext());
+    /// ```
+    pub fn into_ext(self) -> Result<(i8, Vec<u8>), Value> {
+        match self {
+            Value::Ext(ty, buf) => Ok((ty, buf)),
+            val => Err(val),
+        }
+    }
+
+    /// Returns a builder of an array of `len` elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmpv::Value;
+    ///
+    /// let mut builder = Value::array_builder(2);
+    /// builder.push(1).push("two");
+    ///
+    /// assert_eq!(Value::Array(vec![Value::from(1), Value::from("two")]), builder.finish().unwrap());
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn array_builder(len: usize) -> builder::ArrayBuilder {
+        builder::ArrayBuilder::new(len)
+    }
+
+    /// Returns a builder of a map of `len` entries.
+    ///
+    /// See the [`builder`] module for an example.
+    #[inline]
+    #[must_use]
+    pub fn map_builder(len: usize) -> builder::MapBuilder {
+        builder::MapBuilder::new(len)
+    }
+
+    /// Checks that the `Value` matches the given schema.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`ValidationError`](schema::ValidationError) holding the path to the first value
+    /// that doesn't match.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmpv::schema::Schema;
+    /// use rmpv::Value;
+    ///
+    /// let val = Value::Array(vec![Value::from(1), Value::from(2)]);
+    ///
+    /// assert!(val.validate(&Schema::Array(Box::new(Schema::Int))).is_ok());
+    /// assert!(val.validate(&Schema::Array(Box::new(Schema::Str))).is_err());
+    /// ```
+    #[inline]
+    pub fn validate(&self, schema: &schema::Schema) -> Result<(), schema::ValidationError> {
+        schema::validate(self, schema)
+    }
+
+    /// Compares two `Value` trees, returning the path to the first node where they differ, or
+    /// `None` if they are equal.
+    ///
+    /// Arrays and maps of different lengths differ as a whole. Map entries are compared in order;
+    /// if their keys differ, the path ends with the entry's [`Index`](path::PathSegment::Index).
+    ///
+    /// This is mostly useful for making test failures on large trees actionable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmpv::Value;
+    ///
+    /// let lhs = Value::Map(vec![(Value::from("tags"), Value::from(vec![Value::from("a")]))]);
+    /// let rhs = Value::Map(vec![(Value::from("tags"), Value::from(vec![Value::from("b")]))]);
+    ///
+    /// assert_eq!("$.tags[0]", lhs.diff(&rhs).unwrap().to_string());
+    /// assert_eq!(None, lhs.diff(&lhs.clone()));
+    /// ```
+    #[inline]
+    pub fn diff(&self, other: &Value) -> Option<path::ValuePath> {
+        path::diff(self, other)
+    }
+
+    /// Compares two values by their encoded bytes, as written by
+    /// [`write_value`](encode::write_value), rather than by their meaning.
+    ///
+    /// This is the total order that canonical forms of MessagePack use to sort map keys. Values of
+    /// different types are ordered by their markers, so for example all positive fixints sort
+    /// before fixstrs, which sort before negative fixints. Equal values always compare equal, while
+    /// an integer and a float holding the same number don't.
+    ///
+    /// Maps nested in the values are compared with their entries in their current order.
+    ///
+    /// Binary references have no encoding, so they sort after all other values, and by their offset
+    /// and then their length among themselves. Arrays and maps holding them are compared element
+    /// by element, the way their encodings would be, up to the first difference.
+    ///
+    /// Both values are encoded into a new buffer on every call, so sorting with it as the
+    /// comparator encodes each value many times. To sort many values, encode each one once as the
+    /// sort key instead, e.g. with [`slice::sort_by_cached_key`], as
+    /// [`SortMode::Canonical`](encode::SortMode::Canonical) does for map keys.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    ///
+    /// use rmpv::Value;
+    ///
+    /// // 0x7f vs 0xa1 0x61.
+    /// assert_eq!(Ordering::Less, Value::from(127).cmp_encoded(&Value::from("a")));
+    /// // 0xcc 0x80 vs 0xa1 0x61.
+    /// assert_eq!(Ordering::Greater, Value::from(128).cmp_encoded(&Value::from("a")));
+    /// ```
+    pub fn cmp_encoded(&self, other: &Value) -> Ordering {
+        fn encode(val: &Value) -> Option<Vec<u8>> {
+            let mut buf = Vec::new();
+            encode::write_value(&mut buf, val).ok()?;
+            Some(buf)
+        }
+
+        match (encode(self), encode(other)) {
+            (Some(lhs), Some(rhs)) => lhs.cmp(&rhs),
+            // A binary reference is in the way, so the encodings are compared piece by piece.
+            _ => cmp_encoded_parts(self, other),
+        }
+    }
+
+    /// Compares two values by their meaning, as people would sort them, rather than by their
+    /// encoded bytes like [`cmp_encoded`](Value::cmp_encoded).
+    ///
+    /// Integers are ordered numerically, whatever their encoding, so `2` sorts before `10`, and
+    /// negative ones before positive ones. Strings are ordered by their bytes, which for valid
+    /// UTF-8 is the order of their characters. Floats are ordered numerically as well, after
+    /// widening F32 to F64, with NaNs placed as [`f64::total_cmp`] does. Binaries are ordered by
+    /// their bytes, extensions by their type and then their data, arrays and maps element by
+    /// element, in their current order.
+    ///
+    /// Values of different types are ordered by type: nil, booleans, integers, floats, strings,
+    /// binaries, binary references, arrays, maps and extensions. So an integer and a float holding
+    /// the same number don't compare equal, and all integers sort before all floats. Raw values
+    /// are compared as the value they encode.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::cmp::Ordering;
+    ///
+    /// use rmpv::Value;
+    ///
+    /// assert_eq!(Ordering::Less, Value::from(2).cmp_semantic(&Value::from(10)));
+    /// assert_eq!(Ordering::Less, Value::from(-1).cmp_semantic(&Value::from(0)));
+    /// assert_eq!(Ordering::Less, Value::from(300).cmp_semantic(&Value::from("a")));
+    /// ```
+    pub fn cmp_semantic(&self, other: &Value) -> Ordering {
+        fn rank(val: &Value) -> u8 {
+            match *val {
+                Value::Nil => 0,
+                Value::Boolean(..) => 1,
+                Value::Integer(..) => 2,
+                Value::F32(..) | Value::F64(..) => 3,
+                Value::String(..) => 4,
+                Value::Binary(..) => 5,
+                Value::BinaryRef { .. } => 6,
+                Value::Array(..) => 7,
+                Value::Map(..) => 8,
+                Value::Ext(..) => 9,
+                Value::Raw(ref buf) => rank(&raw_value(buf)),
+            }
+        }
+
+        fn cmp_int(lhs: Integer, rhs: Integer) -> Ordering {
+            match (lhs.n, rhs.n) {
+                (IntPriv::PosInt(lhs), IntPriv::PosInt(rhs)) => lhs.cmp(&rhs),
+                (IntPriv::NegInt(lhs), IntPriv::NegInt(rhs)) => lhs.cmp(&rhs),
+                (IntPriv::NegInt(..), IntPriv::PosInt(..)) => Ordering::Less,
+                (IntPriv::PosInt(..), IntPriv::NegInt(..)) => Ordering::Greater,
+            }
+        }
+
+        match (self, other) {
+            (Value::Raw(buf), other) => raw_value(buf).cmp_semantic(other),
+            (val, Value::Raw(buf)) => val.cmp_semantic(&raw_value(buf)),
+            (Value::Boolean(lhs), Value::Boolean(rhs)) => lhs.cmp(rhs),
+            (Value::Integer(lhs), Value::Integer(rhs)) => cmp_int(*lhs, *rhs),
+            (&Value::F32(..) | &Value::F64(..), &Value::F32(..) | &Value::F64(..)) => {
+                let lhs = self.as_f64().unwrap_or_default();
+                lhs.total_cmp(&other.as_f64().unwrap_or_default())
+            }
+            (Value::String(lhs), Value::String(rhs)) => lhs.as_bytes().cmp(rhs.as_bytes()),
+            (Value::Binary(lhs), Value::Binary(rhs)) => lhs.cmp(rhs),
+            (&Value::BinaryRef { offset, len }, &Value::BinaryRef { offset: rhs_offset, len: rhs_len }) => {
+                (offset, len).cmp(&(rhs_offset, rhs_len))
+            }
+            (Value::Array(lhs), Value::Array(rhs)) => {
+                let len = lhs.len().cmp(&rhs.len());
+                lhs.iter()
+                    .zip(rhs)
+                    .map(|(lhs, rhs)| lhs.cmp_semantic(rhs))
+                    .find(|ord| ord.is_ne())
+                    .unwrap_or(len)
+            }
+            (Value::Map(lhs), Value::Map(rhs)) => {
+                let len = lhs.len().cmp(&rhs.len());
+                lhs.iter()
+                    .zip(rhs)
+                    .map(|((lk, lv), (rk, rv))| lk.cmp_semantic(rk).then_with(|| lv.cmp_semantic(rv)))
+                    .find(|ord| ord.is_ne())
+                    .unwrap_or(len)
+            }
+            (Value::Ext(lty, lhs), Value::Ext(rty, rhs)) => (lty, lhs).cmp(&(rty, rhs)),
+            (lhs, rhs) => rank(lhs).cmp(&rank(rhs)),
+        }
+    }
+
+    /// Compares two values like `==`, except that a string and a binary holding the same bytes
+    /// are equal, however deeply nested.
+    ///
+    /// Encoders following the old spec write byte payloads as strings, since it has no binary
+    /// type, so the same data may arrive as either type, depending on the encoder version. The
+    /// string's bytes are compared as is, whether they are valid UTF-8 or not. Integers are
+    /// already compared by value regardless of their encoding, but floats and integers are still
+    /// never equal, nor are an F32 and an F64.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmpv::Value;
+    ///
+    /// let val = Value::Array(vec![Value::from("abc")]);
+    ///
+    /// assert!(val.loose_eq(&Value::Array(vec![Value::Binary(b"abc".to_vec())])));
+    /// assert_ne!(val, Value::Array(vec![Value::Binary(b"abc".to_vec())]));
+    /// ```
+    pub fn loose_eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::String(s), Value::Binary(buf)) |
+            (Value::Binary(buf), Value::String(s)) => s.as_bytes() == &buf[..],
+            (Value::String(lhs), Value::String(rhs)) => lhs.as_bytes() == rhs.as_bytes(),
+            (Value::Array(lhs), Value::Array(rhs)) => {
+                lhs.len() == rhs.len() && lhs.iter().zip(rhs).all(|(lhs, rhs)| lhs.loose_eq(rhs))
+            }
+            (Value::Map(lhs), Value::Map(rhs)) => {
+                lhs.len() == rhs.len() &&
+                    lhs.iter().zip(rhs).all(|((lk, lv), (rk, rv))| lk.loose_eq(rk) && lv.loose_eq(rv))
+            }
+            (lhs, rhs) => lhs == rhs,
+        }
+    }
+
+    /// Estimates the number of heap bytes owned by this value tree.
+    ///
+    /// This counts the capacity of every string, binary and container buffer, recursing into
+    /// nested values, but not the space of `self` itself nor any allocator overhead. It helps
+    /// bounding memory use and detecting pathological messages after decoding.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rmpv::Value;
+    ///
+    /// assert_eq!(0, Value::from(42).heap_size());
+    ///
+    /// let val = Value::Binary(Vec::with_capacity(16));
+    /// assert_eq!(16, val.heap_size());
+    /// ```
+    pub fn heap_size(&self) -> usize {
+        match *self {
+            Value::Nil |
+            Value::Boolean(..) |
+            Value::Integer(..) |
+            Value::F32(..) |
+            Value::F64(..) |
+            Value::BinaryRef { .. } => 0,
+            Value::String(ref val) => val.heap_size(),
+            Value::Binary(ref buf) |
+            Value::Ext(_, ref buf) |
+            Value::Raw(ref buf) => buf.capacity(),
+            Value::Array(ref vec) => {
+                vec.capacity() * mem::size_of::<Value>() + vec.iter().map(Value::heap_size).sum::<usize>()
+            }
+            Value::Map(ref vec) => {
+                vec.capacity() * mem::size_of::<(Value, Value)>() +
+                    vec.iter().map(|(k, v)| k.heap_size() + v.heap_size()).sum::<usize>()
+            }
+        }
+    }
 }
 
 static NIL: Value = Value::Nil;
@@ -1138,12 +1692,7 @@ impl TryFrom<Value> for String {
   type Error = Value;
 
   fn try_from(val: Value) -> Result<Self, Self::Error> {
-    match val {
-      Value::String(Utf8String{ s: Ok(u)}) => {
-        Ok(u)
-      }
-      _ => Err(val)
-    }
+    val.into_string()
   }
 }
 // The following impl was left out intentionally, see
@@ -1183,6 +1732,61 @@ impl_try_from!(Vec<u8>, Binary);
 impl_try_from!(f32, F32);
 impl_try_from!(Utf8String, String);
 
+/// Compares two values as [`Value::cmp_encoded`] does, encoding only the headers of arrays and
+/// maps, followed by their elements one by one, so that binary references can be placed.
+///
+/// As encodings are never a prefix of one another, comparing elements one by one orders
+/// containers the same way as comparing their whole encodings.
+fn cmp_encoded_parts(lhs: &Value, rhs: &Value) -> Ordering {
+    fn head(val: &Value) -> Vec<u8> {
+        let mut buf = Vec::new();
+        // Only values too large for MessagePack fail to encode, and they sort by what was written.
+        let _ = match *val {
+            Value::Array(ref vec) => rmp::encode::write_array_len(&mut buf, vec.len() as u32).map(drop).map_err(drop),
+            Value::Map(ref map) => rmp::encode::write_map_len(&mut buf, map.len() as u32).map(drop).map_err(drop),
+            ref val => encode::write_value(&mut buf, val).map_err(drop),
+        };
+        buf
+    }
+
+    match (lhs, rhs) {
+        (&Value::BinaryRef { offset, len }, &Value::BinaryRef { offset: rhs_offset, len: rhs_len }) => {
+            return (offset, len).cmp(&(rhs_offset, rhs_len));
+        }
+        (Value::BinaryRef { .. }, _) => return Ordering::Greater,
+        (_, Value::BinaryRef { .. }) => return Ordering::Less,
+        // The bytes of a raw value may be those of a container, whose elements are compared then.
+        (Value::Raw(buf), Value::Array(..) | Value::Map(..)) => return cmp_encoded_parts(&raw_value(buf), rhs),
+        (Value::Array(..) | Value::Map(..), Value::Raw(buf)) => return cmp_encoded_parts(lhs, &raw_value(buf)),
+        _ => {}
+    }
+
+    head(lhs).cmp(&head(rhs)).then_with(|| match (lhs, rhs) {
+        (Value::Array(lhs), Value::Array(rhs)) => {
+            lhs.iter()
+                .zip(rhs)
+                .map(|(lhs, rhs)| cmp_encoded_parts(lhs, rhs))
+                .find(|ord| ord.is_ne())
+                .unwrap_or(Ordering::Equal)
+        }
+        (Value::Map(lhs), Value::Map(rhs)) => {
+            lhs.iter()
+                .zip(rhs)
+                .map(|((lhs_key, lhs_val), (rhs_key, rhs_val))| {
+                    cmp_encoded_parts(lhs_key, rhs_key).then_with(|| cmp_encoded_parts(lhs_val, rhs_val))
+                })
+                .find(|ord| ord.is_ne())
+                .unwrap_or(Ordering::Equal)
+        }
+        _ => Ordering::Equal,
+    })
+}
+
+/// Decodes the bytes of a [`Value::Raw`], falling back to a binary if they are malformed.
+pub(crate) fn raw_value(buf: &[u8]) -> Value {
+    decode::read_value(&mut &buf[..]).unwrap_or_else(|_| Value::Binary(buf.to_vec()))
+}
+
 impl Display for Value {
     #[cold]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
@@ -1222,6 +1826,8 @@ impl Display for Value {
 
                 write!(f, "}}")
             }
+            Value::Raw(ref buf) => Display::fmt(&raw_value(buf), f),
+            Value::BinaryRef { offset, len } => write!(f, "<{} bytes at {}>", len, offset),
             Value::Ext(ty, ref data) => {
                 write!(f, "[{}, {:?}]", ty, data)
             }
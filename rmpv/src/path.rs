@@ -0,0 +1,116 @@
+//! Paths to nodes of a [`Value`] tree.
+
+use std::borrow::Borrow;
+use std::fmt::{self, Display, Formatter};
+use std::ops::Deref;
+
+use crate::Value;
+
+/// A step from a value to one of its children.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    /// An array element, or a map entry whose key differs.
+    Index(usize),
+    /// A map field. Non-string keys are represented by their `Display` output.
+    Key(String),
+}
+
+/// The path from the root of a [`Value`] tree to one of its nodes.
+///
+/// It is displayed like `$.config[2].name`, where `$` is the root.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ValuePath(Vec<PathSegment>);
+
+impl ValuePath {
+    /// Returns the segments leading from the root to the node.
+    #[inline]
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.0
+    }
+}
+
+impl Deref for ValuePath {
+    type Target = [PathSegment];
+
+    #[inline]
+    fn deref(&self) -> &[PathSegment] {
+        &self.0
+    }
+}
+
+impl Borrow<[PathSegment]> for ValuePath {
+    #[inline]
+    fn borrow(&self) -> &[PathSegment] {
+        &self.0
+    }
+}
+
+impl From<Vec<PathSegment>> for ValuePath {
+    #[inline]
+    fn from(segments: Vec<PathSegment>) -> Self {
+        ValuePath(segments)
+    }
+}
+
+impl Display for ValuePath {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write_path(fmt, &self.0)
+    }
+}
+
+pub(crate) fn write_path(fmt: &mut Formatter<'_>, path: &[PathSegment]) -> Result<(), fmt::Error> {
+    fmt.write_str("$")?;
+    for segment in path {
+        match *segment {
+            PathSegment::Index(idx) => write!(fmt, "[{}]", idx)?,
+            PathSegment::Key(ref key) => write!(fmt, ".{}", key)?,
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn key_segment(key: &Value) -> PathSegment {
+    match key.as_str() {
+        Some(key) => PathSegment::Key(key.to_owned()),
+        None => PathSegment::Key(key.to_string()),
+    }
+}
+
+fn diff_at(lhs: &Value, rhs: &Value, path: &mut Vec<PathSegment>) -> bool {
+    match (lhs, rhs) {
+        (Value::Array(lhs), Value::Array(rhs)) if lhs.len() == rhs.len() => {
+            for (idx, (lhs, rhs)) in lhs.iter().zip(rhs).enumerate() {
+                path.push(PathSegment::Index(idx));
+                if diff_at(lhs, rhs, path) {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+        (Value::Map(lhs), Value::Map(rhs)) if lhs.len() == rhs.len() => {
+            for (idx, ((lhs_key, lhs), (rhs_key, rhs))) in lhs.iter().zip(rhs).enumerate() {
+                if lhs_key != rhs_key {
+                    path.push(PathSegment::Index(idx));
+                    return true;
+                }
+                path.push(key_segment(lhs_key));
+                if diff_at(lhs, rhs, path) {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+        (lhs, rhs) => lhs != rhs,
+    }
+}
+
+pub(crate) fn diff(lhs: &Value, rhs: &Value) -> Option<ValuePath> {
+    let mut path = Vec::new();
+    if diff_at(lhs, rhs, &mut path) {
+        Some(ValuePath(path))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,502 @@
+//! Validation of a [`Value`] tree against an expected shape.
+//!
+//! # Examples
+//!
+//! ```
+//! use rmpv::schema::Schema;
+//! use rmpv::Value;
+//!
+//! let schema = Schema::Map(vec![
+//!     ("id".into(), Schema::Int),
+//!     ("tags".into(), Schema::Array(Box::new(Schema::Str))),
+//! ]);
+//!
+//! let val = Value::Map(vec![
+//!     (Value::from("id"), Value::from(42)),
+//!     (Value::from("tags"), Value::Array(vec![Value::from("a"), Value::from(1)])),
+//! ]);
+//!
+//! let err = val.validate(&schema).unwrap_err();
+//! assert_eq!("$.tags[1]: expected str", err.to_string());
+//! ```
+//!
+//! # Positional encoding
+//!
+//! A schema also allows writing records without their keys, with [`write_positional`]: every map
+//! matching a [`Schema::Map`] is written as an array of its field values, in the order of the
+//! schema. For data with many small records, the keys often take as much space as the values.
+//!
+//! The output can't be decoded without the schema, which [`read_positional`] uses to attach the
+//! keys again, so both ends must agree on it out-of-band, for example by versioning it along with
+//! the protocol. Reordering the fields of a schema changes the meaning of the data written with it.
+//!
+//! ```
+//! use rmpv::schema::{read_positional, write_positional, Schema};
+//! use rmpv::Value;
+//!
+//! let schema = Schema::Map(vec![("id".into(), Schema::Int), ("name".into(), Schema::Str)]);
+//! let val = Value::Map(vec![(Value::from("name"), Value::from("John")), (Value::from("id"), Value::from(42))]);
+//!
+//! let mut buf = Vec::new();
+//! write_positional(&mut buf, &val, &schema).unwrap();
+//! // [42, "John"]
+//! assert_eq!(vec![0x92, 0x2a, 0xa4, 0x4a, 0x6f, 0x68, 0x6e], buf);
+//!
+//! let decoded = read_positional(&mut &buf[..], &schema).unwrap();
+//! assert_eq!(Value::from(42), decoded["id"]);
+//! assert_eq!(Value::from("John"), decoded["name"]);
+//! ```
+//!
+//! # Profiling
+//!
+//! Conversely, [`read_value_profiled`] helps inferring the shape of undocumented data: alongside
+//! decoding, it counts the types observed for every map field in a [`SchemaProfile`], which
+//! accumulates over as many messages as it is passed to.
+//!
+//! ```
+//! use rmpv::encode::write_value;
+//! use rmpv::schema::{read_value_profiled, SchemaProfile, ValueType};
+//! use rmpv::Value;
+//!
+//! let mut profile = SchemaProfile::new();
+//! for id in [Value::from(1), Value::from("2")] {
+//!     let mut buf = Vec::new();
+//!     write_value(&mut buf, &Value::Map(vec![(Value::from("id"), id)])).unwrap();
+//!     read_value_profiled(&mut &buf[..], &mut profile).unwrap();
+//! }
+//!
+//! assert_eq!(1, profile.count("id", ValueType::Int));
+//! assert_eq!(1, profile.count("id", ValueType::Str));
+//! ```
+
+use std::collections::BTreeMap;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, ErrorKind, Read, Write};
+
+use rmp::decode::{read_array_len, read_marker};
+use rmp::encode::{write_array_len, write_nil};
+use rmp::Marker;
+
+use crate::decode::Error as DecodeError;
+use crate::encode::Error as EncodeError;
+pub use crate::path::PathSegment;
+use crate::path::write_path;
+use crate::Value;
+
+/// The expected shape of a [`Value`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Schema {
+    /// Any value.
+    Any,
+    /// Nil.
+    Nil,
+    /// A boolean.
+    Bool,
+    /// An integer, whether signed or unsigned.
+    Int,
+    /// A 32- or 64-bit float.
+    Float,
+    /// A string, whether valid UTF-8 or not.
+    Str,
+    /// A binary.
+    Bin,
+    /// An array whose elements all match the given schema.
+    Array(Box<Schema>),
+    /// A map containing at least the given string-keyed fields, each matching its schema.
+    ///
+    /// Fields not listed are allowed and not validated.
+    Map(Vec<(String, Schema)>),
+    /// An extension.
+    Ext,
+    /// Nil or a value matching the given schema. A missing map field also matches.
+    Optional(Box<Schema>),
+}
+
+impl Schema {
+    /// Returns a short description of the expected value, as used in error messages.
+    fn name(&self) -> &'static str {
+        match *self {
+            Schema::Any => "any",
+            Schema::Nil => "nil",
+            Schema::Bool => "bool",
+            Schema::Int => "int",
+            Schema::Float => "float",
+            Schema::Str => "str",
+            Schema::Bin => "bin",
+            Schema::Array(..) => "array",
+            Schema::Map(..) => "map",
+            Schema::Ext => "ext",
+            Schema::Optional(..) => "optional",
+        }
+    }
+}
+
+/// The reason a value doesn't match its schema.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationErrorKind {
+    /// The value has another type. Contains the expected one.
+    TypeMismatch(&'static str),
+    /// A required map field is missing. Contains the field name.
+    MissingField(String),
+}
+
+/// An error returned by [`Value::validate`], pointing at the offending value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValidationError {
+    path: Vec<PathSegment>,
+    kind: ValidationErrorKind,
+}
+
+impl ValidationError {
+    /// Returns the path from the root to the offending value.
+    #[inline]
+    pub fn path(&self) -> &[PathSegment] {
+        &self.path
+    }
+
+    /// Returns the reason of the failure.
+    #[inline]
+    pub fn kind(&self) -> &ValidationErrorKind {
+        &self.kind
+    }
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write_path(fmt, &self.path)?;
+
+        match self.kind {
+            ValidationErrorKind::TypeMismatch(expected) => write!(fmt, ": expected {}", expected),
+            ValidationErrorKind::MissingField(ref name) => write!(fmt, ": missing field {}", name),
+        }
+    }
+}
+
+impl error::Error for ValidationError {}
+
+fn fail(path: &[PathSegment], kind: ValidationErrorKind) -> Result<(), ValidationError> {
+    Err(ValidationError { path: path.to_vec(), kind })
+}
+
+fn find_field<'a>(map: &'a [(Value, Value)], name: &str) -> Option<&'a Value> {
+    map.iter().find(|(key, _)| key.as_str() == Some(name)).map(|(_, val)| val)
+}
+
+fn validate_at(val: &Value, schema: &Schema, path: &mut Vec<PathSegment>) -> Result<(), ValidationError> {
+    let matches = match (schema, val) {
+        (Schema::Any, _) |
+        (Schema::Nil, Value::Nil) |
+        (Schema::Bool, Value::Boolean(..)) |
+        (Schema::Int, Value::Integer(..)) |
+        (Schema::Float, Value::F32(..)) |
+        (Schema::Float, Value::F64(..)) |
+        (Schema::Str, Value::String(..)) |
+        (Schema::Bin, Value::Binary(..)) |
+        (Schema::Ext, Value::Ext(..)) |
+        (Schema::Optional(..), Value::Nil) => true,
+        (Schema::Optional(schema), val) => return validate_at(val, schema, path),
+        (Schema::Array(schema), Value::Array(vec)) => {
+            for (idx, val) in vec.iter().enumerate() {
+                path.push(PathSegment::Index(idx));
+                validate_at(val, schema, path)?;
+                path.pop();
+            }
+            true
+        }
+        (Schema::Map(fields), Value::Map(map)) => {
+            for (name, schema) in fields {
+                match (find_field(map, name), schema) {
+                    (Some(val), schema) => {
+                        path.push(PathSegment::Key(name.clone()));
+                        validate_at(val, schema, path)?;
+                        path.pop();
+                    }
+                    (None, Schema::Optional(..)) => {}
+                    (None, _) => return fail(path, ValidationErrorKind::MissingField(name.clone())),
+                }
+            }
+            true
+        }
+        _ => false,
+    };
+
+    if matches {
+        Ok(())
+    } else {
+        fail(path, ValidationErrorKind::TypeMismatch(schema.name()))
+    }
+}
+
+pub(crate) fn validate(val: &Value, schema: &Schema) -> Result<(), ValidationError> {
+    validate_at(val, schema, &mut Vec::new())
+}
+
+/// Encodes and attempts to write the given value, writing the maps that match a
+/// [`Schema::Map`] as arrays of their field values, in the order of the schema, without their
+/// keys.
+///
+/// A missing optional field is written as nil. The other values are written as with
+/// [`write_value`](crate::encode::write_value). See the [module documentation](self#positional-encoding)
+/// for how the output is decoded.
+///
+/// # Errors
+///
+/// This function will return [`EncodeError`](crate::encode::Error) on any I/O error while writing,
+/// and [`InvalidDataWrite`](crate::encode::Error::InvalidDataWrite) with the `InvalidInput` kind if
+/// the value doesn't match the schema, wrapping its [`ValidationError`], or if a map has a field not
+/// in its schema, which couldn't be written.
+pub fn write_positional<W: Write>(wr: &mut W, val: &Value, schema: &Schema) -> Result<(), EncodeError> {
+    if let Err(err) = validate(val, schema) {
+        return Err(EncodeError::InvalidDataWrite(io::Error::new(ErrorKind::InvalidInput, err)));
+    }
+
+    write_positional_at(wr, val, schema)
+}
+
+fn write_positional_at<W: Write>(wr: &mut W, val: &Value, schema: &Schema) -> Result<(), EncodeError> {
+    match (schema, val) {
+        (Schema::Optional(schema), val) if !val.is_nil() => write_positional_at(wr, val, schema),
+        (Schema::Array(schema), Value::Array(vec)) => {
+            write_array_len(wr, vec.len() as u32)?;
+            for val in vec {
+                write_positional_at(wr, val, schema)?;
+            }
+            Ok(())
+        }
+        (Schema::Map(fields), Value::Map(map)) => {
+            let is_known = |key: &Value| fields.iter().any(|(name, _)| key.as_str() == Some(name));
+            if let Some((key, _)) = map.iter().find(|(key, _)| !is_known(key)) {
+                let msg = format!("field {} is not in the schema", key);
+                return Err(EncodeError::InvalidDataWrite(io::Error::new(ErrorKind::InvalidInput, msg)));
+            }
+
+            write_array_len(wr, fields.len() as u32)?;
+            for (name, schema) in fields {
+                match find_field(map, name) {
+                    Some(val) => write_positional_at(wr, val, schema)?,
+                    None => write_nil(wr).map_err(EncodeError::InvalidMarkerWrite)?,
+                }
+            }
+            Ok(())
+        }
+        _ => crate::encode::write_value(wr, val),
+    }
+}
+
+/// Attempts to read a value written by [`write_positional`] with the same schema, decoding the
+/// arrays of field values back into maps keyed by the field names of the schema.
+///
+/// Every field of the schema is present in the decoded maps, so optional fields that were missing
+/// are decoded as nil. The other values are decoded with
+/// [`decode::read_value`](crate::decode::read_value), and not checked against the schema.
+///
+/// # Errors
+///
+/// In addition to the errors of [`decode::read_value`](crate::decode::read_value), returns
+/// [`Error::InvalidLength`](crate::decode::Error::InvalidLength) with the actual length if a record
+/// doesn't have as many fields as its schema, and [`Error::InvalidMarkerRead`] if a value expected
+/// to be an array isn't one.
+///
+/// [`Error::InvalidMarkerRead`]: crate::decode::Error::InvalidMarkerRead
+pub fn read_positional<R: Read>(rd: &mut R, schema: &Schema) -> Result<Value, DecodeError> {
+    read_positional_at(rd, schema)
+}
+
+// Takes a trait object, since peeking markers of optional values wraps the reader.
+fn read_positional_at(mut rd: &mut dyn Read, schema: &Schema) -> Result<Value, DecodeError> {
+    match *schema {
+        Schema::Optional(ref schema) => {
+            let marker = read_marker(&mut rd)?;
+            if marker == Marker::Null {
+                return Ok(Value::Nil);
+            }
+            let buf = [marker.to_u8()];
+            read_positional_at(&mut (&buf[..]).chain(rd), schema)
+        }
+        Schema::Array(ref schema) => {
+            let len = read_array_len(&mut rd)?;
+            let mut vec = Vec::new();
+            for _ in 0..len {
+                vec.push(read_positional_at(rd, schema)?);
+            }
+            Ok(Value::Array(vec))
+        }
+        Schema::Map(ref fields) => {
+            let len = read_array_len(&mut rd)?;
+            if len as usize != fields.len() {
+                return Err(DecodeError::InvalidLength(len));
+            }
+            let mut map = Vec::with_capacity(fields.len());
+            for (name, schema) in fields {
+                map.push((Value::from(name.as_str()), read_positional_at(rd, schema)?));
+            }
+            Ok(Value::Map(map))
+        }
+        _ => crate::decode::read_value(&mut rd),
+    }
+}
+
+/// The type of a value, as counted by a [`SchemaProfile`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ValueType {
+    /// Nil.
+    Nil,
+    /// A boolean.
+    Bool,
+    /// An integer, whether signed or unsigned.
+    Int,
+    /// A 32- or 64-bit float.
+    Float,
+    /// A string, whether valid UTF-8 or not.
+    Str,
+    /// A binary.
+    Bin,
+    /// An array.
+    Array,
+    /// A map.
+    Map,
+    /// An extension.
+    Ext,
+}
+
+impl ValueType {
+    /// Returns the type of the given value.
+    ///
+    /// [`Value::Raw`] and [`Value::BinaryRef`] values, which [`read_value_profiled`] doesn't
+    /// produce, count as binaries.
+    #[must_use]
+    pub fn of(val: &Value) -> Self {
+        match *val {
+            Value::Nil => ValueType::Nil,
+            Value::Boolean(..) => ValueType::Bool,
+            Value::Integer(..) => ValueType::Int,
+            Value::F32(..) | Value::F64(..) => ValueType::Float,
+            Value::String(..) => ValueType::Str,
+            Value::Binary(..) | Value::Raw(..) | Value::BinaryRef { .. } => ValueType::Bin,
+            Value::Array(..) => ValueType::Array,
+            Value::Map(..) => ValueType::Map,
+            Value::Ext(..) => ValueType::Ext,
+        }
+    }
+}
+
+impl Display for ValueType {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        fmt.write_str(match *self {
+            ValueType::Nil => "nil",
+            ValueType::Bool => "bool",
+            ValueType::Int => "int",
+            ValueType::Float => "float",
+            ValueType::Str => "str",
+            ValueType::Bin => "bin",
+            ValueType::Array => "array",
+            ValueType::Map => "map",
+            ValueType::Ext => "ext",
+        })
+    }
+}
+
+/// The types observed for every map field over a batch of values, see
+/// [`read_value_profiled`].
+///
+/// Fields are named by their path from the root, the keys of nested maps joined with dots, e.g.
+/// `user.name`. Arrays don't add to the path, so the fields of the maps in an array are counted
+/// together, under the path of the array. Non-string keys are named by their `Display` output.
+///
+/// Keys aren't escaped, so a key holding a dot collides with the nested path it spells: the values
+/// of a field `"a.b"` and of the field `"b"` of a map under `"a"` are counted as the same field.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchemaProfile {
+    values: u64,
+    fields: BTreeMap<String, BTreeMap<ValueType, u64>>,
+}
+
+impl SchemaProfile {
+    /// Creates an empty profile.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of values recorded.
+    #[inline]
+    #[must_use]
+    pub fn values(&self) -> u64 {
+        self.values
+    }
+
+    /// Returns how many times the given field has been observed with the given type.
+    #[must_use]
+    pub fn count(&self, field: &str, ty: ValueType) -> u64 {
+        self.field(field).and_then(|types| types.get(&ty)).copied().unwrap_or(0)
+    }
+
+    /// Returns the number of occurrences of each type observed for the given field, or `None` if
+    /// it hasn't been observed.
+    #[inline]
+    #[must_use]
+    pub fn field(&self, field: &str) -> Option<&BTreeMap<ValueType, u64>> {
+        self.fields.get(field)
+    }
+
+    /// Returns the fields observed and the number of occurrences of each of their types, ordered by
+    /// field path.
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &BTreeMap<ValueType, u64>)> {
+        self.fields.iter().map(|(field, types)| (field.as_str(), types))
+    }
+
+    /// Records the fields of the given value.
+    pub fn record(&mut self, val: &Value) {
+        self.values += 1;
+        self.record_at(val, &mut String::new());
+    }
+
+    fn record_at(&mut self, val: &Value, path: &mut String) {
+        match *val {
+            Value::Array(ref vec) => {
+                for val in vec {
+                    self.record_at(val, path);
+                }
+            }
+            Value::Map(ref map) => {
+                for (key, val) in map {
+                    let len = path.len();
+                    if len > 0 {
+                        path.push('.');
+                    }
+                    match key.as_str() {
+                        Some(key) => path.push_str(key),
+                        None => path.push_str(&key.to_string()),
+                    }
+
+                    // The path is only copied the first time the field is observed.
+                    let types = match self.fields.get_mut(path.as_str()) {
+                        Some(types) => types,
+                        None => self.fields.entry(path.clone()).or_default(),
+                    };
+                    *types.entry(ValueType::of(val)).or_insert(0) += 1;
+                    self.record_at(val, path);
+                    path.truncate(len);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Attempts to read a value, as with [`decode::read_value`](crate::decode::read_value), recording
+/// the types of its map fields in `profile`.
+///
+/// Passing the same profile to successive calls accumulates the counts over all the values read.
+/// Values that fail to decode aren't recorded.
+///
+/// # Errors
+///
+/// Returns the same errors as [`decode::read_value`](crate::decode::read_value).
+pub fn read_value_profiled<R: Read>(rd: &mut R, profile: &mut SchemaProfile) -> Result<Value, DecodeError> {
+    let val = crate::decode::read_value(rd)?;
+    profile.record(&val);
+    Ok(val)
+}
@@ -0,0 +1,150 @@
+//! A compact in-memory form of [`Value`] trees, for holding many decoded values at once.
+//!
+//! A [`Value`] is as large as its largest variant, so every nil, boolean or integer takes as much
+//! memory as a string or an array. [`SmallValue`] stores integers, floats, booleans and nil inline
+//! and boxes everything else, so that it is less than half the size of a `Value`, at the cost of
+//! one more allocation per string, binary, container or extension.
+//!
+//! # Examples
+//!
+//! ```
+//! use rmpv::small::SmallValue;
+//! use rmpv::Value;
+//!
+//! let val = rmpv::decode::read_value(&mut &[0x93, 0x01, 0xc3, 0xa1, 0x61][..]).unwrap();
+//!
+//! let small = SmallValue::from(val.clone());
+//! assert_eq!(Some(1), small.as_array().unwrap()[0].as_i64());
+//! assert_eq!(val, Value::from(small));
+//! ```
+
+use crate::{IntPriv, Integer, Utf8String, Value};
+
+/// A [`Value`] taking 16 bytes, with its heap-allocated variants boxed.
+///
+/// It converts from and to `Value` losslessly, as long as every [`NegInt`](SmallValue::NegInt)
+/// holds a negative integer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SmallValue {
+    /// Nil represents nil.
+    Nil,
+    /// Boolean represents true or false.
+    Boolean(bool),
+    /// A non-negative integer.
+    PosInt(u64),
+    /// A negative integer.
+    ///
+    /// Only negative integers are expected here, as `Value` keeps the others as positive: a
+    /// `NegInt(5)` converts to the same `Value` as a `PosInt(5)`.
+    NegInt(i64),
+    /// A 32-bit floating point number.
+    F32(f32),
+    /// A 64-bit floating point number.
+    F64(f64),
+    /// A string, whether valid UTF-8 or not.
+    String(Box<Utf8String>),
+    /// A byte array.
+    Binary(Box<Vec<u8>>),
+    /// A sequence of values.
+    Array(Box<Vec<SmallValue>>),
+    /// Key-value pairs of values.
+    Map(Box<Vec<(SmallValue, SmallValue)>>),
+    /// An extension: its type and data.
+    Ext(Box<(i8, Vec<u8>)>),
+    /// Any other value, such as a [`Value::Raw`], kept as is.
+    Other(Box<Value>),
+}
+
+impl SmallValue {
+    /// Returns the integer as `i64` if it is one and fits.
+    #[must_use]
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            SmallValue::PosInt(n) => i64::try_from(n).ok(),
+            SmallValue::NegInt(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Returns the integer as `u64` if it is one and is non-negative.
+    #[must_use]
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            SmallValue::PosInt(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Returns the string if it is one and is valid UTF-8.
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match *self {
+            SmallValue::String(ref s) => s.as_str(),
+            _ => None,
+        }
+    }
+
+    /// Returns the elements if it is an array.
+    #[must_use]
+    pub fn as_array(&self) -> Option<&[SmallValue]> {
+        match *self {
+            SmallValue::Array(ref vec) => Some(vec),
+            _ => None,
+        }
+    }
+
+    /// Returns the entries if it is a map.
+    #[must_use]
+    pub fn as_map(&self) -> Option<&[(SmallValue, SmallValue)]> {
+        match *self {
+            SmallValue::Map(ref map) => Some(map),
+            _ => None,
+        }
+    }
+}
+
+impl From<Value> for SmallValue {
+    fn from(val: Value) -> Self {
+        match val {
+            Value::Nil => SmallValue::Nil,
+            Value::Boolean(val) => SmallValue::Boolean(val),
+            Value::Integer(Integer { n: IntPriv::PosInt(n) }) => SmallValue::PosInt(n),
+            Value::Integer(Integer { n: IntPriv::NegInt(n) }) => SmallValue::NegInt(n),
+            Value::F32(val) => SmallValue::F32(val),
+            Value::F64(val) => SmallValue::F64(val),
+            Value::String(s) => SmallValue::String(Box::new(s)),
+            Value::Binary(buf) => SmallValue::Binary(Box::new(buf)),
+            Value::Array(vec) => SmallValue::Array(Box::new(vec.into_iter().map(SmallValue::from).collect())),
+            Value::Map(map) => {
+                let map = map.into_iter().map(|(key, val)| (SmallValue::from(key), SmallValue::from(val)));
+                SmallValue::Map(Box::new(map.collect()))
+            }
+            Value::Ext(ty, buf) => SmallValue::Ext(Box::new((ty, buf))),
+            val @ (Value::Raw(..) | Value::BinaryRef { .. }) => SmallValue::Other(Box::new(val)),
+        }
+    }
+}
+
+impl From<SmallValue> for Value {
+    fn from(val: SmallValue) -> Self {
+        match val {
+            SmallValue::Nil => Value::Nil,
+            SmallValue::Boolean(val) => Value::Boolean(val),
+            SmallValue::PosInt(n) => Value::from(n),
+            SmallValue::NegInt(n) => Value::from(n),
+            SmallValue::F32(val) => Value::F32(val),
+            SmallValue::F64(val) => Value::F64(val),
+            SmallValue::String(s) => Value::String(*s),
+            SmallValue::Binary(buf) => Value::Binary(*buf),
+            SmallValue::Array(vec) => Value::Array(vec.into_iter().map(Value::from).collect()),
+            SmallValue::Map(map) => {
+                Value::Map(map.into_iter().map(|(key, val)| (Value::from(key), Value::from(val))).collect())
+            }
+            SmallValue::Ext(ext) => {
+                let (ty, buf) = *ext;
+                Value::Ext(ty, buf)
+            }
+            SmallValue::Other(val) => *val,
+        }
+    }
+}
@@ -0,0 +1,222 @@
+//! A compact encoding of [`Value`] trees with repeated strings.
+//!
+//! With [`write_value`], every string is written in full the first time only. Its repetitions
+//! within the same value are written as a reference: an ext of type [`EXT_TYPE`] whose payload is
+//! the index of the string in the table of strings written so far, in big-endian using the fewest
+//! of 1, 2 or 4 bytes, that is a fixext1, fixext2 or fixext4.
+//!
+//! The table holds, in the order they are written, the valid UTF-8 strings that are at least
+//! [`MIN_LEN`] bytes long: shorter ones take no more space than a reference. Values are walked
+//! depth first, map keys before their values, which is the order of their bytes in the output.
+//!
+//! The format is specific to this crate, so it must be read back with [`read_value`], which
+//! resolves the references. Other decoders see them as plain ext values. The strings the
+//! references resolve to share the allocation of the table entry, so that a small input can't
+//! decode into many copies of a long string.
+//!
+//! # Examples
+//!
+//! ```
+//! use rmpv::{string_table, Value};
+//!
+//! let val = Value::Array(vec![Value::from("category"); 10]);
+//!
+//! let mut buf = Vec::new();
+//! string_table::write_value(&mut buf, &val).unwrap();
+//!
+//! // The string itself, then 9 references of 3 bytes each.
+//! assert_eq!(1 + 9 + 9 * 3, buf.len());
+//! assert_eq!(val, string_table::read_value(&mut &buf[..]).unwrap());
+//! ```
+
+use std::collections::HashMap;
+use std::io::{self, ErrorKind, Read, Write};
+use std::slice;
+use std::sync::Arc;
+
+use rmp::encode::{write_array_len, write_ext_meta, write_map_len};
+
+use crate::decode::{DecodeConfig, Error as DecodeError};
+use crate::encode::Error as EncodeError;
+use crate::Value;
+
+/// The ext type of the references to previously written strings.
+///
+/// Values to be written with [`write_value`] must not contain exts of this type themselves.
+pub const EXT_TYPE: i8 = 102;
+
+/// The minimum byte length of the strings kept in the table.
+pub const MIN_LEN: usize = 4;
+
+/// Encodes and attempts to write the given value, replacing repeated strings with references.
+///
+/// # Errors
+///
+/// This function will return [`EncodeError`](crate::encode::Error) on any I/O error while writing,
+/// and [`InvalidDataWrite`](crate::encode::Error::InvalidDataWrite) with the `InvalidInput` kind if
+/// the value contains an ext of type [`EXT_TYPE`], which couldn't be told apart from a reference,
+/// or a [`Value::Raw`], whose strings would be missing from the table while decoding.
+pub fn write_value<W: Write>(wr: &mut W, val: &Value) -> Result<(), EncodeError> {
+    write_value_inner(wr, val, &mut HashMap::new())
+}
+
+fn write_value_inner<'a, W: Write>(wr: &mut W, val: &'a Value, table: &mut HashMap<&'a str, u32>) -> Result<(), EncodeError> {
+    match *val {
+        Value::String(ref s) => match s.as_str() {
+            Some(s) if s.len() >= MIN_LEN => match table.get(s) {
+                Some(&idx) => write_ref(wr, idx),
+                None => {
+                    let idx = table.len() as u32;
+                    table.insert(s, idx);
+                    crate::encode::write_value(wr, val)
+                }
+            },
+            _ => crate::encode::write_value(wr, val),
+        },
+        Value::Array(ref vec) => {
+            write_array_len(wr, vec.len() as u32)?;
+            for v in vec {
+                write_value_inner(wr, v, table)?;
+            }
+            Ok(())
+        }
+        Value::Map(ref map) => {
+            write_map_len(wr, map.len() as u32)?;
+            for (key, val) in map {
+                write_value_inner(wr, key, table)?;
+                write_value_inner(wr, val, table)?;
+            }
+            Ok(())
+        }
+        Value::Ext(EXT_TYPE, ..) => Err(EncodeError::InvalidDataWrite(io::Error::new(
+            ErrorKind::InvalidInput,
+            "ext type reserved for string table references",
+        ))),
+        // The decoder registers the strings of the decoded tree, including the ones encoded in raw
+        // bytes, which the encoder doesn't see, so every later reference would be shifted.
+        Value::Raw(..) => Err(EncodeError::InvalidDataWrite(io::Error::new(
+            ErrorKind::InvalidInput,
+            "raw values can't be written with a string table",
+        ))),
+        _ => crate::encode::write_value(wr, val),
+    }
+}
+
+fn write_ref<W: Write>(wr: &mut W, idx: u32) -> Result<(), EncodeError> {
+    let bytes = idx.to_be_bytes();
+    let payload = if idx <= u32::from(u8::MAX) {
+        &bytes[3..]
+    } else if idx <= u32::from(u16::MAX) {
+        &bytes[2..]
+    } else {
+        &bytes[..]
+    };
+
+    write_ext_meta(wr, payload.len() as u32, EXT_TYPE)?;
+    wr.write_all(payload).map_err(EncodeError::InvalidDataWrite)
+}
+
+/// A container whose elements are being resolved.
+enum Frame<'a> {
+    Array(slice::IterMut<'a, Value>),
+    /// Remaining entries and the value of the entry whose key has just been resolved.
+    Map(slice::IterMut<'a, (Value, Value)>, Option<&'a mut Value>),
+}
+
+/// Attempts to read a value written by [`write_value`], replacing the references with the strings
+/// they refer to.
+///
+/// # Errors
+///
+/// In addition to the errors of [`decode::read_value`](crate::decode::read_value), returns
+/// [`Error::InvalidStringRef`](crate::decode::Error::InvalidStringRef) if a reference is malformed
+/// or refers to a string not written yet.
+pub fn read_value<R: Read>(rd: &mut R) -> Result<Value, DecodeError> {
+    read_value_with_config(rd, &DecodeConfig::default())
+}
+
+/// Attempts to read a value written by [`write_value`], applying the limits from `config`.
+///
+/// The strings the references resolve to are charged against [`DecodeConfig::max_total_alloc`]
+/// in full, like the rest of the value, although they share their allocation: the
+/// [`heap_size`](Value::heap_size) of the result never exceeds the limit.
+///
+/// # Errors
+///
+/// In addition to the errors of [`read_value`], returns
+/// [`Error::LengthLimitExceeded`](crate::decode::Error::LengthLimitExceeded) if the value with its
+/// references resolved exceeds [`DecodeConfig::max_total_alloc`].
+pub fn read_value_with_config<R: Read>(rd: &mut R, config: &DecodeConfig) -> Result<Value, DecodeError> {
+    let mut val = crate::decode::read_value_with_config(rd, config)?;
+    let mut alloc_remaining = match config.max_total_alloc {
+        Some(max) => Some(max.checked_sub(val.heap_size()).ok_or(DecodeError::LengthLimitExceeded)?),
+        None => None,
+    };
+
+    // Walk the tree in the order it was written, without recursion, so that deeply nested values
+    // can't overflow the call stack.
+    let mut table: Vec<Arc<str>> = Vec::new();
+    let mut stack = Vec::new();
+    let mut next = Some(&mut val);
+
+    loop {
+        let val = match next.take() {
+            Some(val) => val,
+            None => match stack.last_mut() {
+                None => break,
+                Some(Frame::Array(iter)) => match iter.next() {
+                    Some(val) => val,
+                    None => {
+                        stack.pop();
+                        continue;
+                    }
+                },
+                Some(Frame::Map(iter, pending)) => match pending.take() {
+                    Some(val) => val,
+                    None => match iter.next() {
+                        Some((key, val)) => {
+                            *pending = Some(val);
+                            key
+                        }
+                        None => {
+                            stack.pop();
+                            continue;
+                        }
+                    },
+                },
+            },
+        };
+
+        match *val {
+            Value::String(ref s) => {
+                if let Some(s) = s.as_str().filter(|s| s.len() >= MIN_LEN) {
+                    table.push(s.into());
+                }
+            }
+            Value::Ext(EXT_TYPE, ref buf) => {
+                let s = match buf.len() {
+                    1 | 2 | 4 => {
+                        let idx = buf.iter().fold(0usize, |acc, &b| (acc << 8) | usize::from(b));
+                        table.get(idx)
+                    }
+                    _ => None,
+                };
+                let s = match s {
+                    Some(s) => s,
+                    None => return Err(DecodeError::InvalidStringRef(buf.clone())),
+                };
+                if let Some(ref mut remaining) = alloc_remaining {
+                    // The payload of the reference is freed once it is replaced.
+                    let available = *remaining + buf.capacity();
+                    *remaining = available.checked_sub(s.len()).ok_or(DecodeError::LengthLimitExceeded)?;
+                }
+                *val = Value::String(Arc::clone(s).into());
+            }
+            Value::Array(ref mut vec) => stack.push(Frame::Array(vec.iter_mut())),
+            Value::Map(ref mut map) => stack.push(Frame::Map(map.iter_mut(), None)),
+            _ => {}
+        }
+    }
+
+    Ok(val)
+}
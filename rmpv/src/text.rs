@@ -0,0 +1,356 @@
+//! A readable text form of [`Value`] trees, for writing MessagePack test fixtures by hand.
+//!
+//! The syntax is JSON with a few additions:
+//!
+//! - `nil` (or `null`), `true` and `false`;
+//! - integers, such as `42` or `-1`, holding any `u64` or `i64`;
+//! - floats, such as `0.5` or `1e-3`, decoded as [`Value::F64`], or as [`Value::F32`] with an
+//!   `f32` suffix, e.g. `0.5f32`;
+//! - strings in double quotes, with the JSON escapes;
+//! - binaries as `b"..."`, holding an even number of hex digits, e.g. `b"c0ff"`;
+//! - extensions as `ext(type, b"...")`, e.g. `ext(-1, b"0102")`;
+//! - arrays as `[...]` and maps as `{key: value, ...}`, whose keys may be any value.
+//!
+//! # Examples
+//!
+//! ```
+//! use rmpv::text::parse_text;
+//! use rmpv::Value;
+//!
+//! let val = parse_text(r#"{"id": 42, "data": b"00ff", 1: [nil, ext(5, b"2a")]}"#).unwrap();
+//!
+//! assert_eq!(Value::from(42), val["id"]);
+//! assert_eq!(Value::Binary(vec![0x00, 0xff]), val["data"]);
+//! ```
+
+use std::error;
+use std::fmt::{self, Display, Formatter};
+
+use crate::decode::MAX_DEPTH;
+use crate::Value;
+
+/// An error returned by [`parse_text`], pointing at the offending byte of the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    offset: usize,
+    message: &'static str,
+}
+
+impl ParseError {
+    /// Returns the byte offset in the input where parsing failed.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(fmt, "{} at byte {}", self.message, self.offset)
+    }
+}
+
+impl error::Error for ParseError {}
+
+/// Parses the text form of a single value, surrounded by optional whitespace.
+///
+/// # Errors
+///
+/// Returns a [`ParseError`] if the input isn't exactly one value in the syntax described in the
+/// [module documentation](self), including when it is nested more than
+/// [`MAX_DEPTH`](crate::decode::MAX_DEPTH) levels deep.
+pub fn parse_text(s: &str) -> Result<Value, ParseError> {
+    let mut parser = Parser { s: s.as_bytes(), pos: 0 };
+
+    let val = parser.parse_value(MAX_DEPTH)?;
+    parser.skip_whitespace();
+    if parser.pos < parser.s.len() {
+        return parser.fail("trailing characters");
+    }
+
+    Ok(val)
+}
+
+struct Parser<'a> {
+    s: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn fail<T>(&self, message: &'static str) -> Result<T, ParseError> {
+        Err(ParseError { offset: self.pos, message })
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.s.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    /// Consumes `token` if the input continues with it.
+    fn eat(&mut self, token: &str) -> bool {
+        if self.s[self.pos..].starts_with(token.as_bytes()) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str, message: &'static str) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        if self.eat(token) {
+            Ok(())
+        } else {
+            self.fail(message)
+        }
+    }
+
+    /// Consumes a keyword, which must not be followed by more identifier characters.
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let end = self.pos + keyword.len();
+        let is_word = self.s[self.pos..].starts_with(keyword.as_bytes()) &&
+            !matches!(self.s.get(end), Some(b) if b.is_ascii_alphanumeric() || *b == b'_');
+        if is_word {
+            self.pos = end;
+        }
+        is_word
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<Value, ParseError> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            None => self.fail("expected a value"),
+            Some(b'[') => {
+                let depth = self.enter(depth)?;
+                let mut vec = Vec::new();
+                self.parse_seq(b']', |parser| {
+                    vec.push(parser.parse_value(depth)?);
+                    Ok(())
+                })?;
+                Ok(Value::Array(vec))
+            }
+            Some(b'{') => {
+                let depth = self.enter(depth)?;
+                let mut map = Vec::new();
+                self.parse_seq(b'}', |parser| {
+                    let key = parser.parse_value(depth)?;
+                    parser.expect(":", "expected ':'")?;
+                    map.push((key, parser.parse_value(depth)?));
+                    Ok(())
+                })?;
+                Ok(Value::Map(map))
+            }
+            Some(b'"') => self.parse_string().map(Value::from),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(..) if self.eat("b\"") => self.parse_hex().map(Value::Binary),
+            Some(..) if self.eat_keyword("nil") || self.eat_keyword("null") => Ok(Value::Nil),
+            Some(..) if self.eat_keyword("true") => Ok(Value::Boolean(true)),
+            Some(..) if self.eat_keyword("false") => Ok(Value::Boolean(false)),
+            Some(..) if self.eat_keyword("ext") => self.parse_ext(),
+            Some(..) => self.fail("expected a value"),
+        }
+    }
+
+    /// Consumes the opening bracket of a container, checking the nesting depth.
+    fn enter(&mut self, depth: usize) -> Result<usize, ParseError> {
+        if depth == 0 {
+            return self.fail("nesting too deep");
+        }
+        self.pos += 1;
+        Ok(depth - 1)
+    }
+
+    /// Parses comma-separated items with `item` until the closing bracket `end`.
+    fn parse_seq<F>(&mut self, end: u8, mut item: F) -> Result<(), ParseError>
+        where F: FnMut(&mut Self) -> Result<(), ParseError>
+    {
+        self.skip_whitespace();
+        if self.peek() == Some(end) {
+            self.pos += 1;
+            return Ok(());
+        }
+
+        loop {
+            item(self)?;
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b) if b == end => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                _ => return self.fail("expected ',' or a closing bracket"),
+            }
+        }
+    }
+
+    fn parse_ext(&mut self) -> Result<Value, ParseError> {
+        self.expect("(", "expected '('")?;
+        self.skip_whitespace();
+        let start = self.pos;
+        let ty = match self.parse_number()? {
+            Value::Integer(n) => n.as_i64().and_then(|n| i8::try_from(n).ok()),
+            _ => None,
+        };
+        let ty = match ty {
+            Some(ty) => ty,
+            None => {
+                self.pos = start;
+                return self.fail("expected an ext type in -128..=127");
+            }
+        };
+        self.expect(",", "expected ','")?;
+        self.expect("b\"", "expected ext data as b\"...\"")?;
+        let data = self.parse_hex()?;
+        self.expect(")", "expected ')'")?;
+
+        Ok(Value::Ext(ty, data))
+    }
+
+    /// Parses hex digits up to the closing quote, which has its opening quote consumed.
+    fn parse_hex(&mut self) -> Result<Vec<u8>, ParseError> {
+        let mut buf = Vec::new();
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(buf);
+                }
+                Some(..) => {
+                    match self.s.get(self.pos..self.pos + 2).and_then(hex_value) {
+                        Some(byte) => buf.push(byte as u8),
+                        None => return self.fail("expected a pair of hex digits"),
+                    }
+                    self.pos += 2;
+                }
+                None => return self.fail("unterminated binary"),
+            }
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Value, ParseError> {
+        let start = self.pos;
+        let mut is_float = false;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        while let Some(b) = self.peek() {
+            match b {
+                b'0'..=b'9' => {}
+                b'.' | b'e' | b'E' => is_float = true,
+                b'+' | b'-' if matches!(self.s[self.pos - 1], b'e' | b'E') => {}
+                _ => break,
+            }
+            self.pos += 1;
+        }
+        // The input is a `&str` and only ASCII has been consumed.
+        let num = std::str::from_utf8(&self.s[start..self.pos]).unwrap_or_default();
+
+        let val = if self.eat("f32") {
+            num.parse::<f32>().ok().map(Value::F32)
+        } else if is_float {
+            num.parse::<f64>().ok().map(Value::F64)
+        } else if num.starts_with('-') {
+            num.parse::<i64>().ok().map(Value::from)
+        } else {
+            num.parse::<u64>().ok().map(Value::from)
+        };
+
+        match val {
+            Some(val) => Ok(val),
+            None => {
+                self.pos = start;
+                self.fail("invalid or out of range number")
+            }
+        }
+    }
+
+    /// Parses a string, whose opening quote is the next character.
+    fn parse_string(&mut self) -> Result<String, ParseError> {
+        self.pos += 1;
+        let mut s = String::new();
+
+        loop {
+            let start = self.pos;
+            while let Some(b) = self.peek() {
+                if b == b'"' || b == b'\\' {
+                    break;
+                }
+                self.pos += 1;
+            }
+            // Stops are at ASCII characters, so the run is valid UTF-8.
+            s.push_str(std::str::from_utf8(&self.s[start..self.pos]).unwrap_or_default());
+
+            match self.peek() {
+                Some(b'"') => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some(..) => {
+                    self.pos += 1;
+                    s.push(self.parse_escape()?);
+                }
+                None => return self.fail("unterminated string"),
+            }
+        }
+    }
+
+    /// Parses an escape sequence, whose backslash has been consumed.
+    fn parse_escape(&mut self) -> Result<char, ParseError> {
+        let ch = match self.peek() {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{8}',
+            Some(b'f') => '\u{c}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                self.pos += 1;
+                let hi = self.parse_code_unit()?;
+                let code = if (0xd800..0xdc00).contains(&hi) {
+                    if !self.eat("\\u") {
+                        return self.fail("expected a low surrogate");
+                    }
+                    let lo = self.parse_code_unit()?;
+                    if !(0xdc00..0xe000).contains(&lo) {
+                        return self.fail("expected a low surrogate");
+                    }
+                    0x10000 + ((hi - 0xd800) << 10) + (lo - 0xdc00)
+                } else {
+                    hi
+                };
+                return match char::from_u32(code) {
+                    Some(ch) => Ok(ch),
+                    None => self.fail("invalid unicode escape"),
+                };
+            }
+            _ => return self.fail("invalid escape"),
+        };
+        self.pos += 1;
+
+        Ok(ch)
+    }
+
+    fn parse_code_unit(&mut self) -> Result<u32, ParseError> {
+        match self.s.get(self.pos..self.pos + 4).and_then(hex_value) {
+            Some(unit) => {
+                self.pos += 4;
+                Ok(unit)
+            }
+            None => self.fail("expected 4 hex digits"),
+        }
+    }
+}
+
+/// Returns the value of the given hex digits, which all must be ones.
+fn hex_value(hex: &[u8]) -> Option<u32> {
+    hex.iter().try_fold(0, |acc, &b| Some((acc << 4) | char::from(b).to_digit(16)?))
+}
@@ -0,0 +1,237 @@
+//! The timestamp extension of the MessagePack specification, and its conversions to and from
+//! [`SystemTime`].
+//!
+//! A timestamp is an ext of type [`EXT_TYPE`] holding a number of seconds since the Unix epoch and
+//! the nanoseconds within that second, in one of three forms:
+//!
+//! - a fixext4 with the seconds as a `u32`, for times from the epoch to 2106 without nanoseconds;
+//! - a fixext8 with the nanoseconds in the upper 30 bits and the seconds in the lower 34 bits of a
+//!   `u64`, for times from the epoch to 2514;
+//! - an ext8 of 12 bytes with the nanoseconds as a `u32` followed by the seconds as an `i64`, for
+//!   any other time, including the ones before the epoch.
+//!
+//! All numbers are big-endian. [`write_timestamp`] uses the shortest form that holds the time.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::{Duration, UNIX_EPOCH};
+//!
+//! use rmpv::timestamp::{read_system_time, write_system_time};
+//!
+//! let time = UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+//!
+//! let mut buf = Vec::new();
+//! write_system_time(&mut buf, time).unwrap();
+//!
+//! assert_eq!(vec![0xd6, 0xff, 0x3b, 0x9a, 0xca, 0x00], buf);
+//! assert_eq!(time, read_system_time(&mut &buf[..]).unwrap());
+//! ```
+
+use std::io::{self, ErrorKind, Read, Write};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rmp::decode::{read_ext_meta, ValueReadError};
+use rmp::encode::write_ext_meta;
+
+use crate::decode::Error as DecodeError;
+use crate::encode::Error as EncodeError;
+use crate::Value;
+
+/// The ext type of timestamps, reserved by the MessagePack specification.
+pub const EXT_TYPE: i8 = -1;
+
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+
+/// A point in time, as the number of seconds since the Unix epoch and the nanoseconds within that
+/// second.
+///
+/// The nanoseconds always count forward, so a time half a second before the epoch has `-1`
+/// seconds and `500_000_000` nanoseconds.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp {
+    secs: i64,
+    nanos: u32,
+}
+
+impl Timestamp {
+    /// The Unix epoch, 1970-01-01 00:00:00 UTC.
+    pub const UNIX_EPOCH: Timestamp = Timestamp { secs: 0, nanos: 0 };
+
+    /// Creates a timestamp, returning `None` unless `nanos` is less than a second.
+    #[inline]
+    #[must_use]
+    pub fn new(secs: i64, nanos: u32) -> Option<Self> {
+        if nanos < NANOS_PER_SEC {
+            Some(Self { secs, nanos })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the number of seconds since the Unix epoch, negative before it.
+    #[inline]
+    #[must_use]
+    pub fn secs(&self) -> i64 {
+        self.secs
+    }
+
+    /// Returns the nanoseconds within the second.
+    #[inline]
+    #[must_use]
+    pub fn nanos(&self) -> u32 {
+        self.nanos
+    }
+
+    /// Converts the given time into a timestamp, returning `None` if it is more than `i64::MAX`
+    /// seconds away from the epoch.
+    #[must_use]
+    pub fn from_system_time(time: SystemTime) -> Option<Self> {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => {
+                let secs = i64::try_from(since.as_secs()).ok()?;
+                Some(Self { secs, nanos: since.subsec_nanos() })
+            }
+            Err(err) => {
+                let before = err.duration();
+                let secs = i64::try_from(before.as_secs()).ok()?;
+                match before.subsec_nanos() {
+                    0 => Some(Self { secs: -secs, nanos: 0 }),
+                    nanos => Some(Self { secs: (-secs).checked_sub(1)?, nanos: NANOS_PER_SEC - nanos }),
+                }
+            }
+        }
+    }
+
+    /// Converts the timestamp into a [`SystemTime`], returning `None` if it is out of the range
+    /// the platform can represent.
+    #[must_use]
+    pub fn to_system_time(&self) -> Option<SystemTime> {
+        let nanos = Duration::from_nanos(self.nanos.into());
+        if self.secs >= 0 {
+            UNIX_EPOCH.checked_add(Duration::from_secs(self.secs as u64))?.checked_add(nanos)
+        } else {
+            UNIX_EPOCH.checked_sub(Duration::from_secs(self.secs.unsigned_abs()))?.checked_add(nanos)
+        }
+    }
+
+    /// Decodes the payload of a timestamp ext, returning `None` if it isn't of one of the three
+    /// lengths or holds a nanosecond count of a second or more.
+    #[must_use]
+    pub fn from_ext_data(data: &[u8]) -> Option<Self> {
+        match data.len() {
+            4 => {
+                let secs = u32::from_be_bytes(data.try_into().ok()?);
+                Some(Self { secs: secs.into(), nanos: 0 })
+            }
+            8 => {
+                let val = u64::from_be_bytes(data.try_into().ok()?);
+                Self::new((val & 0x3_ffff_ffff) as i64, (val >> 34) as u32)
+            }
+            12 => {
+                let nanos = u32::from_be_bytes(data[..4].try_into().ok()?);
+                let secs = i64::from_be_bytes(data[4..].try_into().ok()?);
+                Self::new(secs, nanos)
+            }
+            _ => None,
+        }
+    }
+
+    /// Encodes the timestamp into the payload of a timestamp ext, in the shortest form that holds
+    /// it.
+    #[must_use]
+    pub fn to_ext_data(&self) -> Vec<u8> {
+        if self.secs >> 34 == 0 {
+            if self.nanos == 0 && self.secs <= i64::from(u32::MAX) {
+                (self.secs as u32).to_be_bytes().to_vec()
+            } else {
+                ((u64::from(self.nanos) << 34) | self.secs as u64).to_be_bytes().to_vec()
+            }
+        } else {
+            let mut buf = Vec::with_capacity(12);
+            buf.extend_from_slice(&self.nanos.to_be_bytes());
+            buf.extend_from_slice(&self.secs.to_be_bytes());
+            buf
+        }
+    }
+
+    /// Returns the timestamp held by the given value, if it is a well-formed timestamp ext.
+    #[must_use]
+    pub fn from_value(val: &Value) -> Option<Self> {
+        match *val {
+            Value::Ext(EXT_TYPE, ref data) => Self::from_ext_data(data),
+            _ => None,
+        }
+    }
+}
+
+impl From<Timestamp> for Value {
+    #[inline]
+    fn from(ts: Timestamp) -> Value {
+        Value::Ext(EXT_TYPE, ts.to_ext_data())
+    }
+}
+
+/// Attempts to read a timestamp ext in any of its three forms.
+///
+/// # Errors
+///
+/// This function will return [`Error`](crate::decode::Error) on any I/O error while reading,
+/// [`InvalidMarkerRead`](crate::decode::Error::InvalidMarkerRead) if the value isn't an ext of type
+/// [`EXT_TYPE`], and [`InvalidDataRead`](crate::decode::Error::InvalidDataRead) with the
+/// `InvalidData` kind if its payload isn't a valid timestamp.
+pub fn read_timestamp<R: Read>(rd: &mut R) -> Result<Timestamp, DecodeError> {
+    let meta = read_ext_meta(rd)?;
+    if meta.typeid != EXT_TYPE {
+        return Err(ValueReadError::UnexpectedExtType { expected: EXT_TYPE, found: meta.typeid }.into());
+    }
+    if !matches!(meta.size, 4 | 8 | 12) {
+        return Err(invalid_data("timestamp of invalid length"));
+    }
+
+    let mut buf = [0; 12];
+    let data = &mut buf[..meta.size as usize];
+    rd.read_exact(data).map_err(DecodeError::InvalidDataRead)?;
+
+    Timestamp::from_ext_data(data).ok_or_else(|| invalid_data("timestamp nanoseconds out of range"))
+}
+
+/// Attempts to read a timestamp ext as a [`SystemTime`].
+///
+/// # Errors
+///
+/// In addition to the errors of [`read_timestamp`], returns
+/// [`InvalidDataRead`](crate::decode::Error::InvalidDataRead) with the `InvalidData` kind if the
+/// time is out of the range of `SystemTime` on this platform.
+pub fn read_system_time<R: Read>(rd: &mut R) -> Result<SystemTime, DecodeError> {
+    read_timestamp(rd)?.to_system_time().ok_or_else(|| invalid_data("timestamp out of range of SystemTime"))
+}
+
+fn invalid_data(msg: &'static str) -> DecodeError {
+    DecodeError::InvalidDataRead(io::Error::new(ErrorKind::InvalidData, msg))
+}
+
+/// Encodes and attempts to write a timestamp ext, in the shortest form that holds it.
+///
+/// # Errors
+///
+/// This function will return [`Error`](crate::encode::Error) on any I/O error while writing.
+pub fn write_timestamp<W: Write>(wr: &mut W, ts: Timestamp) -> Result<(), EncodeError> {
+    let data = ts.to_ext_data();
+    write_ext_meta(wr, data.len() as u32, EXT_TYPE)?;
+    wr.write_all(&data).map_err(EncodeError::InvalidDataWrite)
+}
+
+/// Encodes and attempts to write the given time as a timestamp ext.
+///
+/// # Errors
+///
+/// In addition to the errors of [`write_timestamp`], returns
+/// [`InvalidDataWrite`](crate::encode::Error::InvalidDataWrite) with the `InvalidInput` kind if the
+/// time is more than `i64::MAX` seconds away from the epoch.
+pub fn write_system_time<W: Write>(wr: &mut W, time: SystemTime) -> Result<(), EncodeError> {
+    let ts = Timestamp::from_system_time(time).ok_or_else(|| {
+        EncodeError::InvalidDataWrite(io::Error::new(ErrorKind::InvalidInput, "time out of range of a timestamp"))
+    })?;
+    write_timestamp(wr, ts)
+}
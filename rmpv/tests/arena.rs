@@ -0,0 +1,80 @@
+#![cfg(feature = "bumpalo")]
+
+use bumpalo::Bump;
+
+use rmpv::decode::{read_value_in, ArenaValue, Error};
+use rmpv::encode::write_value;
+use rmpv::Value;
+
+#[test]
+fn from_nested_value_read_value_in() {
+    let val = Value::Map(vec![
+        (Value::from("name"), Value::from("le message")),
+        (Value::from(1), Value::Array(vec![Value::Nil, Value::from(-42), Value::F64(1.5), Value::Boolean(true)])),
+        (Value::Binary(vec![1, 2, 3]), Value::Ext(5, vec![0xff; 4])),
+    ]);
+    let mut buf = Vec::new();
+    write_value(&mut buf, &val).unwrap();
+
+    let arena = Bump::new();
+    let decoded = read_value_in(&mut &buf[..], &arena).unwrap();
+
+    assert_eq!(val, decoded.to_owned());
+    match decoded {
+        ArenaValue::Map(entries) => assert_eq!(ArenaValue::Binary(&[1, 2, 3]), entries[2].0),
+        other => panic!("unexpected value: {:?}", other),
+    }
+}
+
+#[test]
+fn from_messages_read_value_in_reused_arena() {
+    let mut arena = Bump::new();
+
+    for n in 0..100u64 {
+        let val = Value::Array(vec![Value::from(n), Value::from("message")]);
+        let mut buf = Vec::new();
+        write_value(&mut buf, &val).unwrap();
+
+        assert_eq!(val, read_value_in(&mut &buf[..], &arena).unwrap().to_owned());
+        arena.reset();
+    }
+}
+
+#[test]
+fn from_invalid_utf8_read_value_in() {
+    let buf = [0xa2, 0xc3, 0x28];
+    let arena = Bump::new();
+
+    match read_value_in(&mut &buf[..], &arena).unwrap() {
+        ArenaValue::String(s) => {
+            assert!(s.is_err());
+            assert_eq!([0xc3, 0x28], s.as_bytes());
+        }
+        other => panic!("unexpected value: {:?}", other),
+    }
+}
+
+#[test]
+fn from_truncated_bin_read_value_in() {
+    // A bin32 declaring 4 GiB, with only 2 bytes following.
+    let buf = [0xc6, 0xff, 0xff, 0xff, 0xff, 0x01, 0x02];
+    let arena = Bump::new();
+
+    match read_value_in(&mut &buf[..], &arena) {
+        Err(Error::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    // Only as much as arrived has been allocated, rather than the declared length.
+    assert!(arena.allocated_bytes() < 1024 * 1024);
+}
+
+#[test]
+fn from_deeply_nested_read_value_in() {
+    let buf = vec![0x91; rmpv::decode::MAX_DEPTH + 1];
+    let arena = Bump::new();
+
+    match read_value_in(&mut &buf[..], &arena) {
+        Err(Error::DepthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
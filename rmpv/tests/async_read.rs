@@ -0,0 +1,73 @@
+#![cfg(feature = "tokio")]
+
+use std::time::Duration;
+
+use rmpv::decode::{read_value_async, Error};
+use rmpv::Value;
+use tokio::io::AsyncWriteExt;
+
+#[tokio::test]
+async fn from_two_chunks_read_value_async() {
+    // ["le message", [1, 2, 3]]
+    let buf: &[u8] = &[
+        0x92, 0xaa, 0x6c, 0x65, 0x20, 0x6d, 0x65, 0x73, 0x73, 0x61, 0x67, 0x65, 0x93, 0x01, 0x02,
+        0x03,
+    ];
+    let (head, tail) = buf.split_at(6);
+
+    let (mut tx, mut rx) = tokio::io::duplex(64);
+    let writer = tokio::spawn(async move {
+        tx.write_all(head).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        tx.write_all(tail).await.unwrap();
+        tx.write_all(&[0xc0]).await.unwrap();
+        tx
+    });
+
+    let expected = Value::Array(vec![
+        Value::from("le message"),
+        Value::Array(vec![Value::from(1), Value::from(2), Value::from(3)]),
+    ]);
+    assert_eq!(expected, read_value_async(&mut rx).await.unwrap());
+
+    // Bytes following the value stay in the reader.
+    let _tx = writer.await.unwrap();
+    assert_eq!(Value::Nil, read_value_async(&mut rx).await.unwrap());
+}
+
+#[tokio::test]
+async fn from_truncated_read_value_async() {
+    let buf: &[u8] = &[0x92, 0x01];
+
+    match read_value_async(&mut &buf[..]).await {
+        Err(Error::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn from_map32_read_value_async() {
+    let buf: &[u8] = &[0xdf, 0x00, 0x00, 0x00, 0x01, 0xc4, 0x02, 0x01, 0x02, 0xd7, 0x05, 0, 0, 0, 0, 0, 0, 0, 1];
+
+    let expected = Value::Map(vec![
+        (Value::Binary(vec![1, 2]), Value::Ext(5, vec![0, 0, 0, 0, 0, 0, 0, 1])),
+    ]);
+    assert_eq!(expected, read_value_async(&mut &buf[..]).await.unwrap());
+}
+
+#[tokio::test]
+async fn from_many_small_pieces_read_value_async() {
+    let val = Value::Array((0..10_000).map(|i| Value::from(format!("item {}", i))).collect());
+    let mut buf = Vec::new();
+    rmpv::encode::write_value(&mut buf, &val).unwrap();
+
+    // A tiny pipe hands the value over in thousands of pieces.
+    let (mut tx, mut rx) = tokio::io::duplex(7);
+    let writer = tokio::spawn(async move {
+        tx.write_all(&buf).await.unwrap();
+        tx
+    });
+
+    assert_eq!(val, read_value_async(&mut rx).await.unwrap());
+    let _tx = writer.await.unwrap();
+}
@@ -0,0 +1,62 @@
+use rmpv::builder::BuildError;
+use rmpv::Value;
+
+#[test]
+fn build_array() {
+    let mut builder = Value::array_builder(3);
+    builder.push(Value::Nil).push(42).push("le message");
+
+    let expected = Value::Array(vec![Value::Nil, Value::from(42), Value::from("le message")]);
+    assert_eq!(expected, builder.finish().unwrap());
+}
+
+#[test]
+fn build_array_length_mismatch() {
+    let mut builder = Value::array_builder(3);
+    builder.push(1).push(2);
+
+    assert_eq!(BuildError::LengthMismatch { declared: 3, actual: 2 }, builder.finish().unwrap_err());
+
+    let mut builder = Value::array_builder(1);
+    builder.push(1).push(2);
+
+    assert_eq!(BuildError::LengthMismatch { declared: 1, actual: 2 }, builder.finish().unwrap_err());
+}
+
+#[test]
+fn build_map() {
+    let mut builder = Value::map_builder(2);
+    builder.key("id").unwrap().value(42).unwrap();
+    builder.entry("tags", Value::Array(vec![Value::from("a")])).unwrap();
+
+    let expected = Value::Map(vec![
+        (Value::from("id"), Value::from(42)),
+        (Value::from("tags"), Value::Array(vec![Value::from("a")])),
+    ]);
+    assert_eq!(expected, builder.finish().unwrap());
+}
+
+#[test]
+fn build_map_dangling_key() {
+    let mut builder = Value::map_builder(1);
+    builder.entry("id", 42).unwrap();
+    builder.key("name").unwrap();
+
+    assert_eq!(BuildError::DanglingKey, builder.clone().key("other").unwrap_err());
+    assert_eq!(BuildError::DanglingKey, builder.finish().unwrap_err());
+}
+
+#[test]
+fn build_map_missing_key() {
+    let mut builder = Value::map_builder(1);
+
+    assert_eq!(BuildError::MissingKey, builder.value(42).unwrap_err());
+}
+
+#[test]
+fn build_map_length_mismatch() {
+    let mut builder = Value::map_builder(2);
+    builder.entry("id", 42).unwrap();
+
+    assert_eq!(BuildError::LengthMismatch { declared: 2, actual: 1 }, builder.finish().unwrap_err());
+}
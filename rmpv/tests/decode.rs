@@ -1,4 +1,18 @@
-use rmpv::decode::{read_value, Error};
+use std::collections::HashSet;
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::sync::Arc;
+
+use rmp::{Marker, MarkerCategory};
+use rmpv::decode::{
+    self, copy_value, decode_to_channel, expect_value, read_either, read_entries, read_integer, read_integer_strict,
+    read_map_field, read_map_recovering, read_pair, read_records_columnar, read_scalar, read_scalar_array,
+    read_str_max_chars, read_string_map, read_string_map_interned, read_struct_with, read_tagged, read_triple,
+    read_until_nil, read_value, read_value_annotated, read_value_index, read_value_seekable,
+    read_value_with_binary_refs, read_value_with_config, read_value_with_strings, read_value_with_warnings, Annotation,
+    ContainerKind, DecodeConfig, DecodeEvent, DecodeWarning, DecodeWarningKind, Either, Error, FieldTable, KeyInterner,
+    Scalar,
+};
+use rmpv::encode::write_value;
 use rmpv::Value;
 
 #[test]
@@ -62,6 +76,27 @@ fn from_i32_decode_value() {
     assert_eq!(Value::from(-1), read_value(&mut &buf[..]).unwrap());
 }
 
+#[test]
+fn from_i8_decode_value() {
+    let buf = [0xd0, 0xdf];
+    let mut rd = &buf[..];
+
+    assert_eq!(Value::from(-33), read_value(&mut rd).unwrap());
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn from_u64_over_i64_max_decode_value() {
+    let buf = [0xcf, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    let mut rd = &buf[..];
+
+    let val = read_value(&mut rd).unwrap();
+    assert_eq!(Value::from(1u64 << 63), val);
+    assert_eq!(Some(1u64 << 63), val.as_u64());
+    assert_eq!(None, val.as_i64());
+    assert!(rd.is_empty());
+}
+
 #[test]
 fn from_f64_decode_value() {
     let buf = [0xcb, 0xff, 0xf0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
@@ -99,7 +134,7 @@ fn from_fixarray_incomplete_decode_value() {
     ];
 
     match read_value(&mut &buf[..]) {
-        Err(Error::InvalidMarkerRead(..)) => {}
+        Err(Error::TruncatedContainer { kind: ContainerKind::Array, index: 2, expected: 3 }) => {}
         other => panic!("unexpected result: {:?}", other),
     }
 }
@@ -393,7 +428,1495 @@ fn invalid_buf_size_arr() {
     ];
     match read_value(&mut &buf[..]) {
         Ok(_) => panic!("Unexpected success"),
-        Err(Error::InvalidMarkerRead(_)) => { /* expected */ },
+        Err(Error::TruncatedContainer { kind: ContainerKind::Array, index: 0, expected: 4294967295 }) => { /* expected */ },
         Err(e) => panic!("Unexpected error: {}", e),
     }
 }
+
+#[test]
+fn from_fixarray_of_two_read_pair() {
+    let buf = [0x92, 0x2a, 0xa3, 0x6b, 0x65, 0x79];
+    let mut rd = &buf[..];
+
+    let pair = read_pair(&mut rd, read_value, read_value).unwrap();
+
+    assert_eq!((Value::from(42), Value::from("key")), pair);
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn from_fixarray_of_three_read_pair() {
+    let buf = [0x93, 0x01, 0x02, 0x03];
+
+    match read_pair(&mut &buf[..], read_value, read_value) {
+        Err(Error::InvalidLength(3)) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_fixarray_of_three_read_triple() {
+    let buf = [0x93, 0x01, 0xc3, 0xc0];
+
+    let triple = read_triple(&mut &buf[..], read_value, read_value, read_value).unwrap();
+
+    assert_eq!((Value::from(1), Value::Boolean(true), Value::Nil), triple);
+}
+
+#[test]
+fn from_map_within_total_alloc_limit() {
+    // {"a": bin8(16), "b": bin8(16)}
+    let mut buf = vec![0x82];
+    for key in [b'a', b'b'] {
+        buf.extend_from_slice(&[0xa1, key, 0xc4, 0x10]);
+        buf.extend_from_slice(&[0; 16]);
+    }
+
+    let config = DecodeConfig {
+        max_total_alloc: Some(1024),
+        ..DecodeConfig::default()
+    };
+    assert!(read_value_with_config(&mut &buf[..], &config).is_ok());
+}
+
+#[test]
+fn from_map_exceeding_total_alloc_limit() {
+    // 64 fields of 256 bytes each: every field fits the budget, but all of them together do not.
+    let mut buf = vec![0xde, 0x00, 0x40];
+    for key in 0..64u8 {
+        buf.extend_from_slice(&[key, 0xc5, 0x01, 0x00]);
+        buf.extend_from_slice(&[0; 256]);
+    }
+
+    let config = DecodeConfig {
+        max_total_alloc: Some(8 * 1024),
+        ..DecodeConfig::default()
+    };
+    match read_value_with_config(&mut &buf[..], &config) {
+        Err(Error::LengthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // Without a limit the same message decodes fine.
+    assert!(read_value_with_config(&mut &buf[..], &DecodeConfig::default()).is_ok());
+}
+
+#[test]
+fn from_array_of_tiny_strings_exceeding_allocation_limit() {
+    // 10000 one-byte strings: 20 KB in total, but one allocation each.
+    let mut buf = vec![0xdc, 0x27, 0x10];
+    for _ in 0..10000 {
+        buf.extend_from_slice(&[0xa1, b'x']);
+    }
+
+    let config = DecodeConfig {
+        max_total_alloc: Some(1024 * 1024),
+        max_allocations: Some(1000),
+        ..DecodeConfig::default()
+    };
+    match read_value_with_config(&mut &buf[..], &config) {
+        Err(Error::LengthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // The array itself and every string count, empty values don't.
+    let config = DecodeConfig {
+        max_allocations: Some(10001),
+        ..DecodeConfig::default()
+    };
+    assert!(read_value_with_config(&mut &buf[..], &config).is_ok());
+
+    let buf = [0x93, 0xa0, 0xc4, 0x00, 0x90];
+    let config = DecodeConfig {
+        max_allocations: Some(1),
+        ..DecodeConfig::default()
+    };
+    assert!(read_value_with_config(&mut &buf[..], &config).is_ok());
+}
+
+#[test]
+fn from_nested_oversized_headers_exceeding_declared_product() {
+    // A map32 of 2^32 - 1 entries whose first key is an array32 of 2^32 - 1 elements, whose first
+    // element is a map16 of 65535 entries: 11 bytes announcing ~2^80 values.
+    let buf = [0xdf, 0xff, 0xff, 0xff, 0xff, 0xdd, 0xff, 0xff, 0xff, 0xff, 0xde, 0xff, 0xff];
+    let config = DecodeConfig {
+        max_declared_product: Some(1 << 40),
+        ..DecodeConfig::default()
+    };
+
+    match read_value_with_config(&mut &buf[..], &config) {
+        Err(Error::LengthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    // Without the limit, it's only rejected at the end of the input.
+    match read_value_with_config(&mut &buf[..], &DecodeConfig::default()) {
+        Err(Error::TruncatedContainer { .. }) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_wide_containers_within_declared_product() {
+    // The product applies along the nesting, so siblings don't add up: [[1, 2, 3], [4, 5, 6]].
+    let buf = [0x92, 0x93, 0x01, 0x02, 0x03, 0x93, 0x04, 0x05, 0x06];
+    let config = DecodeConfig {
+        max_declared_product: Some(6),
+        ..DecodeConfig::default()
+    };
+    assert!(read_value_with_config(&mut &buf[..], &config).is_ok());
+
+    let config = DecodeConfig {
+        max_declared_product: Some(5),
+        ..DecodeConfig::default()
+    };
+    match read_value_with_config(&mut &buf[..], &config) {
+        Err(Error::LengthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Shape {
+    Circle(Value),
+    Rect(Value, Value),
+}
+
+fn read_shape(buf: &[u8]) -> Result<Shape, Error> {
+    read_tagged(&mut &buf[..], |tag, rd| match tag {
+        0 => Ok(Shape::Circle(read_value(rd)?)),
+        1 => read_pair(rd, read_value, read_value).map(|(w, h)| Shape::Rect(w, h)),
+        _ => panic!("unexpected tag: {}", tag),
+    })
+}
+
+#[test]
+fn from_tagged_array_read_tagged() {
+    assert_eq!(Shape::Circle(Value::from(5)), read_shape(&[0x92, 0x00, 0x05]).unwrap());
+    assert_eq!(Shape::Rect(Value::from(2), Value::from(3)),
+               read_shape(&[0x92, 0x01, 0x92, 0x02, 0x03]).unwrap());
+}
+
+#[test]
+fn from_fixarray_of_three_read_tagged() {
+    match read_shape(&[0x93, 0x00, 0x05, 0x06]) {
+        Err(Error::InvalidLength(3)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_array32_exceeding_input_read_value_seekable() {
+    // Array32 claiming 1e6 elements on a 10-byte buffer.
+    let buf = [0xdd, 0x00, 0x0f, 0x42, 0x40, 0x01, 0x02, 0x03, 0x04, 0x05];
+    let mut cur = Cursor::new(&buf[..]);
+
+    match read_value_seekable(&mut cur, &DecodeConfig::default()) {
+        Err(Error::LengthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(5, cur.position());
+
+    // A plain reader only fails once it runs out of data.
+    match read_value(&mut &buf[..]) {
+        Err(Error::TruncatedContainer { kind: ContainerKind::Array, index: 5, expected: 1000000 }) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_nested_array32_exceeding_rest_of_input_read_value_seekable() {
+    // [<10 bytes>, <Array32 claiming 10 elements>], with only 3 bytes left after the latter's header.
+    let mut buf = vec![0x92, 0xc4, 0x0a];
+    buf.extend_from_slice(&[0; 10]);
+    buf.extend_from_slice(&[0xdd, 0x00, 0x00, 0x00, 0x0a, 0x01, 0x02, 0x03]);
+    let mut cur = Cursor::new(&buf[..]);
+
+    match read_value_seekable(&mut cur, &DecodeConfig::default()) {
+        Err(Error::LengthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(18, cur.position());
+}
+
+#[test]
+fn from_array_fitting_input_read_value_seekable() {
+    let buf = [0xff, 0xdd, 0x00, 0x00, 0x00, 0x02, 0x01, 0x02];
+    let mut cur = Cursor::new(&buf[..]);
+    cur.set_position(1);
+
+    let val = read_value_seekable(&mut cur, &DecodeConfig::default()).unwrap();
+    assert_eq!(Value::Array(vec![Value::from(1), Value::from(2)]), val);
+}
+
+#[test]
+fn from_f64_integral_coerce_integral_floats() {
+    // 3.0
+    let buf = [0xcb, 0x40, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    let config = DecodeConfig {
+        coerce_integral_floats: true,
+        ..DecodeConfig::default()
+    };
+
+    assert_eq!(Value::from(3), read_value_with_config(&mut &buf[..], &config).unwrap());
+    assert_eq!(Value::F64(3.0), read_value(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn from_f64_fractional_coerce_integral_floats() {
+    // 3.5
+    let buf = [0xcb, 0x40, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    let config = DecodeConfig {
+        coerce_integral_floats: true,
+        ..DecodeConfig::default()
+    };
+
+    assert_eq!(Value::F64(3.5), read_value_with_config(&mut &buf[..], &config).unwrap());
+}
+
+#[test]
+fn from_string_keyed_map_read_string_map() {
+    // {"id": 42, "name": "rmp", "tags": []}
+    let buf = [
+        0x83, 0xa2, 0x69, 0x64, 0x2a, 0xa4, 0x6e, 0x61, 0x6d, 0x65, 0xa3, 0x72, 0x6d, 0x70, 0xa4,
+        0x74, 0x61, 0x67, 0x73, 0x90,
+    ];
+
+    let expected = vec![
+        ("id".to_string(), Value::from(42)),
+        ("name".to_string(), Value::from("rmp")),
+        ("tags".to_string(), Value::Array(vec![])),
+    ];
+    assert_eq!(expected, read_string_map(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn from_int_keyed_map_read_string_map() {
+    // {"id": 42, 1: 2}
+    let buf = [0x82, 0xa2, 0x69, 0x64, 0x2a, 0x01, 0x02];
+
+    match read_string_map(&mut &buf[..]) {
+        Err(Error::NonStringKey(Marker::FixPos(1))) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_records_read_string_map_interned() {
+    // {"id": 1, "ok": true}, {"ok": false, "id": 2}
+    let buf = [
+        0x82, 0xa2, 0x69, 0x64, 0x01, 0xa2, 0x6f, 0x6b, 0xc3,
+        0x82, 0xa2, 0x6f, 0x6b, 0xc2, 0xa2, 0x69, 0x64, 0x02,
+    ];
+    let mut rd = &buf[..];
+    let mut keys = KeyInterner::new();
+
+    let first = read_string_map_interned(&mut rd, &mut keys).unwrap();
+    let second = read_string_map_interned(&mut rd, &mut keys).unwrap();
+
+    assert_eq!(("id", &Value::from(1)), (&*first[0].0, &first[0].1));
+    assert_eq!(("ok", &Value::from(false)), (&*second[0].0, &second[0].1));
+    assert!(Arc::ptr_eq(&first[0].0, &second[1].0));
+    assert!(Arc::ptr_eq(&first[1].0, &second[0].0));
+    assert_eq!(2, keys.len());
+}
+
+#[test]
+fn from_int_keyed_map_read_string_map_interned() {
+    let buf = [0x81, 0x01, 0x02];
+
+    match read_string_map_interned(&mut &buf[..], &mut KeyInterner::new()) {
+        Err(Error::NonStringKey(Marker::FixPos(1))) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_deeply_nested_array_decode_value() {
+    const DEPTH: usize = 10_000;
+
+    let mut buf = vec![0x91; DEPTH];
+    buf.push(0xc3);
+
+    let config = DecodeConfig {
+        max_depth: usize::MAX,
+        ..DecodeConfig::default()
+    };
+    let mut val = read_value_with_config(&mut &buf[..], &config).unwrap();
+
+    // Unwrap the arrays iteratively, since recursive comparisons could overflow the stack too.
+    for _ in 0..DEPTH {
+        val = match val {
+            Value::Array(mut vec) if vec.len() == 1 => vec.pop().unwrap(),
+            other => panic!("unexpected value: {:?}", other),
+        };
+    }
+    assert_eq!(Value::Boolean(true), val);
+
+    match read_value(&mut &buf[..]) {
+        Err(Error::DepthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn from_scalars_read_scalar() {
+    let buf = [
+        0xc0, 0xc3, 0xd0, 0xee, 0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xca, 0x3f,
+        0x00, 0x00, 0x00, 0xa2, 0x6f, 0x6b, 0xc4, 0x02, 0x01, 0x02,
+    ];
+    let mut rd = &buf[..];
+
+    assert_eq!(Scalar::Nil, read_scalar(&mut rd).unwrap());
+    assert_eq!(Scalar::Boolean(true), read_scalar(&mut rd).unwrap());
+    assert_eq!(Scalar::Integer((-18).into()), read_scalar(&mut rd).unwrap());
+    assert_eq!(Scalar::Integer(u64::MAX.into()), read_scalar(&mut rd).unwrap());
+    assert_eq!(Scalar::F32(0.5), read_scalar(&mut rd).unwrap());
+    assert_eq!(Scalar::String("ok".into()), read_scalar(&mut rd).unwrap());
+    assert_eq!(Scalar::Binary(vec![1, 2]), read_scalar(&mut rd).unwrap());
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn from_fixarray_read_scalar() {
+    let buf = [0x91, 0x01];
+    let mut rd = &buf[..];
+
+    match read_scalar(&mut rd) {
+        Err(Error::NotScalar(Marker::FixArray(1))) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!([0x01], rd);
+}
+
+#[test]
+fn from_fixpos_read_integer_strict() {
+    assert_eq!(Some(5), read_integer_strict(&mut &[0x05][..]).unwrap().as_u64());
+    assert_eq!(Some(-200), read_integer_strict(&mut &[0xd1, 0xff, 0x38][..]).unwrap().as_i64());
+}
+
+#[test]
+fn from_u32_holding_fixpos_read_integer_strict() {
+    let buf = [0xce, 0x00, 0x00, 0x00, 0x05, 0xc0];
+    let mut rd = &buf[..];
+
+    match read_integer_strict(&mut rd) {
+        Err(Error::NonCanonicalInt(Marker::U32)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!([0xc0], rd);
+    // The lenient reader accepts it.
+    assert_eq!(Some(5), read_integer(&mut &buf[..]).unwrap().as_u64());
+}
+
+#[test]
+fn from_str_read_integer() {
+    match read_integer(&mut &[0xa1, 0x61][..]) {
+        Err(Error::InvalidMarkerRead(..)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_str_at_char_limit_read_str_max_chars() {
+    let mut out = [0; 8];
+
+    assert_eq!("abc", read_str_max_chars(&mut &[0xa3, 0x61, 0x62, 0x63][..], &mut out, 3).unwrap());
+}
+
+#[test]
+fn from_str_over_char_limit_read_str_max_chars() {
+    let mut out = [0; 8];
+
+    match read_str_max_chars(&mut &[0xa3, 0x61, 0x62, 0x63][..], &mut out, 2) {
+        Err(Error::LengthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_multibyte_str_read_str_max_chars() {
+    // "€€", which is 2 characters in 6 bytes.
+    let buf = [0xa6, 0xe2, 0x82, 0xac, 0xe2, 0x82, 0xac];
+    let mut out = [0; 8];
+
+    assert_eq!("€€", read_str_max_chars(&mut &buf[..], &mut out, 2).unwrap());
+    match read_str_max_chars(&mut &buf[..], &mut out, 1) {
+        Err(Error::LengthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    // The byte length is capped by the buffer regardless of the character limit.
+    match read_str_max_chars(&mut &buf[..], &mut out[..5], 2) {
+        Err(Error::LengthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_float_array_read_scalar_array() {
+    let buf = [0x93, 0xca, 0x3f, 0x00, 0x00, 0x00, 0xcb, 0xbf, 0xf0, 0, 0, 0, 0, 0, 0, 0xca, 0, 0, 0, 0];
+    let mut out = Vec::new();
+
+    read_scalar_array(&mut &buf[..], MarkerCategory::Float, &mut out).unwrap();
+
+    assert_eq!(vec![Value::F32(0.5), Value::F64(-1.0), Value::F32(0.0)], out);
+}
+
+#[test]
+fn from_float_array_with_str_read_scalar_array() {
+    let buf = [0x93, 0xca, 0x3f, 0x00, 0x00, 0x00, 0xca, 0, 0, 0, 0, 0xa1, 0x61];
+    let mut rd = &buf[..];
+    let mut out = Vec::new();
+
+    match read_scalar_array(&mut rd, MarkerCategory::Float, &mut out) {
+        Err(Error::TypeMismatch { index: 2, marker: Marker::FixStr(1) }) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(vec![Value::F32(0.5), Value::F32(0.0)], out);
+    // Only the marker of the offending element is consumed.
+    assert_eq!([0x61], rd);
+}
+
+#[test]
+fn from_matching_magic_expect_value() {
+    let buf = [0x92, 0xa3, 0x52, 0x4d, 0x50, 0x01, 0xc0];
+    let mut rd = &buf[..];
+    let magic = Value::Array(vec![Value::from("RMP"), Value::from(1)]);
+
+    expect_value(&mut rd, &magic).unwrap();
+    // Only the expected value is consumed.
+    assert_eq!([0xc0], rd);
+}
+
+#[test]
+fn from_mismatching_magic_expect_value() {
+    let buf = [0x92, 0xa3, 0x52, 0x4d, 0x50, 0x02];
+    let magic = Value::Array(vec![Value::from("RMP"), Value::from(1)]);
+
+    match expect_value(&mut &buf[..], &magic) {
+        Err(Error::UnexpectedValue(val)) => {
+            assert_eq!(Value::Array(vec![Value::from("RMP"), Value::from(2)]), val);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_map_with_raw_field_read_value_with_config() {
+    // {"id": 1, "payload": [uint8 1, {"x": nil}]}, where the payload isn't most compactly encoded.
+    let buf = [
+        0x82, 0xa2, 0x69, 0x64, 0x01, 0xa7, 0x70, 0x61, 0x79, 0x6c, 0x6f, 0x61, 0x64, 0xdc, 0x00,
+        0x02, 0xcc, 0x01, 0x81, 0xa1, 0x78, 0xc0,
+    ];
+    let config = DecodeConfig {
+        raw_keys: vec!["payload".into()],
+        ..DecodeConfig::default()
+    };
+
+    let val = read_value_with_config(&mut &buf[..], &config).unwrap();
+
+    assert_eq!(Value::Map(vec![
+        (Value::from("id"), Value::from(1)),
+        (Value::from("payload"), Value::Raw(buf[13..].to_vec())),
+    ]), val);
+
+    let mut out = Vec::new();
+    write_value(&mut out, &val).unwrap();
+    assert_eq!(&buf[..], &out[..]);
+}
+
+#[test]
+fn from_map_with_huge_raw_field_limited_by_max_total_alloc() {
+    // {"payload": [bin32 of 4 GiB, ...]}, truncated.
+    let mut buf = vec![0x81, 0xa7, 0x70, 0x61, 0x79, 0x6c, 0x6f, 0x61, 0x64, 0x91, 0xc6, 0xff, 0xff, 0xff, 0xff];
+    buf.extend_from_slice(&[0; 2048]);
+    let config = DecodeConfig {
+        max_total_alloc: Some(1024),
+        raw_keys: vec!["payload".into()],
+        ..DecodeConfig::default()
+    };
+
+    match read_value_with_config(&mut &buf[..], &config) {
+        Err(Error::LengthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_nested_array_copy_value() {
+    // [[1, "ab"], {}], then true.
+    let buf = [0x92, 0x92, 0x01, 0xa2, 0x61, 0x62, 0x80, 0xc3];
+    let mut rd = &buf[..];
+    let mut out = Vec::new();
+
+    copy_value(&mut rd, &mut out).unwrap();
+
+    assert_eq!(&buf[..7], &out[..]);
+    assert_eq!([0xc3], rd);
+}
+
+#[test]
+fn from_truncated_str_copy_value() {
+    let buf = [0x91, 0xa3, 0x61];
+
+    match copy_value(&mut &buf[..], &mut Vec::new()) {
+        Err(Error::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_three_values_iterate_values() {
+    let buf = [0x01, 0x92, 0xc3, 0xc2, 0xa1, 0x61];
+    let mut rd = &buf[..];
+
+    let mut vals = Vec::new();
+    for val in decode::values(&mut rd) {
+        vals.push(val.unwrap());
+    }
+
+    assert_eq!(vec![
+        Value::from(1),
+        Value::Array(vec![Value::from(true), Value::from(false)]),
+        Value::from("a"),
+    ], vals);
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn from_truncated_stream_iterate_values() {
+    // 1, then an array missing its second element.
+    let buf = [0x01, 0x92, 0xc3];
+
+    let mut vals = decode::values(&buf[..]);
+
+    assert_eq!(Value::from(1), vals.next().unwrap().unwrap());
+    match vals.next() {
+        Some(Err(Error::TruncatedContainer { kind: ContainerKind::Array, index: 1, expected: 2 })) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(vals.next().is_none());
+}
+
+#[test]
+fn from_invalid_utf8_str_defer_utf8_validation() {
+    let buf = [0x92, 0xa2, 0xc3, 0x28, 0xa2, 0x6f, 0x6b];
+    let config = DecodeConfig {
+        defer_utf8_validation: true,
+        ..DecodeConfig::default()
+    };
+
+    let val = read_value_with_config(&mut &buf[..], &config).unwrap();
+    let vec = val.as_array().unwrap();
+
+    let invalid = match vec[0] {
+        Value::String(ref s) => s,
+        ref other => panic!("unexpected value: {:?}", other),
+    };
+    assert_eq!(None, invalid.as_str());
+    assert_eq!(0, invalid.as_err().unwrap().valid_up_to());
+    assert_eq!([0xc3, 0x28], invalid.as_bytes());
+
+    assert_eq!(Some("ok"), vec[1].as_str());
+    assert_eq!(Value::from("ok"), vec[1]);
+
+    let mut out = Vec::new();
+    write_value(&mut out, &val).unwrap();
+    // Invalid strings are written as binaries, like when validated upfront.
+    assert_eq!(vec![0x92, 0xc4, 0x02, 0xc3, 0x28, 0xa2, 0x6f, 0x6b], out);
+}
+
+#[test]
+fn from_map_read_map_lazy_then_skip_remaining() {
+    // {1: "a", 2: [3, 4], 3: {"x": nil}, 4: b"\x00"}, then true.
+    let buf = [
+        0x84, 0x01, 0xa1, 0x61, 0x02, 0x92, 0x03, 0x04, 0x03, 0x81, 0xa1, 0x78, 0xc0, 0x04, 0xc4,
+        0x01, 0x00, 0xc3,
+    ];
+    let mut cur = Cursor::new(&buf[..]);
+
+    let mut map = decode::read_map_lazy(&mut cur).unwrap();
+    assert_eq!(4, map.remaining());
+    assert_eq!((Value::from(1), Value::from("a")), map.next_entry().unwrap().unwrap());
+    assert_eq!((Value::from(2), Value::Array(vec![Value::from(3), Value::from(4)])),
+               map.next_entry().unwrap().unwrap());
+    assert_eq!(2, map.remaining());
+    map.skip_remaining().unwrap();
+
+    assert_eq!(17, cur.position());
+    assert_eq!(Value::from(true), read_value(&mut cur).unwrap());
+}
+
+#[test]
+fn from_map_read_map_lazy_to_the_end() {
+    let buf = [0x81, 0x01, 0x02];
+    let mut rd = &buf[..];
+
+    let mut map = decode::read_map_lazy(&mut rd).unwrap();
+    assert_eq!((Value::from(1), Value::from(2)), map.next_entry().unwrap().unwrap());
+    assert!(map.next_entry().is_none());
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn from_truncated_bin_skip_value() {
+    let buf = [0xc4, 0x03, 0x00];
+
+    match decode::skip_value(&mut &buf[..]) {
+        Err(Error::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_subnormal_f32_reject_subnormal_floats() {
+    // The smallest positive subnormal f32.
+    let buf = [0xca, 0x00, 0x00, 0x00, 0x01];
+
+    assert_eq!(Value::F32(f32::from_bits(1)), read_value(&mut &buf[..]).unwrap());
+
+    let config = DecodeConfig {
+        reject_subnormal_floats: true,
+        ..DecodeConfig::default()
+    };
+    match read_value_with_config(&mut &buf[..], &config) {
+        Err(Error::SubnormalFloat(val)) => assert_eq!(f64::from(f32::from_bits(1)), val),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_normal_floats_reject_subnormal_floats() {
+    let config = DecodeConfig {
+        reject_subnormal_floats: true,
+        ..DecodeConfig::default()
+    };
+
+    let buf = [0xca, 0x00, 0x80, 0x00, 0x00];
+    assert_eq!(Value::F32(f32::MIN_POSITIVE), read_value_with_config(&mut &buf[..], &config).unwrap());
+
+    let buf = [0xcb, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    assert_eq!(Value::F64(-0.0), read_value_with_config(&mut &buf[..], &config).unwrap());
+}
+
+#[test]
+fn from_strings_read_value_with_strings() {
+    let strings: HashSet<Arc<str>> = ["red", "green"].into_iter().map(Arc::from).collect();
+
+    // {"red": ["green", "blue", "red", 0xff]}
+    let buf = [
+        0x81, 0xa3, 0x72, 0x65, 0x64,
+        0x94, 0xa5, 0x67, 0x72, 0x65, 0x65, 0x6e, 0xa4, 0x62, 0x6c, 0x75, 0x65, 0xa3, 0x72, 0x65, 0x64, 0xa1, 0xff,
+    ];
+
+    let val = read_value_with_strings(&mut &buf[..], &DecodeConfig::default(), &strings).unwrap();
+
+    assert_eq!(read_value(&mut &buf[..]).unwrap(), val);
+    let arr = val["red"].as_array().unwrap();
+    assert_eq!(Some("green"), arr[0].as_str());
+    assert_eq!(Some("blue"), arr[1].as_str());
+    assert_eq!(Some("red"), arr[2].as_str());
+    assert_eq!(&[0xff], val["red"][3].as_slice().unwrap());
+}
+
+#[test]
+fn from_empty_strings_read_value_with_strings() {
+    let buf = [0x92, 0xa0, 0xa1, 0x61];
+
+    let val = read_value_with_strings(&mut &buf[..], &DecodeConfig::default(), &HashSet::new()).unwrap();
+
+    assert_eq!(Value::Array(vec![Value::from(""), Value::from("a")]), val);
+}
+
+#[test]
+fn from_truncated_str_read_value_with_strings() {
+    let strings: HashSet<Arc<str>> = ["red"].into_iter().map(Arc::from).collect();
+    let buf = [0xa3, 0x72, 0x65];
+
+    match read_value_with_strings(&mut &buf[..], &DecodeConfig::default(), &strings) {
+        Err(Error::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+fn read_canonical(buf: &[u8]) -> Result<Value, Error> {
+    let config = DecodeConfig {
+        canonical_ints: true,
+        ..DecodeConfig::default()
+    };
+    read_value_with_config(&mut &buf[..], &config)
+}
+
+#[test]
+fn from_signed_small_positive_canonical_ints() {
+    // 5 as int32, where a positive fixint would do.
+    let buf = [0xd2, 0x00, 0x00, 0x00, 0x05];
+
+    assert_eq!(Value::from(5), read_value(&mut &buf[..]).unwrap());
+    match read_canonical(&buf) {
+        Err(Error::NonCanonicalInt(Marker::I32)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    assert_eq!(Value::from(5), read_canonical(&[0x05]).unwrap());
+}
+
+#[test]
+fn from_non_negative_signed_canonical_ints() {
+    // 200 fits uint8 and must not be encoded as int16, even though int8 can't hold it.
+    assert!(matches!(read_canonical(&[0xd1, 0x00, 0xc8]), Err(Error::NonCanonicalInt(Marker::I16))));
+    assert!(matches!(read_canonical(&[0xd0, 0x00]), Err(Error::NonCanonicalInt(Marker::I8))));
+    assert_eq!(Value::from(200), read_canonical(&[0xcc, 0xc8]).unwrap());
+}
+
+#[test]
+fn from_wide_ints_canonical_ints() {
+    assert!(matches!(read_canonical(&[0xcc, 0x7f]), Err(Error::NonCanonicalInt(Marker::U8))));
+    assert!(matches!(read_canonical(&[0xcd, 0x00, 0xff]), Err(Error::NonCanonicalInt(Marker::U16))));
+    assert!(matches!(read_canonical(&[0xd0, 0xe0]), Err(Error::NonCanonicalInt(Marker::I8))));
+    assert!(matches!(read_canonical(&[0xd1, 0xff, 0x80]), Err(Error::NonCanonicalInt(Marker::I16))));
+
+    assert_eq!(Value::from(-33), read_canonical(&[0xd0, 0xdf]).unwrap());
+    assert_eq!(Value::from(-129), read_canonical(&[0xd1, 0xff, 0x7f]).unwrap());
+    assert_eq!(Value::from(u64::MAX), read_canonical(&[0xcf, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]).unwrap());
+    assert_eq!(Value::from(i64::MIN), read_canonical(&[0xd3, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]).unwrap());
+}
+
+#[test]
+fn from_fixarray_truncated_after_second_element() {
+    let buf = [0x93, 0x01, 0x02];
+
+    let err = read_value(&mut &buf[..]).unwrap_err();
+
+    match err {
+        Error::TruncatedContainer { kind: ContainerKind::Array, index: 2, expected: 3 } => (),
+        ref other => panic!("unexpected error: {:?}", other),
+    }
+    assert_eq!(std::io::ErrorKind::UnexpectedEof, err.kind());
+    assert_eq!("array truncated: input ended at element 2 of 3", err.to_string());
+}
+
+#[test]
+fn from_nested_map_truncated_in_value() {
+    // [{"a": 1, "b": "xy"}] with the last string cut short.
+    let buf = [0x91, 0x82, 0xa1, 0x61, 0x01, 0xa1, 0x62, 0xa2, 0x78];
+
+    match read_value(&mut &buf[..]) {
+        Err(Error::TruncatedContainer { kind: ContainerKind::Map, index: 1, expected: 2 }) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_truncated_top_level_str_keeps_eof() {
+    match read_value(&mut &[0xa2, 0x78][..]) {
+        Err(Error::InvalidDataRead(..)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_coerced_values_read_value_with_warnings() {
+    let config = DecodeConfig {
+        coerce_integral_floats: true,
+        ..DecodeConfig::default()
+    };
+    // {"a": 0xff as str, "b": [3.0, 0.5]}
+    let buf = [
+        0x82,
+        0xa1, 0x61, 0xa1, 0xff,
+        0xa1, 0x62, 0x92,
+        0xcb, 0x40, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0xcb, 0x3f, 0xe0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let mut warnings = Vec::new();
+
+    let val = read_value_with_warnings(&mut &buf[..], &config, &mut warnings).unwrap();
+
+    assert_eq!(read_value_with_config(&mut &buf[..], &config).unwrap(), val);
+    assert_eq!(vec![
+        DecodeWarning { offset: 3, kind: DecodeWarningKind::InvalidUtf8 },
+        DecodeWarning { offset: 8, kind: DecodeWarningKind::IntegralFloat(3.0) },
+    ], warnings);
+}
+
+#[test]
+fn from_strict_values_read_value_with_warnings() {
+    // Not coerced without coerce_integral_floats.
+    let buf = [0xcb, 0x40, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    let mut warnings = Vec::new();
+
+    let val = read_value_with_warnings(&mut &buf[..], &DecodeConfig::default(), &mut warnings).unwrap();
+
+    assert_eq!(Value::F64(3.0), val);
+    assert!(warnings.is_empty());
+}
+
+/// Decodes an integer as `Left` or a string as `Right`.
+fn read_int_or_string(buf: &[u8]) -> Result<Either<i64, String>, Error> {
+    let is_str = |marker| matches!(marker, Marker::FixStr(..) | Marker::Str8 | Marker::Str16 | Marker::Str32);
+
+    read_either(
+        &mut &buf[..],
+        |marker| !is_str(marker),
+        |rd| Ok(rmp::decode::read_int(rd)?),
+        |rd| match read_value(rd)? {
+            Value::String(s) => Ok(s.into_str().unwrap()),
+            val => Err(Error::UnexpectedValue(val)),
+        },
+    )
+}
+
+#[test]
+fn from_int_read_either() {
+    assert_eq!(Either::Left(300), read_int_or_string(&[0xcd, 0x01, 0x2c]).unwrap());
+    assert_eq!(Either::Left(-1), read_int_or_string(&[0xff]).unwrap());
+}
+
+#[test]
+fn from_str_read_either() {
+    assert_eq!(Either::Right("abc".to_string()), read_int_or_string(&[0xa3, 0x61, 0x62, 0x63]).unwrap());
+}
+
+#[test]
+fn from_other_read_either() {
+    // Nil is dispatched to the integer reader, which rejects it.
+    assert!(read_int_or_string(&[0xc0]).is_err());
+
+    match read_int_or_string(&[]) {
+        Err(Error::InvalidMarkerRead(..)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_either_leaves_rest() {
+    let buf = [0x01, 0xc3];
+    let mut rd = &buf[..];
+
+    let val = read_either(&mut rd, |_| true, |rd| read_value(rd), |_| Ok(()));
+
+    assert_eq!(Either::Left(Value::from(1)), val.unwrap());
+    assert_eq!([0xc3], rd);
+}
+
+#[test]
+fn from_nil_terminated_sequence_read_until_nil() {
+    // 1, "a", [true], nil, then a trailing 0x2a.
+    let buf = [0x01, 0xa1, 0x61, 0x91, 0xc3, 0xc0, 0x2a];
+    let mut rd = &buf[..];
+
+    let vals = read_until_nil(&mut rd, |rd| read_value(rd)).unwrap();
+
+    assert_eq!(vec![Value::from(1), Value::from("a"), Value::Array(vec![Value::Boolean(true)])], vals);
+    assert_eq!([0x2a], rd);
+}
+
+#[test]
+fn from_nil_read_until_nil() {
+    let buf = [0xc0, 0x2a];
+    let mut rd = &buf[..];
+
+    let vals = read_until_nil(&mut rd, |rd| read_value(rd)).unwrap();
+
+    assert!(vals.is_empty());
+    assert_eq!([0x2a], rd);
+}
+
+#[test]
+fn from_unterminated_sequence_read_until_nil() {
+    let buf = [0x01, 0x02];
+
+    match read_until_nil(&mut &buf[..], |rd| read_value(rd)) {
+        Err(Error::InvalidMarkerRead(..)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_map_with_corrupt_value_read_map_recovering() {
+    // {"a": 1, "b": [[[nil]]], "c": "ok"}, where "b" is nested too deep.
+    let buf = [
+        0x83,
+        0xa1, 0x61, 0x01,
+        0xa1, 0x62, 0x91, 0x91, 0x91, 0xc0,
+        0xa1, 0x63, 0xa2, 0x6f, 0x6b,
+        0xc3,
+    ];
+    let config = DecodeConfig {
+        max_depth: 2,
+        ..DecodeConfig::default()
+    };
+    let mut rd = &buf[..];
+
+    let map = read_map_recovering(&mut rd, &config).unwrap();
+
+    assert_eq!(vec![
+        (Value::from("a"), Value::from(1)),
+        (Value::from("c"), Value::from("ok")),
+    ], map.entries);
+    assert_eq!(1, map.errors.len());
+    match map.errors[0] {
+        (1, Error::DepthLimitExceeded) => (),
+        ref other => panic!("unexpected error: {:?}", other),
+    }
+    assert_eq!([0xc3], rd);
+}
+
+#[test]
+fn from_truncated_map_read_map_recovering() {
+    // {"a": 1, "b": ...} cut short in the second value.
+    let buf = [0x82, 0xa1, 0x61, 0x01, 0xa1, 0x62, 0xa3, 0x78];
+
+    let map = read_map_recovering(&mut &buf[..], &DecodeConfig::default()).unwrap();
+
+    assert_eq!(vec![(Value::from("a"), Value::from(1))], map.entries);
+    match map.errors[..] {
+        [(1, Error::InvalidDataRead(..))] => (),
+        ref other => panic!("unexpected errors: {:?}", other),
+    }
+}
+
+#[test]
+fn from_huge_value_read_map_recovering_limited_by_max_total_alloc() {
+    // {"a": 1, "b": bin32 of 4 GiB}, truncated.
+    let mut buf = vec![0x82, 0xa1, 0x61, 0x01, 0xa1, 0x62, 0xc6, 0xff, 0xff, 0xff, 0xff];
+    buf.extend_from_slice(&[0; 2048]);
+    let config = DecodeConfig { max_total_alloc: Some(1024), ..DecodeConfig::default() };
+
+    let map = read_map_recovering(&mut &buf[..], &config).unwrap();
+
+    assert_eq!(vec![(Value::from("a"), Value::from(1))], map.entries);
+    match map.errors[..] {
+        [(1, Error::LengthLimitExceeded)] => (),
+        ref other => panic!("unexpected errors: {:?}", other),
+    }
+}
+
+#[test]
+fn from_non_map_read_map_recovering() {
+    assert!(read_map_recovering(&mut &[0x91, 0x01][..], &DecodeConfig::default()).is_err());
+}
+
+#[test]
+fn from_annotated_small_u32() {
+    let buf = [0xce, 0x00, 0x00, 0x00, 0x05];
+
+    let (val, annotation) = read_value_annotated(&mut &buf[..]).unwrap();
+
+    assert_eq!(Value::from(5), val);
+    assert_eq!(Annotation::Scalar(Marker::U32), annotation);
+}
+
+#[test]
+fn from_annotated_nested() {
+    // map16 {"a": [fixpos 1, str8 "b"]}
+    let buf = [0xde, 0x00, 0x01, 0xa1, 0x61, 0x92, 0x01, 0xd9, 0x01, 0x62];
+    let mut rd = &buf[..];
+
+    let (val, annotation) = read_value_annotated(&mut rd).unwrap();
+
+    assert_eq!(read_value(&mut &buf[..]).unwrap(), val);
+    let expected = Annotation::Map(Marker::Map16, vec![(
+        Annotation::Scalar(Marker::FixStr(1)),
+        Annotation::Array(Marker::FixArray(2), vec![
+            Annotation::Scalar(Marker::FixPos(1)),
+            Annotation::Scalar(Marker::Str8),
+        ]),
+    )]);
+    assert_eq!(expected, annotation);
+    assert_eq!(Marker::Map16, annotation.marker());
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn from_annotated_depth_limit() {
+    let buf = [0x91; 2000];
+
+    match read_value_annotated(&mut &buf[..]) {
+        Err(Error::DepthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_map_field_in_the_middle() {
+    // {1: "int key", "alpha": [1, 2], "name": {"nested": true}, "omega": nil}
+    let mut buf = Vec::new();
+    let map = Value::Map(vec![
+        (Value::from(1), Value::from("int key")),
+        (Value::from("alpha"), Value::Array(vec![Value::from(1), Value::from(2)])),
+        (Value::from("name"), Value::Map(vec![(Value::from("nested"), Value::Boolean(true))])),
+        (Value::from("omega"), Value::Nil),
+    ]);
+    write_value(&mut buf, &map).unwrap();
+    let mut rd = &buf[..];
+
+    let val = read_map_field(&mut rd, "name").unwrap();
+
+    assert_eq!(Some(Value::Map(vec![(Value::from("nested"), Value::Boolean(true))])), val);
+    // The entries after the match are left unread.
+    assert_eq!([0xa5, b'o', b'm', b'e', b'g', b'a', 0xc0], rd);
+}
+
+#[test]
+fn from_map_field_missing() {
+    // {"a": 1, [1]: 2, "abc": 3}
+    let buf = [0x83, 0xa1, 0x61, 0x01, 0x91, 0x01, 0x02, 0xa3, 0x61, 0x62, 0x63, 0x03, 0xc0];
+    let mut rd = &buf[..];
+
+    assert_eq!(None, read_map_field(&mut rd, "ab").unwrap());
+    assert_eq!([0xc0], rd);
+}
+
+#[test]
+fn from_map_field_truncated_key() {
+    // {"abcd": ..., with only 2 bytes of the key.
+    let buf = [0x81, 0xa4, 0x61, 0x62];
+
+    match read_map_field(&mut &buf[..], "ab") {
+        Err(Error::InvalidDataRead(err)) => assert_eq!(std::io::ErrorKind::UnexpectedEof, err.kind()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_array_and_map_read_entries() {
+    let expected = vec![(Value::from(0), Value::from("a")), (Value::from(1), Value::Array(vec![Value::Nil]))];
+
+    let mut buf = Vec::new();
+    write_value(&mut buf, &Value::Array(vec![Value::from("a"), Value::Array(vec![Value::Nil])])).unwrap();
+    write_value(&mut buf, &Value::Map(expected.clone())).unwrap();
+    let mut rd = &buf[..];
+
+    assert_eq!(expected, read_entries(&mut rd).unwrap());
+    assert_eq!(expected, read_entries(&mut rd).unwrap());
+    assert!(rd.is_empty());
+}
+
+#[test]
+fn from_map_with_non_index_keys_read_entries() {
+    let entries = vec![(Value::from("b"), Value::from(2)), (Value::from(-1), Value::Nil)];
+    let mut buf = Vec::new();
+    write_value(&mut buf, &Value::Map(entries.clone())).unwrap();
+
+    assert_eq!(entries, read_entries(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn from_str_read_entries() {
+    match read_entries(&mut &[0xa1, 0x61][..]) {
+        Err(Error::InvalidMarkerRead(..)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct User {
+    id: u64,
+    name: String,
+}
+
+fn user_table<'a>() -> FieldTable<User, &'a [u8]> {
+    FieldTable::new()
+        .field("id", |user: &mut User, rd: &mut &'a [u8]| {
+            user.id = rmp::decode::read_int(rd)?;
+            Ok(())
+        })
+        .field("name", |user, rd| {
+            user.name = read_value(rd)?.as_str().unwrap_or_default().into();
+            Ok(())
+        })
+}
+
+#[test]
+fn from_map_with_unknown_field_read_struct_with() {
+    // {"id": 42, "extra": [1, {"a": 2}], 7: nil, "name": "John"}, then nil.
+    let val = Value::Map(vec![
+        (Value::from("id"), Value::from(42)),
+        (Value::from("extra"), Value::Array(vec![Value::from(1), Value::Map(vec![(Value::from("a"), Value::from(2))])])),
+        (Value::from(7), Value::Nil),
+        (Value::from("name"), Value::from("John")),
+    ]);
+    let mut buf = Vec::new();
+    write_value(&mut buf, &val).unwrap();
+    buf.push(0xc0);
+    let mut rd = &buf[..];
+
+    let mut user = User::default();
+    read_struct_with(&mut rd, &user_table(), &mut user).unwrap();
+
+    assert_eq!(User { id: 42, name: "John".into() }, user);
+    assert_eq!([0xc0], rd);
+}
+
+#[test]
+fn from_map_with_invalid_field_read_struct_with() {
+    // {"name": "John", "id": "x"}
+    let buf = [0x82, 0xa4, 0x6e, 0x61, 0x6d, 0x65, 0xa4, 0x4a, 0x6f, 0x68, 0x6e, 0xa2, 0x69, 0x64, 0xa1, 0x78];
+
+    let mut user = User::default();
+    match read_struct_with(&mut &buf[..], &user_table(), &mut user) {
+        Err(Error::InvalidMarkerRead(..)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    // The fields before the failing one are set.
+    assert_eq!("John", user.name);
+}
+
+#[test]
+fn from_array_of_pairs_as_map() {
+    // [[1, "a"], ["b", [2, 3, 4]]]
+    let buf = [0x92, 0x92, 0x01, 0xa1, 0x61, 0x92, 0xa1, 0x62, 0x93, 0x02, 0x03, 0x04];
+    let config = DecodeConfig {
+        arrays_of_pairs_as_maps: true,
+        ..DecodeConfig::default()
+    };
+
+    let expected = Value::Map(vec![
+        (Value::from(1), Value::from("a")),
+        (Value::from("b"), Value::Array(vec![Value::from(2), Value::from(3), Value::from(4)])),
+    ]);
+    assert_eq!(expected, read_value_with_config(&mut &buf[..], &config).unwrap());
+
+    let expected = Value::Array(vec![
+        Value::Array(vec![Value::from(1), Value::from("a")]),
+        Value::Array(vec![Value::from("b"), Value::Array(vec![Value::from(2), Value::from(3), Value::from(4)])]),
+    ]);
+    assert_eq!(expected, read_value(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn from_array_of_mixed_arity_stays_array() {
+    // [[1, 2], [3], []]
+    let buf = [0x93, 0x92, 0x01, 0x02, 0x91, 0x03, 0x90];
+    let config = DecodeConfig {
+        arrays_of_pairs_as_maps: true,
+        ..DecodeConfig::default()
+    };
+
+    let expected = Value::Array(vec![
+        Value::Array(vec![Value::from(1), Value::from(2)]),
+        Value::Array(vec![Value::from(3)]),
+        Value::Array(vec![]),
+    ]);
+    assert_eq!(expected, read_value_with_config(&mut &buf[..], &config).unwrap());
+
+    // An empty array has no pairs to tell it's a map.
+    assert_eq!(Value::Array(vec![]), read_value_with_config(&mut &[0x90][..], &config).unwrap());
+}
+
+#[test]
+fn from_array_of_pairs_as_map_limited_by_max_allocations() {
+    // [[1, 2]], which takes two allocations as arrays, and a third one for the map.
+    let buf = [0x91, 0x92, 0x01, 0x02];
+    let config = DecodeConfig {
+        max_allocations: Some(2),
+        ..DecodeConfig::default()
+    };
+    assert!(read_value_with_config(&mut &buf[..], &config).is_ok());
+
+    let config = DecodeConfig {
+        arrays_of_pairs_as_maps: true,
+        ..config
+    };
+    match read_value_with_config(&mut &buf[..], &config) {
+        Err(Error::LengthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_nested_to_channel() {
+    use std::sync::mpsc;
+    use std::thread;
+
+    let val = Value::Map(vec![
+        (Value::from("a"), Value::Array(vec![Value::from(1), Value::Array(vec![]), Value::Map(vec![])])),
+        (Value::from(2), Value::Array(vec![Value::Array(vec![Value::Nil])])),
+    ]);
+    let mut buf = Vec::new();
+    write_value(&mut buf, &val).unwrap();
+
+    let (tx, rx) = mpsc::channel();
+    let decoder = thread::spawn(move || decode_to_channel(&mut &buf[..], tx));
+    let events: Vec<_> = rx.iter().collect();
+    decoder.join().unwrap().unwrap();
+
+    let expected = vec![
+        DecodeEvent::BeginMap(2),
+        DecodeEvent::Scalar(Value::from("a")),
+        DecodeEvent::BeginArray(3),
+        DecodeEvent::Scalar(Value::from(1)),
+        DecodeEvent::BeginArray(0),
+        DecodeEvent::EndArray,
+        DecodeEvent::BeginMap(0),
+        DecodeEvent::EndMap,
+        DecodeEvent::EndArray,
+        DecodeEvent::Scalar(Value::from(2)),
+        DecodeEvent::BeginArray(1),
+        DecodeEvent::BeginArray(1),
+        DecodeEvent::Scalar(Value::Nil),
+        DecodeEvent::EndArray,
+        DecodeEvent::EndArray,
+        DecodeEvent::EndMap,
+    ];
+    assert_eq!(expected, events);
+}
+
+#[test]
+fn from_truncated_to_channel() {
+    use std::sync::mpsc;
+
+    let buf = [0x92, 0x01];
+    let (tx, rx) = mpsc::channel();
+
+    match decode_to_channel(&mut &buf[..], tx) {
+        Err(Error::InvalidMarkerRead(..)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    let events: Vec<_> = rx.iter().collect();
+    assert_eq!(vec![DecodeEvent::BeginArray(2), DecodeEvent::Scalar(Value::from(1))], events);
+}
+
+#[test]
+fn from_value_to_disconnected_channel() {
+    use std::sync::mpsc;
+
+    let (tx, rx) = mpsc::channel();
+    drop(rx);
+
+    match decode_to_channel(&mut &[0x91, 0x01][..], tx) {
+        Err(Error::InvalidDataRead(err)) => assert_eq!(std::io::ErrorKind::BrokenPipe, err.kind()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_map_with_large_binary_read_value_with_binary_refs() {
+    let blob: Vec<u8> = (0..1 << 20).map(|i| i as u8).collect();
+    let val = Value::Map(vec![
+        (Value::from("name"), Value::from("photo")),
+        (Value::from("data"), Value::Binary(blob.clone())),
+        (Value::from("thumb"), Value::Binary(vec![1, 2, 3])),
+    ]);
+    let mut buf = Vec::new();
+    write_value(&mut buf, &val).unwrap();
+    let mut rd = Cursor::new(&buf[..]);
+
+    let val = read_value_with_binary_refs(&mut rd, &DecodeConfig::default(), 1024).unwrap();
+
+    // The blob starts after the map header, the first entry, the "data" key and a bin32 header.
+    let offset = 1 + 11 + 5 + 5;
+    assert_eq!(Value::BinaryRef { offset, len: 1 << 20 }, val["data"]);
+    assert_eq!(Value::Binary(vec![1, 2, 3]), val["thumb"]);
+    assert_eq!(buf.len() as u64, rd.position());
+
+    let mut data = vec![0; 1 << 20];
+    rd.seek(SeekFrom::Start(offset)).unwrap();
+    rd.read_exact(&mut data).unwrap();
+    assert_eq!(blob, data);
+}
+
+#[test]
+fn from_offset_reader_read_value_with_binary_refs() {
+    let buf = [0xff, 0xc4, 0x03, 0x01, 0x02, 0x03];
+    let mut rd = Cursor::new(&buf[..]);
+    rd.set_position(1);
+
+    let val = read_value_with_binary_refs(&mut rd, &DecodeConfig::default(), 0).unwrap();
+
+    assert_eq!(Value::BinaryRef { offset: 3, len: 3 }, val);
+}
+
+#[test]
+fn from_truncated_binary_read_value_with_binary_refs() {
+    let buf = [0x91, 0xc4, 0x03, 0x01];
+
+    match read_value_with_binary_refs(&mut Cursor::new(&buf[..]), &DecodeConfig::default(), 0) {
+        Err(Error::TruncatedContainer { index: 0, expected: 1, .. }) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_large_binary_read_value_with_binary_refs_seeks_past_it() {
+    /// Counts the bytes actually read.
+    struct CountingReads<'a> {
+        rd: Cursor<&'a [u8]>,
+        read: usize,
+    }
+
+    impl Read for CountingReads<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = self.rd.read(buf)?;
+            self.read += len;
+            Ok(len)
+        }
+    }
+
+    impl Seek for CountingReads<'_> {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.rd.seek(pos)
+        }
+    }
+
+    let val = Value::Array(vec![Value::Binary(vec![7; 1 << 20]), Value::from(1)]);
+    let mut buf = Vec::new();
+    write_value(&mut buf, &val).unwrap();
+    let mut rd = CountingReads { rd: Cursor::new(&buf[..]), read: 0 };
+
+    let val = read_value_with_binary_refs(&mut rd, &DecodeConfig::default(), 1024).unwrap();
+
+    assert_eq!(Value::Array(vec![Value::BinaryRef { offset: 6, len: 1 << 20 }, Value::from(1)]), val);
+    assert!(rd.read < 64, "{}", rd.read);
+    assert_eq!(buf.len() as u64, rd.rd.position());
+}
+
+#[test]
+fn from_trailing_large_binary_read_value_with_binary_refs() {
+    let buf = [0xc4, 0x03, 0x01, 0x02, 0x03, 0xc0];
+    let mut rd = Cursor::new(&buf[..]);
+
+    let val = read_value_with_binary_refs(&mut rd, &DecodeConfig::default(), 0).unwrap();
+
+    assert_eq!(Value::BinaryRef { offset: 2, len: 3 }, val);
+    // The reader is left right after the value, although its last bytes were skipped.
+    assert_eq!(5, rd.position());
+}
+
+#[test]
+fn from_nested_map_read_value_index() {
+    use rmpv::path::PathSegment;
+
+    let deep = Value::Array(vec![Value::from(1), Value::from(300)]);
+    let val = Value::Map(vec![
+        (Value::from("id"), Value::from(7)),
+        (Value::from("items"), Value::Array(vec![
+            Value::Nil,
+            Value::Map(vec![
+                (Value::from("name"), Value::from("widget")),
+                (Value::from(3), Value::Map(vec![(Value::from("deep"), deep.clone())])),
+            ]),
+        ])),
+    ]);
+    let mut buf = vec![0xc0];
+    write_value(&mut buf, &val).unwrap();
+    let mut rd = Cursor::new(&buf[..]);
+    rd.set_position(1);
+
+    let (decoded, index) = read_value_index(&mut rd).unwrap();
+
+    assert_eq!(val, decoded);
+    // id, items, items[1].name, items[1].3 and items[1].3.deep.
+    assert_eq!(5, index.len());
+
+    let path = [
+        PathSegment::Key("items".into()),
+        PathSegment::Index(1),
+        PathSegment::Key("3".into()),
+        PathSegment::Key("deep".into()),
+    ];
+    let span = index.get(&path).unwrap();
+
+    let mut field = Vec::new();
+    rd.seek(SeekFrom::Start(span.offset)).unwrap();
+    copy_value(&mut rd, &mut field).unwrap();
+    assert_eq!(field.len() as u64, span.len);
+    assert_eq!(deep, read_value(&mut &field[..]).unwrap());
+
+    assert_eq!(None, index.get(&[PathSegment::Key("missing".into())]));
+}
+
+#[test]
+fn from_scalar_read_value_index() {
+    let (val, index) = read_value_index(&mut Cursor::new(&[0x2a][..])).unwrap();
+
+    assert_eq!(Value::from(42), val);
+    assert!(index.is_empty());
+}
+
+#[test]
+fn from_fields_under_long_key_read_value_index() {
+    use rmpv::path::PathSegment;
+
+    let key = "k".repeat(64 * 1024);
+    let item = Value::Map(vec![(Value::from("a"), Value::Nil)]);
+    let val = Value::Map(vec![(Value::from(key.as_str()), Value::Array(vec![item; 1000]))]);
+    let mut buf = Vec::new();
+    write_value(&mut buf, &val).unwrap();
+
+    let (_, index) = read_value_index(&mut Cursor::new(&buf[..])).unwrap();
+
+    // The long key itself, then the "a" of each item.
+    assert_eq!(1001, index.len());
+    let path = [PathSegment::Key(key.clone()), PathSegment::Index(999), PathSegment::Key("a".into())];
+    let span = index.get(&path).unwrap();
+    assert_eq!(Value::Nil, read_value(&mut &buf[span.offset as usize..]).unwrap());
+
+    let mut paths: Vec<_> = index.iter().map(|(path, _)| path.len()).collect();
+    paths.sort_unstable();
+    assert_eq!(1001, paths.len());
+    assert_eq!([1, 3], [paths[0], paths[1000]]);
+}
+
+fn record(fields: &[(&str, Value)]) -> Value {
+    Value::Map(fields.iter().map(|(key, val)| (Value::from(*key), val.clone())).collect())
+}
+
+#[test]
+fn from_array_of_records_read_records_columnar() {
+    let val = Value::Array(vec![
+        record(&[("id", Value::from(1)), ("name", Value::from("a"))]),
+        record(&[("name", Value::from("b")), ("id", Value::from(2))]),
+        record(&[("id", Value::from(3)), ("name", Value::from("c"))]),
+    ]);
+    let mut buf = Vec::new();
+    write_value(&mut buf, &val).unwrap();
+
+    let columns = read_records_columnar(&mut &buf[..], &["id", "name"]).unwrap();
+
+    assert_eq!(3, columns.rows());
+    assert_eq!(["id", "name"], columns.names());
+    assert_eq!(Some(&[Value::from(1), Value::from(2), Value::from(3)][..]), columns.column("id"));
+    assert_eq!(Some(&[Value::from("a"), Value::from("b"), Value::from("c")][..]), columns.column("name"));
+    assert_eq!(None, columns.column("missing"));
+}
+
+#[test]
+fn from_sparse_records_read_records_columnar() {
+    let val = Value::Array(vec![
+        record(&[("id", Value::from(1)), ("extra", Value::Array(vec![Value::Nil; 3]))]),
+        record(&[("name", Value::from("b")), ("name", Value::from("c"))]),
+    ]);
+    let mut buf = Vec::new();
+    write_value(&mut buf, &val).unwrap();
+    buf.push(0x2a);
+    let mut rd = &buf[..];
+
+    let columns = read_records_columnar(&mut rd, &["id", "name"]).unwrap();
+
+    assert_eq!(vec![
+        ("id".to_string(), vec![Value::from(1), Value::Nil]),
+        ("name".to_string(), vec![Value::Nil, Value::from("c")]),
+    ], columns.into_columns());
+    assert_eq!([0x2a], rd);
+}
+
+#[test]
+fn fail_duplicate_fields_read_records_columnar() {
+    let buf = [0x91, 0x81, 0xa1, 0x78, 0x01];
+    let mut rd = &buf[..];
+
+    match read_records_columnar(&mut rd, &["x", "y", "x"]) {
+        Err(Error::InvalidDataRead(err)) => assert_eq!(std::io::ErrorKind::InvalidInput, err.kind()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(buf, rd);
+}
+
+#[test]
+fn from_array_of_non_maps_read_records_columnar() {
+    let buf = [0x91, 0x01];
+
+    assert!(read_records_columnar(&mut &buf[..], &["id"]).is_err());
+}
@@ -329,6 +329,24 @@ fn from_bin32() {
         read_value_ref(&mut rd).ok().unwrap());
 }
 
+#[test]
+fn from_bin8_borrows_from_buffer() {
+    let buf = [0x92, 0xc4, 0x03, 0x00, 0x01, 0x02, 0xc4, 0x01, 0x03];
+
+    let mut rd = &buf[..];
+
+    match read_value_ref(&mut rd).unwrap() {
+        ValueRef::Array(vec) => match vec[..] {
+            [ValueRef::Binary(first), ValueRef::Binary(second)] => {
+                assert!(std::ptr::eq(&buf[3..6], first));
+                assert!(std::ptr::eq(&buf[8..], second));
+            }
+            ref other => panic!("unexpected elements: {:?}", other),
+        },
+        other => panic!("unexpected value: {:?}", other),
+    }
+}
+
 #[test]
 fn from_bin8_eof_while_reading_data() {
     let buf = [0xc4, 0x05, 0x00, 0x01, 0x02, 0x03];
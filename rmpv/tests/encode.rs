@@ -0,0 +1,642 @@
+use std::collections::BTreeMap;
+use std::hash::Hasher;
+
+use rmp::Marker;
+use rmpv::decode::{read_value_with_config, DecodeConfig};
+use rmpv::encode::{
+    write_btreemap, write_value, write_value_padded, write_value_with_config, BinaryChunks, ChunkedEncoder,
+    EncodeConfig, HashingWriter, NonFiniteFloat, NonFinitePolicy, SortMode, ValueTooLarge,
+};
+use rmpv::Value;
+
+fn describe_byte(byte: Option<&u8>) -> String {
+    match byte {
+        Some(&b) => format!("0x{:02x} ({})", b, Marker::from_u8(b).name()),
+        None => "end of output".into(),
+    }
+}
+
+/// Returns a line per differing byte, naming the marker each byte would be read as, or `None` if
+/// the bytes are equal.
+fn encode_diff(actual: &[u8], expected: &[u8]) -> Option<String> {
+    let lines: Vec<_> = (0..actual.len().max(expected.len()))
+        .filter(|&i| actual.get(i) != expected.get(i))
+        .map(|i| format!("at {}: expected {}, got {}", i, describe_byte(expected.get(i)), describe_byte(actual.get(i))))
+        .collect();
+
+    if lines.is_empty() {
+        None
+    } else {
+        Some(lines.join("\n"))
+    }
+}
+
+/// Encodes `val` and asserts it gives `expected`, printing a byte-level diff if it doesn't.
+fn assert_encodes_to(val: &Value, expected: &[u8]) {
+    let mut buf = Vec::new();
+    write_value(&mut buf, val).unwrap();
+
+    if let Some(diff) = encode_diff(&buf, expected) {
+        panic!("{:?} encoded differently:\n{}", val, diff);
+    }
+}
+
+#[test]
+fn encode_diff_of_mismatch() {
+    let mut buf = Vec::new();
+    write_value(&mut buf, &Value::from(300)).unwrap();
+
+    let expected = "at 0: expected 0xcc (uint8), got 0xcd (uint16)\n\
+                    at 1: expected 0xff (negative fixint), got 0x01 (positive fixint)\n\
+                    at 2: expected end of output, got 0x2c (positive fixint)";
+    assert_eq!(Some(expected.to_string()), encode_diff(&buf, &[0xcc, 0xff]));
+    assert_eq!(None, encode_diff(&buf, &[0xcd, 0x01, 0x2c]));
+}
+
+#[test]
+#[should_panic(expected = "at 1: expected 0x02 (positive fixint), got 0x01 (positive fixint)")]
+fn assert_encodes_to_mismatch() {
+    assert_encodes_to(&Value::Array(vec![Value::from(1)]), &[0x91, 0x02]);
+}
+
+#[test]
+fn pack_nested_assert_encodes_to() {
+    let val = Value::Map(vec![(Value::from("a"), Value::Array(vec![Value::Nil, Value::from(-1)]))]);
+
+    assert_encodes_to(&val, &[0x81, 0xa1, 0x61, 0x92, 0xc0, 0xff]);
+}
+
+#[test]
+fn pack_f64_shrink_floats() {
+    let mut buf = Vec::new();
+    let config = EncodeConfig {
+        shrink_floats: true,
+        ..EncodeConfig::default()
+    };
+
+    write_value_with_config(&mut buf, &Value::F64(0.5), &config).unwrap();
+
+    assert_eq!(vec![0xca, 0x3f, 0x00, 0x00, 0x00], buf);
+    assert_eq!(Value::F32(0.5), rmpv::decode::read_value(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn pack_f64_shrink_floats_nested() {
+    let mut buf = Vec::new();
+    let config = EncodeConfig {
+        shrink_floats: true,
+        ..EncodeConfig::default()
+    };
+
+    write_value_with_config(&mut buf, &Value::Array(vec![Value::F64(-2.0)]), &config).unwrap();
+
+    assert_eq!(vec![0x91, 0xca, 0xc0, 0x00, 0x00, 0x00], buf);
+}
+
+#[test]
+fn pack_negative_zero_preserved() {
+    let mut buf = Vec::new();
+
+    write_value_with_config(&mut buf, &Value::F64(-0.0), &EncodeConfig::default()).unwrap();
+
+    assert_eq!(vec![0xcb, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], buf);
+}
+
+#[test]
+fn pack_negative_zero_normalized() {
+    let mut buf = Vec::new();
+    let config = EncodeConfig {
+        normalize_negative_zero: true,
+        ..EncodeConfig::default()
+    };
+
+    write_value_with_config(&mut buf, &Value::Array(vec![Value::F64(-0.0), Value::F32(-0.0)]), &config).unwrap();
+
+    assert_eq!(vec![0x92, 0xcb, 0, 0, 0, 0, 0, 0, 0, 0, 0xca, 0, 0, 0, 0], buf);
+}
+
+#[test]
+fn pack_f64_lossy_shrink_floats() {
+    let mut buf = Vec::new();
+    let config = EncodeConfig {
+        shrink_floats: true,
+        ..EncodeConfig::default()
+    };
+
+    write_value_with_config(&mut buf, &Value::F64(0.1), &config).unwrap();
+
+    assert_eq!(vec![0xcb, 0x3f, 0xb9, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a], buf);
+}
+
+#[test]
+fn pack_f64_without_shrink_floats() {
+    let mut buf = Vec::new();
+
+    write_value(&mut buf, &Value::F64(0.5)).unwrap();
+
+    assert_eq!(vec![0xcb, 0x3f, 0xe0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], buf);
+}
+
+fn write_with_policy(val: &Value, policy: NonFinitePolicy) -> Result<Vec<u8>, rmpv::encode::Error> {
+    let mut buf = Vec::new();
+    let config = EncodeConfig {
+        non_finite_floats: policy,
+        ..EncodeConfig::default()
+    };
+
+    write_value_with_config(&mut buf, val, &config).map(|()| buf)
+}
+
+#[test]
+fn pack_nan_as_is() {
+    let buf = write_with_policy(&Value::F64(f64::NAN), NonFinitePolicy::AsIs).unwrap();
+
+    assert_eq!(0xcb, buf[0]);
+    match rmpv::decode::read_value(&mut &buf[..]).unwrap() {
+        Value::F64(val) => assert!(val.is_nan()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn pack_nan_as_null() {
+    assert_eq!(vec![0xc0], write_with_policy(&Value::F64(f64::NAN), NonFinitePolicy::AsNull).unwrap());
+    assert_eq!(vec![0x91, 0xc0],
+               write_with_policy(&Value::Array(vec![Value::F32(f32::INFINITY)]), NonFinitePolicy::AsNull).unwrap());
+}
+
+#[test]
+fn pack_nan_reject() {
+    match write_with_policy(&Value::F32(f32::NAN), NonFinitePolicy::Reject) {
+        Err(rmpv::encode::Error::InvalidDataWrite(err)) => {
+            assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+            let inner = err.get_ref().and_then(|err| err.downcast_ref::<NonFiniteFloat>()).unwrap();
+            assert!(inner.0.is_nan());
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn pack_finite_reject() {
+    assert_eq!(vec![0xca, 0x3f, 0x80, 0x00, 0x00],
+               write_with_policy(&Value::F32(1.0), NonFinitePolicy::Reject).unwrap());
+}
+
+fn round_trip(val: &Value) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_value(&mut buf, val).unwrap();
+    assert_eq!(*val, rmpv::decode::read_value(&mut &buf[..]).unwrap());
+    buf
+}
+
+#[test]
+fn pass_unknown_ext_round_trip() {
+    let val = Value::Ext(42, vec![1, 2, 3, 4, 5]);
+
+    // There is no fixext of 5 bytes, hence the ext8.
+    assert_eq!(vec![0xc7, 0x05, 0x2a, 0x01, 0x02, 0x03, 0x04, 0x05], round_trip(&val));
+}
+
+#[test]
+fn pass_unknown_fixext_round_trip() {
+    let val = Value::Ext(42, vec![1, 2, 3, 4]);
+    assert_eq!(vec![0xd6, 0x2a, 0x01, 0x02, 0x03, 0x04], round_trip(&val));
+
+    // Reserved type ids survive as well.
+    let val = Value::Ext(-100, vec![1, 2]);
+    assert_eq!(vec![0xd5, 0x9c, 0x01, 0x02], round_trip(&val));
+}
+
+#[test]
+fn pack_raw_field_verbatim() {
+    // A non-canonical uint8 1, which must not be re-encoded.
+    let raw = vec![0xcc, 0x01];
+    let val = Value::Map(vec![
+        (Value::from("a"), Value::Raw(raw.clone())),
+        (Value::from("b"), Value::from(true)),
+    ]);
+
+    let mut buf = Vec::new();
+    write_value(&mut buf, &val).unwrap();
+
+    assert_eq!(vec![0x82, 0xa1, 0x61, 0xcc, 0x01, 0xa1, 0x62, 0xc3], buf);
+    assert_eq!(&raw[..], &buf[3..5]);
+}
+
+#[test]
+fn pack_ext4_prefer_fixext() {
+    let val = Value::Ext(1, vec![1, 2, 3, 4]);
+
+    let mut buf = Vec::new();
+    write_value(&mut buf, &val).unwrap();
+
+    assert_eq!(vec![0xd6, 0x01, 0x01, 0x02, 0x03, 0x04], buf);
+}
+
+#[test]
+fn pack_ext4_without_prefer_fixext() {
+    let val = Value::Ext(1, vec![1, 2, 3, 4]);
+    let config = EncodeConfig {
+        prefer_fixext: false,
+        ..EncodeConfig::default()
+    };
+
+    let mut buf = Vec::new();
+    write_value_with_config(&mut buf, &val, &config).unwrap();
+
+    assert_eq!(vec![0xc7, 0x04, 0x01, 0x01, 0x02, 0x03, 0x04], buf);
+    assert_eq!(val, rmpv::decode::read_value(&mut &buf[..]).unwrap());
+
+    // Other lengths are written as usual.
+    let mut buf = Vec::new();
+    write_value_with_config(&mut buf, &Value::Ext(1, vec![0; 3]), &config).unwrap();
+
+    assert_eq!(vec![0xc7, 0x03, 0x01, 0x00, 0x00, 0x00], buf);
+}
+
+/// Drains the encoder through a buffer of `chunk_len` bytes, checking every chunk but the last is
+/// full.
+fn encode_chunked(mut enc: ChunkedEncoder<'_>, chunk_len: usize) -> Vec<u8> {
+    let mut buf = Vec::new();
+    let mut chunk = vec![0; chunk_len];
+    loop {
+        let len = enc.next_chunk(&mut chunk).unwrap();
+        if len == 0 {
+            break;
+        }
+        assert!(len == chunk_len || enc.is_finished());
+        buf.extend_from_slice(&chunk[..len]);
+    }
+    buf
+}
+
+#[test]
+fn pack_large_tree_chunked() {
+    let records = (0..500)
+        .map(|id| {
+            Value::Map(vec![
+                (Value::from("id"), Value::from(id)),
+                (Value::from("name"), Value::from(format!("record #{}", id))),
+                (Value::from("data"), Value::Binary(vec![id as u8; id % 100])),
+                (Value::from("tags"), Value::Array(vec![Value::from("a"), Value::Ext(1, vec![2; 16])])),
+                (Value::from("nested"), Value::Array(vec![Value::Array(vec![]), Value::Map(vec![])])),
+                (Value::from("ratio"), Value::F64(id as f64 / 3.0)),
+            ])
+        })
+        .collect();
+    let val = Value::Array(vec![Value::Array(records), Value::from("a".repeat(300)), Value::Nil]);
+
+    let mut expected = Vec::new();
+    write_value(&mut expected, &val).unwrap();
+
+    assert_eq!(expected, encode_chunked(ChunkedEncoder::new(&val), 64));
+    assert_eq!(expected, encode_chunked(ChunkedEncoder::new(&val), 1));
+}
+
+#[test]
+fn pack_chunked_finished() {
+    let val = Value::from(42);
+    let mut enc = ChunkedEncoder::new(&val);
+    assert!(!enc.is_finished());
+
+    let mut chunk = [0; 64];
+    assert_eq!(0, enc.next_chunk(&mut []).unwrap());
+    assert_eq!(1, enc.next_chunk(&mut chunk).unwrap());
+    assert!(enc.is_finished());
+    assert_eq!(0, enc.next_chunk(&mut chunk).unwrap());
+}
+
+#[test]
+fn pack_chunked_with_config() {
+    let val = Value::Array(vec![Value::F64(0.5), Value::F64(f64::NAN)]);
+    let config = EncodeConfig {
+        shrink_floats: true,
+        non_finite_floats: NonFinitePolicy::AsNull,
+        ..EncodeConfig::default()
+    };
+
+    let mut expected = Vec::new();
+    write_value_with_config(&mut expected, &val, &config).unwrap();
+
+    assert_eq!(vec![0x92, 0xca, 0x3f, 0x00, 0x00, 0x00, 0xc0], expected);
+    assert_eq!(expected, encode_chunked(ChunkedEncoder::with_config(&val, config), 3));
+}
+
+#[test]
+fn pack_chunked_without_prefer_fixext() {
+    let val = Value::Array(vec![Value::Ext(1, vec![2; 16]), Value::Ext(-1, vec![3])]);
+    let config = EncodeConfig {
+        prefer_fixext: false,
+        ..EncodeConfig::default()
+    };
+
+    let mut expected = Vec::new();
+    write_value_with_config(&mut expected, &val, &config).unwrap();
+
+    assert_eq!([0x92, 0xc7, 0x10, 0x01], expected[..4]);
+    assert_eq!(expected, encode_chunked(ChunkedEncoder::with_config(&val, config), 5));
+}
+
+fn int_keyed_map(keys: &[i64]) -> Value {
+    Value::Map(keys.iter().map(|&key| (Value::from(key), Value::Nil)).collect())
+}
+
+#[test]
+fn pack_map_sort_keys_semantic() {
+    let config = EncodeConfig {
+        sort_keys: SortMode::Semantic,
+        ..EncodeConfig::default()
+    };
+    let mut buf = Vec::new();
+
+    write_value_with_config(&mut buf, &int_keyed_map(&[10, 300, -1, 2]), &config).unwrap();
+
+    let mut expected = Vec::new();
+    write_value(&mut expected, &int_keyed_map(&[-1, 2, 10, 300])).unwrap();
+    assert_eq!(expected, buf);
+}
+
+#[test]
+fn pack_map_sort_keys_canonical() {
+    let config = EncodeConfig {
+        sort_keys: SortMode::Canonical,
+        ..EncodeConfig::default()
+    };
+    let mut buf = Vec::new();
+
+    write_value_with_config(&mut buf, &int_keyed_map(&[10, 300, -1, 2]), &config).unwrap();
+
+    // 0x02, 0x0a, 0xcd 0x01 0x2c, 0xff: negative fixints sort last by their bytes.
+    let mut expected = Vec::new();
+    write_value(&mut expected, &int_keyed_map(&[2, 10, 300, -1])).unwrap();
+    assert_eq!(expected, buf);
+}
+
+#[test]
+fn pack_nested_map_sort_keys() {
+    let config = EncodeConfig {
+        sort_keys: SortMode::Semantic,
+        ..EncodeConfig::default()
+    };
+    let val = Value::Array(vec![Value::Map(vec![
+        (Value::from("b"), int_keyed_map(&[3, 1])),
+        (Value::from("a"), Value::Nil),
+    ])]);
+    let mut buf = Vec::new();
+
+    write_value_with_config(&mut buf, &val, &config).unwrap();
+
+    assert_eq!(vec![0x91, 0x82, 0xa1, 0x61, 0xc0, 0xa1, 0x62, 0x82, 0x01, 0xc0, 0x03, 0xc0], buf);
+    assert_eq!(buf, encode_chunked(ChunkedEncoder::with_config(&val, config), 3));
+}
+
+#[test]
+fn pack_chunked_rejected_float() {
+    let val = Value::Array(vec![Value::from(1), Value::F32(f32::NAN)]);
+    let config = EncodeConfig {
+        non_finite_floats: NonFinitePolicy::Reject,
+        ..EncodeConfig::default()
+    };
+    let mut enc = ChunkedEncoder::with_config(&val, config);
+
+    let mut chunk = [0; 64];
+    assert!(enc.next_chunk(&mut chunk).is_err());
+    assert!(enc.is_finished());
+    assert_eq!(0, enc.next_chunk(&mut chunk).unwrap());
+}
+
+#[test]
+fn pack_padded_fitting_frame() {
+    let val = Value::Array(vec![Value::from(1), Value::from(300)]);
+    let mut buf = Vec::new();
+
+    write_value_padded(&mut buf, &val, 16).unwrap();
+
+    assert_eq!(16, buf.len());
+    assert_eq!([0x92, 0x01, 0xcd, 0x01, 0x2c], buf[..5]);
+    assert_eq!([0xc0; 11], buf[5..]);
+    assert_eq!(val, rmpv::decode::read_value(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn pack_padded_exact_frame() {
+    let mut buf = Vec::new();
+
+    write_value_padded(&mut buf, &Value::from(300), 3).unwrap();
+
+    assert_eq!(vec![0xcd, 0x01, 0x2c], buf);
+}
+
+#[test]
+fn pack_padded_overflowing_frame() {
+    let mut buf = Vec::new();
+
+    match write_value_padded(&mut buf, &Value::from("too long"), 8) {
+        Err(rmpv::encode::Error::InvalidDataWrite(err)) => {
+            assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+            let err = err.get_ref().unwrap().downcast_ref::<ValueTooLarge>().unwrap();
+            assert_eq!(&ValueTooLarge { len: 9, frame_size: 8 }, err);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn pack_btreemap_sorted() {
+    let mut map = BTreeMap::new();
+    map.insert("zeta".to_string(), Value::from(3));
+    map.insert("alpha".to_string(), Value::Array(vec![Value::Nil]));
+    map.insert("mu".to_string(), Value::from("m"));
+
+    let mut buf = Vec::new();
+    write_btreemap(&mut buf, &map).unwrap();
+
+    let val = rmpv::decode::read_value(&mut &buf[..]).unwrap();
+    let expected = vec![
+        (Value::from("alpha"), Value::Array(vec![Value::Nil])),
+        (Value::from("mu"), Value::from("m")),
+        (Value::from("zeta"), Value::from(3)),
+    ];
+    assert_eq!(expected, Vec::<(Value, Value)>::try_from(val).unwrap());
+}
+
+#[test]
+fn pack_btreemap_empty() {
+    let mut buf = Vec::new();
+    write_btreemap(&mut buf, &BTreeMap::new()).unwrap();
+
+    assert_eq!(vec![0x80], buf);
+}
+
+#[test]
+fn pack_btreemap_order_differs_from_canonical() {
+    let mut map = BTreeMap::new();
+    map.insert("b".to_string(), Value::Nil);
+    map.insert("aa".to_string(), Value::Nil);
+    let mut buf = Vec::new();
+    write_btreemap(&mut buf, &map).unwrap();
+
+    let val = Value::Map(vec![(Value::from("b"), Value::Nil), (Value::from("aa"), Value::Nil)]);
+    let config = EncodeConfig {
+        sort_keys: SortMode::Canonical,
+        ..EncodeConfig::default()
+    };
+    let mut canonical = Vec::new();
+    write_value_with_config(&mut canonical, &val, &config).unwrap();
+
+    assert_eq!(vec![0x82, 0xa2, 0x61, 0x61, 0xc0, 0xa1, 0x62, 0xc0], buf);
+    assert_eq!(vec![0x82, 0xa1, 0x62, 0xc0, 0xa2, 0x61, 0x61, 0xc0], canonical);
+}
+
+#[test]
+fn pack_binary_ref_fails() {
+    let mut buf = Vec::new();
+
+    match write_value(&mut buf, &Value::BinaryRef { offset: 0, len: 1 }) {
+        Err(rmpv::encode::Error::InvalidDataWrite(err)) => assert_eq!(std::io::ErrorKind::InvalidInput, err.kind()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+/// A hasher recording the bytes fed into it, to check them regardless of how they are split.
+#[derive(Default)]
+struct BytesHasher(Vec<u8>);
+
+impl Hasher for BytesHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        unimplemented!()
+    }
+}
+
+#[test]
+fn pack_through_hashing_writer() {
+    let val = Value::Map(vec![
+        (Value::from("b"), Value::Binary(vec![0; 100])),
+        (Value::from("a"), Value::Array(vec![Value::from(1), Value::F64(0.5)])),
+    ]);
+    let config = EncodeConfig {
+        sort_keys: SortMode::Canonical,
+        ..EncodeConfig::default()
+    };
+
+    let mut wr = HashingWriter::new(Vec::new(), BytesHasher::default());
+    write_value_with_config(&mut wr, &val, &config).unwrap();
+    let (buf, hasher) = wr.into_inner();
+
+    let mut expected = Vec::new();
+    write_value_with_config(&mut expected, &val, &config).unwrap();
+
+    assert_eq!(expected, buf);
+    assert_eq!(expected, hasher.0);
+}
+
+#[test]
+fn pack_through_hashing_writer_short_write() {
+    let mut out = [0; 3];
+    let mut wr = HashingWriter::new(&mut out[..], BytesHasher::default());
+
+    assert!(write_value(&mut wr, &Value::from("abcd")).is_err());
+
+    assert_eq!([0xa4, 0x61, 0x62], wr.hasher().0[..]);
+}
+
+fn binary_chunks_config() -> (EncodeConfig, DecodeConfig) {
+    let encode = EncodeConfig {
+        binary_chunks: Some(BinaryChunks { ext_type: 42, max_len: 1024 }),
+        ..EncodeConfig::default()
+    };
+    let decode = DecodeConfig {
+        binary_chunks: Some(42),
+        ..DecodeConfig::default()
+    };
+    (encode, decode)
+}
+
+#[test]
+fn pack_binary_chunks_round_trip() {
+    let blob: Vec<u8> = (0..10 * 1024).map(|n| (n % 251) as u8).collect();
+    let val = Value::Map(vec![(Value::from("blob"), Value::Binary(blob.clone()))]);
+    let (encode, decode) = binary_chunks_config();
+
+    let mut buf = Vec::new();
+    write_value_with_config(&mut buf, &val, &encode).unwrap();
+
+    // Without reassembly, the blob is an array of 10 chunks of 1 KiB, the last one flagged.
+    let chunks = match rmpv::decode::read_value(&mut &buf[..]).unwrap() {
+        Value::Map(mut map) => map.pop().unwrap().1,
+        other => panic!("unexpected value: {:?}", other),
+    };
+    let chunks = chunks.as_array().unwrap();
+    assert_eq!(10, chunks.len());
+    for (idx, chunk) in chunks.iter().enumerate() {
+        let (ty, data) = chunk.as_ext().unwrap();
+        assert_eq!(42, ty);
+        assert_eq!(1024 + 5, data.len());
+        assert_eq!((idx as u32).to_be_bytes(), data[..4]);
+        assert_eq!(u8::from(idx == 9), data[4]);
+    }
+
+    assert_eq!(val, read_value_with_config(&mut &buf[..], &decode).unwrap());
+}
+
+#[test]
+fn pack_short_binary_unchunked() {
+    let (encode, decode) = binary_chunks_config();
+    let val = Value::Binary(vec![1; 1024]);
+
+    let mut buf = Vec::new();
+    write_value_with_config(&mut buf, &val, &encode).unwrap();
+
+    assert_eq!([0xc5, 0x04, 0x00], buf[..3]);
+    assert_eq!(val, read_value_with_config(&mut &buf[..], &decode).unwrap());
+}
+
+#[test]
+fn pack_binary_chunks_through_chunked_encoder() {
+    let (encode, _) = binary_chunks_config();
+    let val = Value::Array(vec![Value::Binary(vec![7; 3000]), Value::Nil]);
+
+    let mut expected = Vec::new();
+    write_value_with_config(&mut expected, &val, &encode).unwrap();
+
+    assert_eq!(expected, encode_chunked(ChunkedEncoder::with_config(&val, encode.clone()), 512));
+    assert_eq!(expected, encode_chunked(ChunkedEncoder::with_config(&val, encode), 1));
+}
+
+#[test]
+fn pack_binary_chunks_without_prefer_fixext() {
+    // Chunks of 3 bytes take 8-byte payloads, which would otherwise be fixext8.
+    let config = EncodeConfig {
+        binary_chunks: Some(BinaryChunks { ext_type: 42, max_len: 3 }),
+        prefer_fixext: false,
+        ..EncodeConfig::default()
+    };
+    let val = Value::Binary(vec![1, 2, 3, 4, 5, 6]);
+
+    let mut buf = Vec::new();
+    write_value_with_config(&mut buf, &val, &config).unwrap();
+
+    assert_eq!(vec![
+        0x92,
+        0xc7, 0x08, 42, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x02, 0x03,
+        0xc7, 0x08, 42, 0x00, 0x00, 0x00, 0x01, 0x01, 0x04, 0x05, 0x06,
+    ], buf);
+    assert_eq!(buf, encode_chunked(ChunkedEncoder::with_config(&val, config), 2));
+}
+
+#[test]
+fn unpack_misordered_binary_chunks_kept() {
+    let (_, decode) = binary_chunks_config();
+    let val = Value::Array(vec![Value::Ext(42, vec![0, 0, 0, 1, 1, 0xaa]), Value::Ext(42, vec![0, 0, 0, 0, 0, 0xbb])]);
+
+    let mut buf = Vec::new();
+    write_value(&mut buf, &val).unwrap();
+
+    assert_eq!(val, read_value_with_config(&mut &buf[..], &decode).unwrap());
+}
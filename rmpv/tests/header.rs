@@ -0,0 +1,74 @@
+use std::io::ErrorKind;
+
+use rmp::MSGPACK_VERSION;
+use rmpv::decode::{read_value, Error};
+use rmpv::header::{read_header, write_header, write_value_with_header, Header, IncompatibleHeader};
+use rmpv::Value;
+
+const HEADER: Header = Header { ext_type: 42, schema_version: 3 };
+
+#[test]
+fn header_round_trip_matching_version() {
+    let val = Value::Array(vec![Value::from(1), Value::from("two")]);
+
+    let mut buf = Vec::new();
+    write_value_with_header(&mut buf, &val, HEADER).unwrap();
+
+    assert_eq!([0xd7, 42, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x03], buf[..10]);
+
+    let mut rd = &buf[..];
+    assert_eq!(3, read_header(&mut rd, HEADER).unwrap());
+    assert_eq!(val, read_value(&mut rd).unwrap());
+}
+
+#[test]
+fn read_header_accepts_older_schema_version() {
+    let mut buf = Vec::new();
+    write_header(&mut buf, Header { schema_version: 1, ..HEADER }).unwrap();
+
+    assert_eq!(1, read_header(&mut &buf[..], HEADER).unwrap());
+}
+
+#[test]
+fn read_header_rejects_future_schema_version() {
+    let mut buf = Vec::new();
+    write_value_with_header(&mut buf, &Value::Nil, Header { schema_version: 4, ..HEADER }).unwrap();
+
+    match read_header(&mut &buf[..], HEADER) {
+        Err(Error::InvalidDataRead(err)) => {
+            assert_eq!(ErrorKind::InvalidData, err.kind());
+            let err = err.get_ref().unwrap().downcast_ref::<IncompatibleHeader>().unwrap();
+            assert_eq!(IncompatibleHeader { msgpack_version: MSGPACK_VERSION, schema_version: 4 }, *err);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn read_header_rejects_future_msgpack_version() {
+    let buf = [0xd7, 42, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x03];
+
+    match read_header(&mut &buf[..], HEADER) {
+        Err(Error::InvalidDataRead(err)) => {
+            let err = err.get_ref().unwrap().downcast_ref::<IncompatibleHeader>().unwrap();
+            assert_eq!(6, err.msgpack_version);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn read_header_rejects_other_ext_types() {
+    let mut buf = Vec::new();
+    write_header(&mut buf, Header { ext_type: 7, ..HEADER }).unwrap();
+
+    assert!(read_header(&mut &buf[..], HEADER).is_err());
+}
+
+#[test]
+fn read_header_rejects_missing_header() {
+    let mut buf = Vec::new();
+    rmpv::encode::write_value(&mut buf, &Value::from("body")).unwrap();
+
+    assert!(read_header(&mut &buf[..], HEADER).is_err());
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "with-json")]
+
+use rmpv::Value;
+
+#[test]
+fn json_object_round_trip() {
+    let json: serde_json::Value = serde_json::from_str(r#"{
+        "name": "le message",
+        "id": 42,
+        "offset": -100500,
+        "ratio": 0.5,
+        "tags": ["a", "b", null, true],
+        "nested": {"empty": {}}
+    }"#).unwrap();
+
+    let val = Value::from(json.clone());
+    assert_eq!(Value::from("le message"), val["name"]);
+    assert_eq!(Value::from(42), val["id"]);
+    assert_eq!(Value::from(-100500), val["offset"]);
+    assert_eq!(Value::F64(0.5), val["ratio"]);
+    assert_eq!(Value::Map(vec![]), val["nested"]["empty"]);
+
+    assert_eq!(json, serde_json::Value::from(val));
+}
+
+#[test]
+fn binary_to_json_as_base64() {
+    let val = Value::Map(vec![
+        (Value::from("one"), Value::Binary(vec![0x01])),
+        (Value::from("two"), Value::Binary(vec![0x01, 0x02])),
+        (Value::from("three"), Value::Binary(b"Man".to_vec())),
+        (Value::from(4), Value::Ext(2, vec![0xff, 0xfe, 0xfd, 0xfc])),
+    ]);
+
+    let expected: serde_json::Value = serde_json::from_str(r#"{
+        "one": "AQ==",
+        "two": "AQI=",
+        "three": "TWFu",
+        "4": [2, "//79/A=="]
+    }"#).unwrap();
+
+    assert_eq!(expected, serde_json::Value::from(val));
+}
+
+#[test]
+fn non_finite_float_to_json_null() {
+    assert_eq!(serde_json::Value::Null, serde_json::Value::from(Value::F64(f64::NAN)));
+    assert_eq!(serde_json::Value::Null, serde_json::Value::from(Value::F32(f32::INFINITY)));
+}
+
+#[test]
+fn binary_ref_to_json_as_object() {
+    let val = Value::Array(vec![Value::BinaryRef { offset: 10, len: 4 }]);
+
+    let expected: serde_json::Value = serde_json::from_str(r#"[{"offset": 10, "len": 4}]"#).unwrap();
+    assert_eq!(expected, serde_json::Value::from(val));
+}
+
+#[test]
+fn try_into_json() {
+    let val = Value::Map(vec![(Value::from("a"), Value::Array(vec![Value::from(1)]))]);
+    let expected: serde_json::Value = serde_json::from_str(r#"{"a": [1]}"#).unwrap();
+    assert_eq!(Some(expected), val.try_into_json());
+
+    let val = Value::Map(vec![(Value::from("a"), Value::BinaryRef { offset: 10, len: 4 })]);
+    assert_eq!(None, val.try_into_json());
+    let val = Value::Map(vec![(Value::BinaryRef { offset: 10, len: 4 }, Value::Nil)]);
+    assert_eq!(None, val.try_into_json());
+}
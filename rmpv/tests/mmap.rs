@@ -0,0 +1,71 @@
+#![cfg(feature = "memmap")]
+
+use std::fs;
+use std::path::PathBuf;
+
+use rmpv::decode::{from_mmap, Error};
+use rmpv::encode::write_value;
+use rmpv::{Value, ValueRef};
+
+/// A file in the temporary directory, removed when dropped.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn new(name: &str, data: &[u8]) -> Self {
+        let path = std::env::temp_dir().join(format!("rmpv-{}-{}.msgpack", name, std::process::id()));
+        fs::write(&path, data).unwrap();
+        TempFile(path)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[test]
+fn from_mapped_file() {
+    let val = Value::Map(vec![
+        (Value::from("name"), Value::from("le message")),
+        (Value::from("data"), Value::Binary(vec![7; 4096])),
+    ]);
+    let mut buf = Vec::new();
+    write_value(&mut buf, &val).unwrap();
+    // A second value, which is left alone.
+    buf.push(0xc0);
+    let file = TempFile::new("from-mapped-file", &buf);
+
+    let mapped = unsafe { from_mmap(&file.0) }.unwrap();
+    let decoded = mapped.value().unwrap();
+
+    assert_eq!(val.as_ref(), decoded);
+    assert_eq!(&buf[..], mapped.as_bytes());
+    // The binary borrows from the mapping.
+    match decoded {
+        ValueRef::Map(ref entries) => match entries[1].1 {
+            ValueRef::Binary(data) => assert!(mapped.as_bytes().as_ptr_range().contains(&data.as_ptr())),
+            ref other => panic!("unexpected value: {:?}", other),
+        },
+        ref other => panic!("unexpected value: {:?}", other),
+    }
+}
+
+#[test]
+fn from_mapped_empty_file() {
+    let file = TempFile::new("from-mapped-empty-file", &[]);
+
+    let mapped = unsafe { from_mmap(&file.0) }.unwrap();
+
+    match mapped.value() {
+        Err(Error::InvalidMarkerRead(..)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_missing_file_mmap() {
+    let path = std::env::temp_dir().join("rmpv-from-missing-file-mmap.msgpack");
+
+    assert!(unsafe { from_mmap(&path) }.is_err());
+}
@@ -0,0 +1,46 @@
+use rmpv::path::PathSegment;
+use rmpv::Value;
+
+fn config(name: &str) -> Value {
+    Value::Map(vec![
+        (Value::from("version"), Value::from(1)),
+        (Value::from("config"), Value::Array(vec![
+            Value::Map(vec![(Value::from("name"), Value::from("a"))]),
+            Value::Map(vec![(Value::from("name"), Value::from("b"))]),
+            Value::Map(vec![(Value::from("name"), Value::from(name))]),
+        ])),
+    ])
+}
+
+#[test]
+fn diff_identical_trees() {
+    assert_eq!(None, config("c").diff(&config("c")));
+}
+
+#[test]
+fn diff_nested_leaf() {
+    let path = config("c").diff(&config("d")).unwrap();
+
+    assert_eq!(&[
+        PathSegment::Key("config".into()),
+        PathSegment::Index(2),
+        PathSegment::Key("name".into()),
+    ], path.segments());
+    assert_eq!("$.config[2].name", path.to_string());
+}
+
+#[test]
+fn diff_map_keys() {
+    let lhs = Value::Map(vec![(Value::from(1), Value::Nil), (Value::from(2), Value::Nil)]);
+    let rhs = Value::Map(vec![(Value::from(1), Value::Nil), (Value::from(3), Value::Nil)]);
+
+    assert_eq!(&[PathSegment::Index(1)], lhs.diff(&rhs).unwrap().segments());
+}
+
+#[test]
+fn diff_array_lengths() {
+    let lhs = Value::Array(vec![Value::from(1)]);
+    let rhs = Value::Array(vec![Value::from(1), Value::from(2)]);
+
+    assert!(lhs.diff(&rhs).unwrap().is_empty());
+}
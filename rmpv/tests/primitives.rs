@@ -0,0 +1,122 @@
+//! Checks that `write_value` picks the same encoding as the `rmp` primitive for every kind of
+//! value, including at the boundaries between markers.
+
+use rmp::encode::{
+    write_array_len, write_bin, write_bool, write_ext, write_f32, write_f64, write_map_len,
+    write_nil, write_sint, write_str, write_uint,
+};
+use rmpv::encode::write_value;
+use rmpv::Value;
+
+fn assert_same_encoding<F>(val: Value, write: F)
+    where F: FnOnce(&mut Vec<u8>)
+{
+    let mut expected = Vec::new();
+    write(&mut expected);
+
+    let mut buf = Vec::new();
+    write_value(&mut buf, &val).unwrap();
+
+    assert_eq!(expected, buf, "{:?}", val);
+}
+
+#[test]
+fn pass_nil() {
+    assert_same_encoding(Value::Nil, |wr| write_nil(wr).unwrap());
+}
+
+#[test]
+fn pass_bool() {
+    for val in [false, true] {
+        assert_same_encoding(Value::from(val), |wr| write_bool(wr, val).unwrap());
+    }
+}
+
+#[test]
+fn pass_uint_boundaries() {
+    let vals = [
+        0, 127, 128, 255, 256, 65535, 65536,
+        u32::MAX as u64, u32::MAX as u64 + 1, u64::MAX,
+    ];
+
+    for val in vals {
+        assert_same_encoding(Value::from(val), |wr| {
+            write_uint(wr, val).unwrap();
+        });
+    }
+}
+
+#[test]
+fn pass_sint_boundaries() {
+    let vals = [
+        -1, -32, -33, -128, -129, -32768, -32769,
+        i32::MIN as i64, i32::MIN as i64 - 1, i64::MIN,
+    ];
+
+    for val in vals {
+        assert_same_encoding(Value::from(val), |wr| {
+            write_sint(wr, val).unwrap();
+        });
+    }
+}
+
+#[test]
+fn pass_floats() {
+    for val in [0.0, -1.5, f32::MAX, f32::INFINITY] {
+        assert_same_encoding(Value::F32(val), |wr| write_f32(wr, val).unwrap());
+    }
+    for val in [0.0, 0.1, f64::MIN, f64::NEG_INFINITY] {
+        assert_same_encoding(Value::F64(val), |wr| write_f64(wr, val).unwrap());
+    }
+}
+
+#[test]
+fn pass_str_boundaries() {
+    for len in [0, 31, 32, 255, 256, 65535, 65536] {
+        let val = "a".repeat(len);
+        assert_same_encoding(Value::from(val.as_str()), |wr| write_str(wr, &val).unwrap());
+    }
+}
+
+#[test]
+fn pass_bin_boundaries() {
+    for len in [0, 255, 256, 65535, 65536] {
+        let val = vec![0xffu8; len];
+        assert_same_encoding(Value::from(&val[..]), |wr| write_bin(wr, &val).unwrap());
+    }
+}
+
+#[test]
+fn pass_array_boundaries() {
+    for len in [0, 15, 16, 65535, 65536] {
+        let val = Value::Array(vec![Value::Nil; len]);
+        assert_same_encoding(val, |wr| {
+            write_array_len(wr, len as u32).unwrap();
+            for _ in 0..len {
+                write_nil(wr).unwrap();
+            }
+        });
+    }
+}
+
+#[test]
+fn pass_map_boundaries() {
+    for len in [0, 15, 16, 65535, 65536] {
+        let val = Value::Map((0..len).map(|key| (Value::from(key), Value::Nil)).collect());
+        assert_same_encoding(val, |wr| {
+            write_map_len(wr, len as u32).unwrap();
+            for key in 0..len {
+                write_uint(wr, key as u64).unwrap();
+                write_nil(wr).unwrap();
+            }
+        });
+    }
+}
+
+#[test]
+fn pass_ext_boundaries() {
+    for len in [0, 1, 2, 3, 4, 8, 16, 17, 255, 256, 65535, 65536] {
+        let data = vec![0x2a; len];
+        assert_same_encoding(Value::Ext(42, data.clone()), |wr| write_ext(wr, 42, &data).unwrap());
+    }
+}
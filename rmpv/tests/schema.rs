@@ -0,0 +1,199 @@
+use rmpv::encode::write_value;
+use rmpv::schema::{
+    read_positional, read_value_profiled, write_positional, PathSegment, Schema, SchemaProfile, ValidationErrorKind,
+    ValueType,
+};
+use rmpv::Value;
+
+fn user_schema() -> Schema {
+    Schema::Map(vec![
+        ("id".into(), Schema::Int),
+        ("name".into(), Schema::Str),
+        ("email".into(), Schema::Optional(Box::new(Schema::Str))),
+        ("roles".into(), Schema::Array(Box::new(Schema::Map(vec![
+            ("name".into(), Schema::Str),
+            ("level".into(), Schema::Int),
+        ])))),
+    ])
+}
+
+fn role(name: &str, level: Value) -> Value {
+    Value::Map(vec![
+        (Value::from("name"), Value::from(name)),
+        (Value::from("level"), level),
+    ])
+}
+
+#[test]
+fn validate_conforming_tree() {
+    let val = Value::Map(vec![
+        (Value::from("id"), Value::from(42)),
+        (Value::from("name"), Value::from("John")),
+        (Value::from("extra"), Value::Boolean(true)),
+        (Value::from("roles"), Value::Array(vec![role("admin", Value::from(1))])),
+    ]);
+
+    assert_eq!(Ok(()), val.validate(&user_schema()));
+}
+
+#[test]
+fn validate_reports_path_of_mismatch() {
+    let val = Value::Map(vec![
+        (Value::from("id"), Value::from(42)),
+        (Value::from("name"), Value::from("John")),
+        (Value::from("roles"), Value::Array(vec![
+            role("admin", Value::from(1)),
+            role("user", Value::from("high")),
+        ])),
+    ]);
+
+    let err = val.validate(&user_schema()).unwrap_err();
+
+    assert_eq!(&[
+        PathSegment::Key("roles".into()),
+        PathSegment::Index(1),
+        PathSegment::Key("level".into()),
+    ], err.path());
+    assert_eq!(&ValidationErrorKind::TypeMismatch("int"), err.kind());
+    assert_eq!("$.roles[1].level: expected int", err.to_string());
+}
+
+#[test]
+fn validate_reports_missing_field() {
+    let val = Value::Map(vec![(Value::from("id"), Value::from(42))]);
+
+    let err = val.validate(&user_schema()).unwrap_err();
+
+    assert!(err.path().is_empty());
+    assert_eq!(&ValidationErrorKind::MissingField("name".into()), err.kind());
+    assert_eq!("$: missing field name", err.to_string());
+}
+
+#[test]
+fn positional_round_trip() {
+    let val = Value::Map(vec![
+        (Value::from("roles"), Value::Array(vec![role("admin", Value::from(1)), role("dev", Value::from(2))])),
+        (Value::from("name"), Value::from("John")),
+        (Value::from("id"), Value::from(42)),
+    ]);
+    let mut keyed = Vec::new();
+    rmpv::encode::write_value(&mut keyed, &val).unwrap();
+
+    let mut buf = Vec::new();
+    write_positional(&mut buf, &val, &user_schema()).unwrap();
+    let decoded = read_positional(&mut &buf[..], &user_schema()).unwrap();
+
+    // [42, "John", nil, [["admin", 1], ["dev", 2]]]
+    assert_eq!(vec![
+        0x94, 0x2a, 0xa4, 0x4a, 0x6f, 0x68, 0x6e, 0xc0, 0x92,
+        0x92, 0xa5, 0x61, 0x64, 0x6d, 0x69, 0x6e, 0x01,
+        0x92, 0xa3, 0x64, 0x65, 0x76, 0x02,
+    ], buf);
+    assert!(buf.len() * 2 < keyed.len());
+    assert_eq!(Value::Map(vec![
+        (Value::from("id"), Value::from(42)),
+        (Value::from("name"), Value::from("John")),
+        (Value::from("email"), Value::Nil),
+        (Value::from("roles"), Value::Array(vec![role("admin", Value::from(1)), role("dev", Value::from(2))])),
+    ]), decoded);
+}
+
+#[test]
+fn positional_optional_present() {
+    let schema = Schema::Map(vec![("email".into(), Schema::Optional(Box::new(Schema::Str)))]);
+    let val = Value::Map(vec![(Value::from("email"), Value::from("a@b"))]);
+
+    let mut buf = Vec::new();
+    write_positional(&mut buf, &val, &schema).unwrap();
+
+    assert_eq!(vec![0x91, 0xa3, 0x61, 0x40, 0x62], buf);
+    assert_eq!(val, read_positional(&mut &buf[..], &schema).unwrap());
+}
+
+#[test]
+fn positional_rejects_unknown_field() {
+    let schema = Schema::Map(vec![("id".into(), Schema::Int)]);
+    let val = Value::Map(vec![(Value::from("id"), Value::from(1)), (Value::from("extra"), Value::Nil)]);
+
+    match write_positional(&mut Vec::new(), &val, &schema) {
+        Err(rmpv::encode::Error::InvalidDataWrite(err)) => assert_eq!(std::io::ErrorKind::InvalidInput, err.kind()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn positional_rejects_mismatching_value() {
+    let val = Value::Map(vec![(Value::from("id"), Value::from("42"))]);
+
+    match write_positional(&mut Vec::new(), &val, &Schema::Map(vec![("id".into(), Schema::Int)])) {
+        Err(rmpv::encode::Error::InvalidDataWrite(err)) => assert_eq!("$.id: expected int", err.to_string()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn positional_wrong_field_count() {
+    let schema = Schema::Map(vec![("id".into(), Schema::Int), ("name".into(), Schema::Str)]);
+    let buf = [0x91, 0x2a];
+
+    match read_positional(&mut &buf[..], &schema) {
+        Err(rmpv::decode::Error::InvalidLength(1)) => (),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn profile_counts_field_types_across_messages() {
+    let messages = [
+        Value::Map(vec![(Value::from("id"), Value::from(1)), (Value::from("name"), Value::from("John"))]),
+        Value::Map(vec![(Value::from("id"), Value::from("2")), (Value::from("roles"), Value::Array(vec![
+            role("admin", Value::from(1)),
+            role("user", Value::Nil),
+        ]))]),
+        Value::Map(vec![(Value::from("id"), Value::from(3))]),
+    ];
+
+    let mut buf = Vec::new();
+    for val in &messages {
+        write_value(&mut buf, val).unwrap();
+    }
+
+    let mut profile = SchemaProfile::new();
+    let mut rd = &buf[..];
+    for val in &messages {
+        assert_eq!(*val, read_value_profiled(&mut rd, &mut profile).unwrap());
+    }
+
+    assert_eq!(3, profile.values());
+    assert_eq!(2, profile.count("id", ValueType::Int));
+    assert_eq!(1, profile.count("id", ValueType::Str));
+    assert_eq!(0, profile.count("id", ValueType::Float));
+    assert_eq!(1, profile.field("id").unwrap().get(&ValueType::Str).copied().unwrap());
+    assert_eq!(1, profile.count("name", ValueType::Str));
+    assert_eq!(1, profile.count("roles", ValueType::Array));
+    assert_eq!(2, profile.count("roles.name", ValueType::Str));
+    assert_eq!(1, profile.count("roles.level", ValueType::Int));
+    assert_eq!(1, profile.count("roles.level", ValueType::Nil));
+    assert_eq!(None, profile.field("email"));
+
+    let fields: Vec<_> = profile.fields().map(|(field, _)| field).collect();
+    assert_eq!(vec!["id", "name", "roles", "roles.level", "roles.name"], fields);
+}
+
+#[test]
+fn profile_names_non_string_keys() {
+    let mut profile = SchemaProfile::new();
+    profile.record(&Value::Map(vec![(Value::from(7), Value::F64(1.5))]));
+
+    assert_eq!(1, profile.count("7", ValueType::Float));
+    assert_eq!("float", ValueType::Float.to_string());
+}
+
+#[test]
+fn profile_dotted_key_collides_with_nested_path() {
+    let mut profile = SchemaProfile::new();
+    profile.record(&Value::Map(vec![(Value::from("a.b"), Value::from(1))]));
+    profile.record(&Value::Map(vec![(Value::from("a"), Value::Map(vec![(Value::from("b"), Value::from(2))]))]));
+
+    assert_eq!(2, profile.count("a.b", ValueType::Int));
+}
@@ -0,0 +1,39 @@
+use std::mem;
+
+use rmpv::small::SmallValue;
+use rmpv::Value;
+
+#[test]
+fn small_value_size() {
+    assert!(mem::size_of::<SmallValue>() <= 16);
+    assert!(mem::size_of::<SmallValue>() < mem::size_of::<Value>());
+}
+
+#[test]
+fn small_value_round_trip() {
+    let val = Value::Map(vec![
+        (Value::from("ints"), Value::Array(vec![Value::from(0), Value::from(u64::MAX), Value::from(i64::MIN)])),
+        (Value::from(1), Value::Array(vec![Value::Nil, Value::Boolean(true), Value::F32(0.5), Value::F64(-1.5)])),
+        (Value::Binary(vec![1, 2]), Value::Ext(-1, vec![3])),
+        (Value::from("raw"), Value::Raw(vec![0xc0])),
+        (Value::from("ref"), Value::BinaryRef { offset: 10, len: 20 }),
+    ]);
+
+    let small = SmallValue::from(val.clone());
+
+    assert_eq!(val, Value::from(small));
+}
+
+#[test]
+fn small_value_inline_scalars() {
+    assert_eq!(SmallValue::PosInt(42), SmallValue::from(Value::from(42)));
+    assert_eq!(SmallValue::NegInt(-1), SmallValue::from(Value::from(-1)));
+    assert_eq!(Some(-1), SmallValue::NegInt(-1).as_i64());
+    assert_eq!(None, SmallValue::PosInt(u64::MAX).as_i64());
+    assert_eq!(Some("a"), SmallValue::from(Value::from("a")).as_str());
+}
+
+#[test]
+fn small_value_non_negative_neg_int_converts_to_pos_int() {
+    assert_eq!(SmallValue::PosInt(5), SmallValue::from(Value::from(SmallValue::NegInt(5))));
+}
@@ -0,0 +1,115 @@
+use rmpv::decode::{DecodeConfig, Error};
+use rmpv::encode::write_value;
+use rmpv::{string_table, Value};
+
+fn records() -> Value {
+    let statuses = ["pending", "active", "suspended"];
+    let records = (0..100)
+        .map(|id| {
+            Value::Map(vec![
+                (Value::from("id"), Value::from(id)),
+                (Value::from("status"), Value::from(statuses[id % 3])),
+                (Value::from("owner"), Value::from(format!("user{}", id % 7))),
+                (Value::from("tags"), Value::Array(vec![Value::from("imported"), Value::from("ok")])),
+            ])
+        })
+        .collect();
+    Value::Array(records)
+}
+
+#[test]
+fn pass_repeated_strings_round_trip() {
+    let val = records();
+
+    let mut plain = Vec::new();
+    write_value(&mut plain, &val).unwrap();
+    let mut buf = Vec::new();
+    string_table::write_value(&mut buf, &val).unwrap();
+
+    // Most of the payload is repeated keys and statuses.
+    assert!(buf.len() * 3 < plain.len() * 2, "{} vs {}", buf.len(), plain.len());
+    assert_eq!(val, string_table::read_value(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn pass_repeated_string_as_reference() {
+    let val = Value::Array(vec![Value::from("abc"), Value::from("abcd"), Value::from("abc"), Value::from("abcd")]);
+
+    let mut buf = Vec::new();
+    string_table::write_value(&mut buf, &val).unwrap();
+
+    // Strings shorter than MIN_LEN are repeated as is.
+    assert_eq!(vec![
+        0x94,
+        0xa3, 0x61, 0x62, 0x63,
+        0xa4, 0x61, 0x62, 0x63, 0x64,
+        0xa3, 0x61, 0x62, 0x63,
+        0xd4, string_table::EXT_TYPE as u8, 0x00,
+    ], buf);
+    assert_eq!(val, string_table::read_value(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn pass_many_distinct_strings_round_trip() {
+    // More than 256 distinct strings take 2-byte references.
+    let strings: Vec<Value> = (0..300).map(|i| Value::from(format!("string #{}", i))).collect();
+    let val = Value::Array(strings.iter().chain(strings.iter()).cloned().collect());
+
+    let mut buf = Vec::new();
+    string_table::write_value(&mut buf, &val).unwrap();
+
+    assert_eq!(val, string_table::read_value(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn pass_reserved_ext_rejected() {
+    let val = Value::Ext(string_table::EXT_TYPE, vec![0]);
+
+    assert!(string_table::write_value(&mut Vec::new(), &val).is_err());
+}
+
+#[test]
+fn from_dangling_reference() {
+    let buf = [0x92, 0xa4, 0x61, 0x62, 0x63, 0x64, 0xd4, string_table::EXT_TYPE as u8, 0x01];
+
+    match string_table::read_value(&mut &buf[..]) {
+        Err(Error::InvalidStringRef(buf)) => assert_eq!(vec![0x01], buf),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn pass_raw_rejected() {
+    // The string inside the raw bytes would be registered by the decoder only.
+    let val = Value::Array(vec![
+        Value::Raw(vec![0xa4, 0x61, 0x62, 0x63, 0x64]),
+        Value::from("wxyz"),
+        Value::from("wxyz"),
+    ]);
+
+    match string_table::write_value(&mut Vec::new(), &val) {
+        Err(rmpv::encode::Error::InvalidDataWrite(err)) => assert_eq!(std::io::ErrorKind::InvalidInput, err.kind()),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn from_repeated_references_limited_by_max_total_alloc() {
+    let long = Value::from("x".repeat(1000));
+    let val = Value::Array(vec![long; 1000]);
+
+    let mut buf = Vec::new();
+    string_table::write_value(&mut buf, &val).unwrap();
+    assert!(buf.len() < 5000);
+
+    let config = DecodeConfig { max_total_alloc: Some(1_000_000), ..DecodeConfig::default() };
+    match string_table::read_value_with_config(&mut &buf[..], &config) {
+        Err(Error::LengthLimitExceeded) => (),
+        other => panic!("unexpected result: {:?}", other.map(|val| val.heap_size())),
+    }
+
+    let config = DecodeConfig { max_total_alloc: Some(2 * 1024 * 1024), ..DecodeConfig::default() };
+    let decoded = string_table::read_value_with_config(&mut &buf[..], &config).unwrap();
+    assert_eq!(val, decoded);
+    assert!(decoded.heap_size() <= 2 * 1024 * 1024);
+}
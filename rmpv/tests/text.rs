@@ -0,0 +1,69 @@
+use rmpv::encode::write_value;
+use rmpv::text::parse_text;
+use rmpv::Value;
+
+fn encode(val: &Value) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_value(&mut buf, val).unwrap();
+    buf
+}
+
+#[test]
+fn parse_nested_fixture() {
+    let val = parse_text(r#"
+        {
+            "id": 300,
+            "neg": -1,
+            "tags": ["a", nil, true],
+            "data": b"00ff",
+            "ext": ext(-2, b"2a"),
+            1: {"ratio": 0.5f32}
+        }
+    "#).unwrap();
+
+    let expected = [
+        0x86,
+        0xa2, b'i', b'd', 0xcd, 0x01, 0x2c,
+        0xa3, b'n', b'e', b'g', 0xff,
+        0xa4, b't', b'a', b'g', b's', 0x93, 0xa1, b'a', 0xc0, 0xc3,
+        0xa4, b'd', b'a', b't', b'a', 0xc4, 0x02, 0x00, 0xff,
+        0xa3, b'e', b'x', b't', 0xd4, 0xfe, 0x2a,
+        0x01, 0x81, 0xa5, b'r', b'a', b't', b'i', b'o', 0xca, 0x3f, 0x00, 0x00, 0x00,
+    ];
+    assert_eq!(&expected[..], &encode(&val)[..]);
+}
+
+#[test]
+fn parse_scalars() {
+    assert_eq!(Value::Nil, parse_text("null").unwrap());
+    assert_eq!(Value::from(u64::MAX), parse_text("18446744073709551615").unwrap());
+    assert_eq!(Value::from(i64::MIN), parse_text("-9223372036854775808").unwrap());
+    assert_eq!(Value::F64(-1.5e3), parse_text("-1.5e+3").unwrap());
+    assert_eq!(Value::from("é\n\"😀"), parse_text(r#""\u00e9\n\"\ud83d\ude00""#).unwrap());
+    assert_eq!(Value::from("😀"), parse_text("\"😀\"").unwrap());
+    assert_eq!(Value::Binary(vec![]), parse_text("b\"\"").unwrap());
+    assert_eq!(Value::Array(vec![]), parse_text(" [ ] ").unwrap());
+    assert_eq!(Value::Map(vec![]), parse_text("{}").unwrap());
+}
+
+#[test]
+fn parse_errors() {
+    let err = parse_text("[1, 2").unwrap_err();
+    assert_eq!("expected ',' or a closing bracket at byte 5", err.to_string());
+
+    assert_eq!(4, parse_text("b\"abc\"").unwrap_err().offset());
+    assert_eq!(2, parse_text("b\"+f\"").unwrap_err().offset());
+    assert_eq!(4, parse_text("ext(128, b\"00\")").unwrap_err().offset());
+    assert_eq!(0, parse_text("18446744073709551616").unwrap_err().offset());
+    assert_eq!(4, parse_text("nil nil").unwrap_err().offset());
+    assert_eq!(0, parse_text("nils").unwrap_err().offset());
+    assert_eq!(2, parse_text("\"\\x\"").unwrap_err().offset());
+    assert_eq!(0, parse_text("").unwrap_err().offset());
+}
+
+#[test]
+fn parse_too_deep() {
+    let s = "[".repeat(2000);
+
+    assert_eq!("nesting too deep at byte 1024", parse_text(&s).unwrap_err().to_string());
+}
@@ -0,0 +1,81 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use rmpv::timestamp::{read_system_time, read_timestamp, write_system_time, write_timestamp, Timestamp};
+use rmpv::Value;
+
+#[test]
+fn system_time_round_trip_unix_epoch() {
+    let mut buf = Vec::new();
+    write_system_time(&mut buf, UNIX_EPOCH).unwrap();
+
+    assert_eq!(vec![0xd6, 0xff, 0x00, 0x00, 0x00, 0x00], buf);
+    assert_eq!(UNIX_EPOCH, read_system_time(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn system_time_round_trip_far_future() {
+    // Year 2500, past the 32-bit form but within the 64-bit one.
+    let time = UNIX_EPOCH + Duration::new(16_725_225_600, 123_456_789);
+
+    let mut buf = Vec::new();
+    write_system_time(&mut buf, time).unwrap();
+
+    assert_eq!(10, buf.len());
+    assert_eq!([0xd7, 0xff], buf[..2]);
+    assert_eq!(time, read_system_time(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn system_time_round_trip_before_epoch() {
+    let time = UNIX_EPOCH - Duration::new(86_400, 250_000_000);
+
+    let mut buf = Vec::new();
+    write_system_time(&mut buf, time).unwrap();
+
+    assert_eq!(15, buf.len());
+    assert_eq!([0xc7, 0x0c, 0xff], buf[..3]);
+    assert_eq!(Timestamp::new(-86_401, 750_000_000).unwrap(), read_timestamp(&mut &buf[..]).unwrap());
+    assert_eq!(time, read_system_time(&mut &buf[..]).unwrap());
+}
+
+#[test]
+fn write_timestamp_uses_shortest_form() {
+    let mut buf = Vec::new();
+    write_timestamp(&mut buf, Timestamp::new(u32::MAX.into(), 0).unwrap()).unwrap();
+    assert_eq!(6, buf.len());
+
+    buf.clear();
+    write_timestamp(&mut buf, Timestamp::new(1, 1).unwrap()).unwrap();
+    assert_eq!(vec![0xd7, 0xff, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01], buf);
+}
+
+#[test]
+fn read_timestamp_rejects_other_ext_types() {
+    let buf = [0xd6, 0x01, 0x00, 0x00, 0x00, 0x00];
+
+    assert!(read_timestamp(&mut &buf[..]).is_err());
+}
+
+#[test]
+fn read_timestamp_rejects_invalid_nanos() {
+    // The 96-bit form with 10^9 nanoseconds.
+    let buf = [0xc7, 0x0c, 0xff, 0x3b, 0x9a, 0xca, 0x00, 0, 0, 0, 0, 0, 0, 0, 0];
+
+    assert!(read_timestamp(&mut &buf[..]).is_err());
+}
+
+#[test]
+fn read_timestamp_rejects_invalid_length() {
+    let buf = [0xd5, 0xff, 0x00, 0x00];
+
+    assert!(read_timestamp(&mut &buf[..]).is_err());
+}
+
+#[test]
+fn timestamp_as_value() {
+    let ts = Timestamp::new(-1, 500_000_000).unwrap();
+    let val = Value::from(ts);
+
+    assert_eq!(Some(ts), Timestamp::from_value(&val));
+    assert_eq!(None, Timestamp::from_value(&Value::Ext(1, vec![0; 4])));
+}
@@ -191,8 +191,240 @@ fn try_from_val() {
   use rmpv::Utf8String;
   use std::convert::TryInto;
 
-  assert_eq!(false, Value::Boolean(false).try_into().unwrap());
+  assert_eq!(false, TryInto::<bool>::try_into(Value::Boolean(false)).unwrap());
   assert_eq!(Utf8String::from("spook"), Value::from("spook").try_into().unwrap());
   assert_eq!(String::from("spook"), TryInto::<String>::try_into(Value::from("spook")).unwrap());
   assert_eq!(vec![0], TryInto::<Vec<u8>>::try_into(Value::Binary(vec![0u8])).unwrap());
 }
+
+#[test]
+fn take_string_out_of_map() {
+    let mut val = Value::Map(vec![
+        (Value::from("name"), Value::from("le message")),
+        (Value::from("id"), Value::from(42)),
+    ]);
+
+    let taken = match val {
+        Value::Map(ref mut map) => map[0].1.take(),
+        _ => unreachable!(),
+    };
+
+    assert_eq!(Ok("le message".to_string()), taken.into_string());
+    assert_eq!(Value::Nil, val["name"]);
+    assert_eq!(Value::from(42), val["id"]);
+}
+
+#[test]
+fn into_map_of_non_map() {
+    let val = Value::Array(vec![Value::from(1)]);
+
+    assert_eq!(Err(Value::Array(vec![Value::from(1)])), val.into_map());
+}
+
+#[test]
+#[cfg(target_pointer_width = "64")]
+fn value_size() {
+    // A string's validation cache mustn't make every value larger.
+    assert_eq!(40, std::mem::size_of::<Value>());
+}
+
+#[test]
+fn heap_size_of_scalars() {
+    assert_eq!(0, Value::Nil.heap_size());
+    assert_eq!(0, Value::from(42).heap_size());
+    assert_eq!(0, Value::from(4.2).heap_size());
+}
+
+#[test]
+fn heap_size_of_nested_tree() {
+    let val = Value::Map(vec![
+        (Value::from("name"), Value::from("le message")),
+        (Value::from("data"), Value::Array(vec![Value::Binary(vec![0; 100]), Value::from(42)])),
+    ]);
+
+    let size = val.heap_size();
+
+    // Strings and binaries alone take 4 + 10 + 4 + 100 bytes, to which the container buffers add.
+    let payload = 118;
+    let containers = 2 * std::mem::size_of::<(Value, Value)>() + 2 * std::mem::size_of::<Value>();
+    assert!(size >= payload + containers, "{}", size);
+    assert!(size <= 2 * (payload + containers), "{}", size);
+}
+
+#[test]
+fn utf8_string_from_arc_str() {
+    let s: std::sync::Arc<str> = "le message".into();
+    let val = Value::String(s.clone().into());
+
+    assert_eq!(Value::from("le message"), val);
+    assert_eq!(Some("le message"), val.as_str());
+    assert_eq!(Ok("le message".to_string()), val.into_string());
+}
+
+#[test]
+fn cmp_encoded_sorts_mixed_keys_by_bytes() {
+    let mut keys = vec![
+        Value::from(-1),
+        Value::Binary(vec![0x00]),
+        Value::from("b"),
+        Value::from(300),
+        Value::from(""),
+        Value::from(1),
+        Value::from("ab"),
+        Value::from(-100),
+        Value::Binary(vec![]),
+        Value::from(127),
+    ];
+
+    keys.sort_by(Value::cmp_encoded);
+
+    let encoded: Vec<Vec<u8>> = keys
+        .iter()
+        .map(|key| {
+            let mut buf = Vec::new();
+            rmpv::encode::write_value(&mut buf, key).unwrap();
+            buf
+        })
+        .collect();
+    let mut sorted = encoded.clone();
+    sorted.sort();
+    assert_eq!(sorted, encoded);
+
+    assert_eq!(vec![
+        Value::from(1),              // 0x01
+        Value::from(127),            // 0x7f
+        Value::from(""),             // 0xa0
+        Value::from("b"),            // 0xa1 0x62
+        Value::from("ab"),           // 0xa2 0x61 0x62
+        Value::Binary(vec![]),       // 0xc4 0x00
+        Value::Binary(vec![0x00]),   // 0xc4 0x01 0x00
+        Value::from(300),            // 0xcd 0x01 0x2c
+        Value::from(-100),           // 0xd0 0x9c
+        Value::from(-1),             // 0xff
+    ], keys);
+}
+
+#[test]
+fn cmp_encoded_equal_values() {
+    use std::cmp::Ordering;
+
+    assert_eq!(Ordering::Equal, Value::from("a").cmp_encoded(&Value::from("a")));
+    assert_eq!(Ordering::Equal, Value::from(5u64).cmp_encoded(&Value::from(5i64)));
+    assert_ne!(Ordering::Equal, Value::from(1).cmp_encoded(&Value::F64(1.0)));
+}
+
+#[test]
+fn cmp_encoded_binary_refs() {
+    use std::cmp::Ordering;
+
+    let binary_ref = |offset| Value::BinaryRef { offset, len: 4 };
+
+    assert_eq!(Ordering::Less, Value::from(-1).cmp_encoded(&binary_ref(0)));
+    assert_eq!(Ordering::Greater, binary_ref(0).cmp_encoded(&Value::Map(vec![])));
+    assert_eq!(Ordering::Less, binary_ref(0).cmp_encoded(&binary_ref(1)));
+    assert_eq!(Ordering::Equal, binary_ref(1).cmp_encoded(&binary_ref(1)));
+
+    // 0x92 0x01 0x02 vs 0x92 0x01 and a binary reference.
+    let lhs = Value::Array(vec![Value::from(1), Value::from(2)]);
+    let rhs = Value::Array(vec![Value::from(1), binary_ref(0)]);
+    assert_eq!(Ordering::Less, lhs.cmp_encoded(&rhs));
+    // 0x91 vs 0x92, whatever the elements.
+    assert_eq!(Ordering::Less, Value::Array(vec![binary_ref(0)]).cmp_encoded(&lhs));
+    // 0x91 0x02 vs 0x91 and a binary reference, with the former given as raw bytes.
+    assert_eq!(Ordering::Less, Value::Raw(vec![0x91, 0x02]).cmp_encoded(&Value::Array(vec![binary_ref(0)])));
+    // 0x81 0x01 0x02 vs 0x81 0x01 and a binary reference.
+    let lhs = Value::Map(vec![(Value::from(1), Value::from(2))]);
+    let rhs = Value::Map(vec![(Value::from(1), binary_ref(0))]);
+    assert_eq!(Ordering::Greater, rhs.cmp_encoded(&lhs));
+}
+
+#[test]
+fn cmp_semantic_sorts_mixed_keys_by_meaning() {
+    let mut keys = vec![
+        Value::from("b"),
+        Value::from(10),
+        Value::F64(0.5),
+        Value::Nil,
+        Value::from(2),
+        Value::from("ab"),
+        Value::from(-100),
+        Value::F32(-1.5),
+        Value::from(u64::MAX),
+        Value::Boolean(false),
+    ];
+
+    keys.sort_by(Value::cmp_semantic);
+
+    assert_eq!(vec![
+        Value::Nil,
+        Value::Boolean(false),
+        Value::from(-100),
+        Value::from(2),
+        Value::from(10),
+        Value::from(u64::MAX),
+        Value::F32(-1.5),
+        Value::F64(0.5),
+        Value::from("ab"),
+        Value::from("b"),
+    ], keys);
+}
+
+#[test]
+fn cmp_semantic_nested() {
+    use std::cmp::Ordering;
+
+    let lhs = Value::Array(vec![Value::from(1), Value::from(2)]);
+    let rhs = Value::Array(vec![Value::from(1), Value::from(10)]);
+
+    assert_eq!(Ordering::Less, lhs.cmp_semantic(&rhs));
+    assert_eq!(Ordering::Less, Value::Array(vec![Value::from(1)]).cmp_semantic(&lhs));
+    assert_eq!(Ordering::Equal, Value::from(5u64).cmp_semantic(&Value::from(5i64)));
+}
+
+#[test]
+fn loose_eq_str_and_bin() {
+    let s = Value::from("le message");
+    let bin = Value::Binary(b"le message".to_vec());
+
+    assert!(s.loose_eq(&bin));
+    assert!(bin.loose_eq(&s));
+    assert_ne!(s, bin);
+
+    // Strings that aren't valid UTF-8 are compared by their bytes as well.
+    let invalid = rmpv::decode::read_value(&mut &[0xa2, 0xff, 0x00][..]).unwrap();
+    assert!(invalid.loose_eq(&Value::Binary(vec![0xff, 0x00])));
+}
+
+#[test]
+fn loose_eq_nested() {
+    let lhs = Value::Map(vec![(Value::from("data"), Value::Array(vec![Value::from("ab"), Value::from(1)]))]);
+    let rhs = Value::Map(vec![(
+        Value::Binary(b"data".to_vec()),
+        Value::Array(vec![Value::Binary(b"ab".to_vec()), Value::from(1u8)]),
+    )]);
+
+    assert!(lhs.loose_eq(&rhs));
+}
+
+#[test]
+fn loose_eq_str_and_different_bin() {
+    assert!(!Value::from("abc").loose_eq(&Value::Binary(b"abd".to_vec())));
+    assert!(!Value::from("abc").loose_eq(&Value::Binary(b"ab".to_vec())));
+    assert!(!Value::Array(vec![Value::from("a")]).loose_eq(&Value::Array(vec![Value::from("a"), Value::Nil])));
+    assert!(!Value::from(1).loose_eq(&Value::F64(1.0)));
+}
+
+#[test]
+#[should_panic(expected = "binary reference")]
+fn binary_ref_as_ref_panics() {
+    let _ = Value::Array(vec![Value::BinaryRef { offset: 0, len: 4 }]).as_ref();
+}
+
+#[test]
+fn binary_ref_try_as_ref() {
+    let val = Value::Map(vec![(Value::from("a"), Value::BinaryRef { offset: 0, len: 4 })]);
+    assert_eq!(None, val.try_as_ref());
+
+    let val = Value::Map(vec![(Value::from("a"), Value::Binary(vec![1]))]);
+    assert_eq!(Some(val.as_ref()), val.try_as_ref());
+}